@@ -0,0 +1,148 @@
+//! Monthly report aggregation: loading every row into memory versus grouped SQL.
+//!
+//! Seeds a throwaway SQLite database with `BENCH_ROWS` orders and expenses
+//! (default 50 000 each) spread over one year, then times both approaches
+//! for a single month and for the whole year.
+//!
+//! ```sh
+//! BENCH_ROWS=200000 cargo bench --bench report_aggregation
+//! ```
+
+use backend::entities::{expenses, invoices, orders, payments, users};
+use backend::money::{self, Money};
+use backend::services::reports::ReportsService;
+use chrono::{Duration, NaiveDate};
+use criterion::{criterion_group, criterion_main, Criterion};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, Database, DatabaseConnection, EntityTrait, QueryFilter, Schema, Set,
+};
+use tokio::runtime::Runtime;
+
+const LABELS: [&str; 6] = ["Supplies", "Rent", "Utilities", "Salaries", "Maintenance", "Transport"];
+const SERVICES: [&str; 5] = ["Consultation", "Laboratory", "X-Ray", "Ultrasound", "Vaccination"];
+const BATCH_SIZE: usize = 500;
+
+fn bench_rows() -> usize {
+    std::env::var("BENCH_ROWS")
+        .ok()
+        .and_then(|rows| rows.parse().ok())
+        .unwrap_or(50_000)
+}
+
+async fn seed(rows: usize) -> DatabaseConnection {
+    let path = std::env::temp_dir().join("report_aggregation_bench.db");
+    let _ = std::fs::remove_file(&path);
+
+    let db = Database::connect(format!("sqlite://{}?mode=rwc", path.display()))
+        .await
+        .expect("Failed to open benchmark database");
+
+    let backend = db.get_database_backend();
+    let schema = Schema::new(backend);
+    for statement in [
+        schema.create_table_from_entity(users::Entity),
+        schema.create_table_from_entity(orders::Entity),
+        schema.create_table_from_entity(expenses::Entity),
+        schema.create_table_from_entity(invoices::Entity),
+        schema.create_table_from_entity(payments::Entity),
+    ] {
+        db.execute(backend.build(&statement))
+            .await
+            .expect("Failed to create benchmark table");
+    }
+
+    let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+    let date_of = |i: usize| start + Duration::days((i % 365) as i64);
+
+    for batch in (0..rows).collect::<Vec<_>>().chunks(BATCH_SIZE) {
+        let order_rows = batch.iter().map(|&i| {
+            let amount = Money::from_cents(((i * 37) % 500_000 + 100) as i64);
+            orders::ActiveModel {
+                patient_name: Set(format!("Patient {}", i)),
+                order_date: Set(date_of(i)),
+                total_amount: Set(amount),
+                original_currency: Set(money::base_currency().to_string()),
+                original_amount: Set(amount),
+                exchange_rate: Set(Decimal::ONE),
+                description: Set(SERVICES[i % SERVICES.len()].to_string()),
+                branch_id: Set(1),
+                ..Default::default()
+            }
+        });
+        orders::Entity::insert_many(order_rows)
+            .exec(&db)
+            .await
+            .expect("Failed to seed orders");
+
+        let expense_rows = batch.iter().map(|&i| {
+            let amount = Money::from_cents(((i * 53) % 200_000 + 100) as i64);
+            expenses::ActiveModel {
+                description: Set(format!("Expense {}", i)),
+                label: Set(LABELS[i % LABELS.len()].to_string()),
+                amount: Set(amount),
+                original_currency: Set(money::base_currency().to_string()),
+                original_amount: Set(amount),
+                exchange_rate: Set(Decimal::ONE),
+                expense_date: Set(date_of(i * 7)),
+                branch_id: Set(1),
+                ..Default::default()
+            }
+        });
+        expenses::Entity::insert_many(expense_rows)
+            .exec(&db)
+            .await
+            .expect("Failed to seed expenses");
+    }
+
+    db
+}
+
+/// The approach reports used before aggregation moved into SQL
+async fn load_and_sum(db: &DatabaseConnection, from: NaiveDate, to: NaiveDate) -> (usize, Money, Money) {
+    let orders_list = orders::Entity::find()
+        .filter(orders::Column::OrderDate.between(from, to))
+        .all(db)
+        .await
+        .unwrap();
+    let expenses_list = expenses::Entity::find()
+        .filter(expenses::Column::ExpenseDate.between(from, to))
+        .all(db)
+        .await
+        .unwrap();
+
+    let income: Money = orders_list.iter().map(|o| o.total_amount).sum();
+    let spent: Money = expenses_list.iter().map(|e| e.amount).sum();
+    (orders_list.len(), income, spent)
+}
+
+fn report_aggregation(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to start runtime");
+    let db = runtime.block_on(seed(bench_rows()));
+    let service = ReportsService::new(db.clone());
+
+    let periods = [
+        ("month", NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 6, 30).unwrap()),
+        ("year", NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 12, 31).unwrap()),
+    ];
+
+    for (name, from, to) in periods {
+        let mut group = c.benchmark_group(format!("report_{}", name));
+        group.sample_size(20);
+
+        group.bench_function("load_and_sum_in_rust", |b| {
+            b.iter(|| runtime.block_on(load_and_sum(&db, from, to)))
+        });
+        group.bench_function("grouped_sql", |b| {
+            b.iter(|| runtime.block_on(service.summarize_period(from, to)).unwrap())
+        });
+        group.bench_function("grouped_sql_with_breakdown", |b| {
+            b.iter(|| runtime.block_on(service.breakdown_period(from, to, 5)).unwrap())
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, report_aggregation);
+criterion_main!(benches);
@@ -7,6 +7,34 @@ mod m20250928_033942_create_invoices;
 mod m20250928_033942_create_reports;
 mod m20251002_093643_create_registration_codes;
 mod m20251002_201324_create_registration_code_resets;
+mod m20261019_090000_create_report_refresh_queue;
+mod m20261019_100000_create_accounts;
+mod m20261019_100100_create_journal_entries;
+mod m20261019_100200_create_journal_lines;
+mod m20261019_100300_create_payments;
+mod m20261019_110000_create_statement_mappings;
+mod m20261019_120000_add_account_categories;
+mod m20261019_130000_add_report_currency;
+mod m20261019_140000_create_exchange_rates;
+mod m20261019_140100_add_original_currencies;
+mod m20261019_150000_create_branches;
+mod m20261019_150100_create_user_branches;
+mod m20261019_150200_add_branch_columns;
+mod m20261019_160000_create_record_edits;
+mod m20261019_170000_add_user_roles;
+mod m20261019_180000_create_sessions;
+mod m20261019_190000_add_user_emails;
+mod m20261019_190100_create_invitations;
+mod m20261019_190200_create_password_resets;
+mod m20261019_200000_create_auth_throttles;
+mod m20261019_200100_create_lockout_events;
+mod m20261019_210000_add_user_totp;
+mod m20261019_210100_create_recovery_codes;
+mod m20261019_210200_create_role_policies;
+mod m20261019_220000_add_user_display_names;
+mod m20261019_230000_add_user_status;
+mod m20261019_240000_create_api_keys;
+mod m20261019_250000_add_registration_code_reset_attempts;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -20,6 +48,34 @@ impl MigratorTrait for Migrator {
             Box::new(m20250928_033942_create_reports::Migration),
             Box::new(m20251002_093643_create_registration_codes::Migration),
             Box::new(m20251002_201324_create_registration_code_resets::Migration),
+            Box::new(m20261019_090000_create_report_refresh_queue::Migration),
+            Box::new(m20261019_100000_create_accounts::Migration),
+            Box::new(m20261019_100100_create_journal_entries::Migration),
+            Box::new(m20261019_100200_create_journal_lines::Migration),
+            Box::new(m20261019_100300_create_payments::Migration),
+            Box::new(m20261019_110000_create_statement_mappings::Migration),
+            Box::new(m20261019_120000_add_account_categories::Migration),
+            Box::new(m20261019_130000_add_report_currency::Migration),
+            Box::new(m20261019_140000_create_exchange_rates::Migration),
+            Box::new(m20261019_140100_add_original_currencies::Migration),
+            Box::new(m20261019_150000_create_branches::Migration),
+            Box::new(m20261019_150100_create_user_branches::Migration),
+            Box::new(m20261019_150200_add_branch_columns::Migration),
+            Box::new(m20261019_160000_create_record_edits::Migration),
+            Box::new(m20261019_170000_add_user_roles::Migration),
+            Box::new(m20261019_180000_create_sessions::Migration),
+            Box::new(m20261019_190000_add_user_emails::Migration),
+            Box::new(m20261019_190100_create_invitations::Migration),
+            Box::new(m20261019_190200_create_password_resets::Migration),
+            Box::new(m20261019_200000_create_auth_throttles::Migration),
+            Box::new(m20261019_200100_create_lockout_events::Migration),
+            Box::new(m20261019_210000_add_user_totp::Migration),
+            Box::new(m20261019_210100_create_recovery_codes::Migration),
+            Box::new(m20261019_210200_create_role_policies::Migration),
+            Box::new(m20261019_220000_add_user_display_names::Migration),
+            Box::new(m20261019_230000_add_user_status::Migration),
+            Box::new(m20261019_240000_create_api_keys::Migration),
+            Box::new(m20261019_250000_add_registration_code_reset_attempts::Migration),
         ]
     }
 }
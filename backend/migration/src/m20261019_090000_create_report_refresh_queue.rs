@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReportRefreshQueue::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ReportRefreshQueue::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ReportRefreshQueue::Month)
+                            .date()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(ReportRefreshQueue::DirtySince).date_time().not_null())
+                    .col(ColumnDef::new(ReportRefreshQueue::LastMarkedAt).date_time().not_null())
+                    .col(
+                        ColumnDef::new(ReportRefreshQueue::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(ReportRefreshQueue::NextAttemptAt).date_time().null())
+                    .col(ColumnDef::new(ReportRefreshQueue::LastError).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ReportRefreshQueue::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ReportRefreshQueue {
+    Table,
+    Id,
+    Month,
+    DirtySince,
+    LastMarkedAt,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+}
@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Accounts the backend posts to automatically
+const DEFAULT_ACCOUNTS: [(&str, &str, &str); 5] = [
+    ("1000", "Cash", "asset"),
+    ("1100", "Accounts Receivable", "asset"),
+    ("3000", "Owner's Equity", "equity"),
+    ("4000", "Service Revenue", "revenue"),
+    ("5000", "Operating Expenses", "expense"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Accounts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Accounts::AccountId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Accounts::Code).string().not_null().unique_key())
+                    .col(ColumnDef::new(Accounts::Name).string().not_null())
+                    .col(ColumnDef::new(Accounts::AccountType).string().not_null())
+                    .col(
+                        ColumnDef::new(Accounts::IsSystem)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut insert = Query::insert()
+            .into_table(Accounts::Table)
+            .columns([Accounts::Code, Accounts::Name, Accounts::AccountType, Accounts::IsSystem])
+            .to_owned();
+        for (code, name, account_type) in DEFAULT_ACCOUNTS {
+            insert.values_panic([code.into(), name.into(), account_type.into(), true.into()]);
+        }
+
+        manager.exec_stmt(insert).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Accounts::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+enum Accounts {
+    Table,
+    AccountId,
+    Code,
+    Name,
+    AccountType,
+    IsSystem,
+}
@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(JournalEntries::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(JournalEntries::EntryId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(JournalEntries::EntryDate).date().not_null())
+                    .col(ColumnDef::new(JournalEntries::Description).string().not_null())
+                    .col(ColumnDef::new(JournalEntries::SourceType).string().null())
+                    .col(ColumnDef::new(JournalEntries::SourceId).integer().null())
+                    .col(ColumnDef::new(JournalEntries::CreatedBy).integer().null())
+                    .col(
+                        ColumnDef::new(JournalEntries::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-journal_entries-created_by")
+                            .from(JournalEntries::Table, JournalEntries::CreatedBy)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-journal_entries-source")
+                    .table(JournalEntries::Table)
+                    .col(JournalEntries::SourceType)
+                    .col(JournalEntries::SourceId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(JournalEntries::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum JournalEntries {
+    Table,
+    EntryId,
+    EntryDate,
+    Description,
+    SourceType,
+    SourceId,
+    CreatedBy,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+}
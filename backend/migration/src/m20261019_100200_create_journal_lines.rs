@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(JournalLines::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(JournalLines::LineId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(JournalLines::EntryId).integer().not_null())
+                    .col(ColumnDef::new(JournalLines::AccountId).integer().not_null())
+                    .col(ColumnDef::new(JournalLines::Debit).decimal_len(12, 2).not_null())
+                    .col(ColumnDef::new(JournalLines::Credit).decimal_len(12, 2).not_null())
+                    .col(ColumnDef::new(JournalLines::Memo).string().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-journal_lines-entry_id")
+                            .from(JournalLines::Table, JournalLines::EntryId)
+                            .to(JournalEntries::Table, JournalEntries::EntryId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-journal_lines-account_id")
+                            .from(JournalLines::Table, JournalLines::AccountId)
+                            .to(Accounts::Table, Accounts::AccountId)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(JournalLines::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum JournalLines {
+    Table,
+    LineId,
+    EntryId,
+    AccountId,
+    Debit,
+    Credit,
+    Memo,
+}
+
+#[derive(Iden)]
+enum JournalEntries {
+    Table,
+    EntryId,
+}
+
+#[derive(Iden)]
+enum Accounts {
+    Table,
+    AccountId,
+}
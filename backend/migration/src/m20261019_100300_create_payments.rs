@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Payments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Payments::PaymentId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Payments::InvoiceId).integer().not_null())
+                    .col(ColumnDef::new(Payments::Amount).decimal_len(12, 2).not_null())
+                    .col(ColumnDef::new(Payments::PaymentDate).date().not_null())
+                    .col(ColumnDef::new(Payments::Method).string().not_null())
+                    .col(ColumnDef::new(Payments::CreatedBy).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-payments-invoice_id")
+                            .from(Payments::Table, Payments::InvoiceId)
+                            .to(Invoices::Table, Invoices::InvoiceId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-payments-created_by")
+                            .from(Payments::Table, Payments::CreatedBy)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Payments::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+enum Payments {
+    Table,
+    PaymentId,
+    InvoiceId,
+    Amount,
+    PaymentDate,
+    Method,
+    CreatedBy,
+}
+
+#[derive(Iden)]
+enum Invoices {
+    Table,
+    InvoiceId,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StatementMappings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(StatementMappings::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(StatementMappings::Label).string().not_null().unique_key())
+                    .col(ColumnDef::new(StatementMappings::Section).string().not_null())
+                    .col(ColumnDef::new(StatementMappings::Line).string().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StatementMappings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum StatementMappings {
+    Table,
+    Id,
+    Label,
+    Section,
+    Line,
+}
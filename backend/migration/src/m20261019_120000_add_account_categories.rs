@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Balance-sheet accounts added alongside the categories
+const NEW_ACCOUNTS: [(&str, &str, &str, Option<&str>); 4] = [
+    ("1010", "Bank", "asset", Some("bank")),
+    ("1500", "Fixed Assets", "asset", Some("fixed_asset")),
+    ("2000", "Accounts Payable", "liability", Some("payable")),
+    ("3900", "Opening Balance Equity", "equity", None),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Accounts::Table)
+                    .add_column(ColumnDef::new(Accounts::Category).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        for (code, category) in [("1000", "cash"), ("1100", "receivable")] {
+            manager
+                .exec_stmt(
+                    Query::update()
+                        .table(Accounts::Table)
+                        .value(Accounts::Category, category)
+                        .and_where(Expr::col(Accounts::Code).eq(code))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        let mut insert = Query::insert()
+            .into_table(Accounts::Table)
+            .columns([
+                Accounts::Code,
+                Accounts::Name,
+                Accounts::AccountType,
+                Accounts::Category,
+                Accounts::IsSystem,
+            ])
+            .to_owned();
+        for (code, name, account_type, category) in NEW_ACCOUNTS {
+            insert.values_panic([
+                code.into(),
+                name.into(),
+                account_type.into(),
+                category.into(),
+                true.into(),
+            ]);
+        }
+
+        manager.exec_stmt(insert).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Accounts::Table)
+                    .and_where(Expr::col(Accounts::Code).is_in(NEW_ACCOUNTS.map(|(code, ..)| code)))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Accounts::Table)
+                    .drop_column(Accounts::Category)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Accounts {
+    Table,
+    Code,
+    Name,
+    AccountType,
+    Category,
+    IsSystem,
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExchangeRates::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ExchangeRates::RateId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ExchangeRates::Currency).string_len(3).not_null())
+                    .col(ColumnDef::new(ExchangeRates::RateDate).date().not_null())
+                    .col(ColumnDef::new(ExchangeRates::Rate).decimal_len(16, 6).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-exchange_rates-currency-date")
+                    .table(ExchangeRates::Table)
+                    .col(ExchangeRates::Currency)
+                    .col(ExchangeRates::RateDate)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ExchangeRates::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ExchangeRates {
+    Table,
+    RateId,
+    Currency,
+    RateDate,
+    Rate,
+}
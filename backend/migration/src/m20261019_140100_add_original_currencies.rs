@@ -0,0 +1,147 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const FX_ACCOUNT: &str = "4900";
+
+fn currency_column<T: IntoIden>(column: T) -> ColumnDef {
+    ColumnDef::new(column).string_len(3).not_null().default("PHP").to_owned()
+}
+
+fn rate_column<T: IntoIden>(column: T) -> ColumnDef {
+    ColumnDef::new(column).decimal_len(16, 6).not_null().default(1).to_owned()
+}
+
+fn amount_column<T: IntoIden>(column: T) -> ColumnDef {
+    ColumnDef::new(column).decimal_len(12, 2).not_null().default(0).to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only adds one column per ALTER TABLE
+        let columns = [
+            Table::alter().table(Orders::Table).add_column(currency_column(Orders::OriginalCurrency)).to_owned(),
+            Table::alter().table(Orders::Table).add_column(amount_column(Orders::OriginalAmount)).to_owned(),
+            Table::alter().table(Orders::Table).add_column(rate_column(Orders::ExchangeRate)).to_owned(),
+            Table::alter().table(Expenses::Table).add_column(currency_column(Expenses::OriginalCurrency)).to_owned(),
+            Table::alter().table(Expenses::Table).add_column(amount_column(Expenses::OriginalAmount)).to_owned(),
+            Table::alter().table(Expenses::Table).add_column(rate_column(Expenses::ExchangeRate)).to_owned(),
+            Table::alter().table(Payments::Table).add_column(currency_column(Payments::OriginalCurrency)).to_owned(),
+            Table::alter().table(Payments::Table).add_column(amount_column(Payments::OriginalAmount)).to_owned(),
+            Table::alter().table(Payments::Table).add_column(rate_column(Payments::ExchangeRate)).to_owned(),
+            Table::alter().table(Payments::Table).add_column(amount_column(Payments::FxGainLoss)).to_owned(),
+            Table::alter().table(Reports::Table).add_column(amount_column(Reports::FxGainLoss)).to_owned(),
+        ];
+        for statement in columns {
+            manager.alter_table(statement).await?;
+        }
+
+        // Everything recorded so far was entered in the base currency
+        let backfills = [
+            Query::update()
+                .table(Orders::Table)
+                .value(Orders::OriginalAmount, Expr::col(Orders::TotalAmount))
+                .to_owned(),
+            Query::update()
+                .table(Expenses::Table)
+                .value(Expenses::OriginalAmount, Expr::col(Expenses::Amount))
+                .to_owned(),
+            Query::update()
+                .table(Payments::Table)
+                .value(Payments::OriginalAmount, Expr::col(Payments::Amount))
+                .to_owned(),
+        ];
+        for statement in backfills {
+            manager.exec_stmt(statement).await?;
+        }
+
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(Accounts::Table)
+                    .columns([Accounts::Code, Accounts::Name, Accounts::AccountType, Accounts::IsSystem])
+                    .values_panic([
+                        FX_ACCOUNT.into(),
+                        "Foreign Exchange Gain/Loss".into(),
+                        "revenue".into(),
+                        true.into(),
+                    ])
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Accounts::Table)
+                    .and_where(Expr::col(Accounts::Code).eq(FX_ACCOUNT))
+                    .to_owned(),
+            )
+            .await?;
+
+        let columns = [
+            Table::alter().table(Reports::Table).drop_column(Reports::FxGainLoss).to_owned(),
+            Table::alter().table(Payments::Table).drop_column(Payments::FxGainLoss).to_owned(),
+            Table::alter().table(Payments::Table).drop_column(Payments::ExchangeRate).to_owned(),
+            Table::alter().table(Payments::Table).drop_column(Payments::OriginalAmount).to_owned(),
+            Table::alter().table(Payments::Table).drop_column(Payments::OriginalCurrency).to_owned(),
+            Table::alter().table(Expenses::Table).drop_column(Expenses::ExchangeRate).to_owned(),
+            Table::alter().table(Expenses::Table).drop_column(Expenses::OriginalAmount).to_owned(),
+            Table::alter().table(Expenses::Table).drop_column(Expenses::OriginalCurrency).to_owned(),
+            Table::alter().table(Orders::Table).drop_column(Orders::ExchangeRate).to_owned(),
+            Table::alter().table(Orders::Table).drop_column(Orders::OriginalAmount).to_owned(),
+            Table::alter().table(Orders::Table).drop_column(Orders::OriginalCurrency).to_owned(),
+        ];
+        for statement in columns {
+            manager.alter_table(statement).await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Orders {
+    Table,
+    TotalAmount,
+    OriginalCurrency,
+    OriginalAmount,
+    ExchangeRate,
+}
+
+#[derive(Iden)]
+enum Expenses {
+    Table,
+    Amount,
+    OriginalCurrency,
+    OriginalAmount,
+    ExchangeRate,
+}
+
+#[derive(Iden)]
+enum Payments {
+    Table,
+    Amount,
+    OriginalCurrency,
+    OriginalAmount,
+    ExchangeRate,
+    FxGainLoss,
+}
+
+#[derive(Iden)]
+enum Reports {
+    Table,
+    FxGainLoss,
+}
+
+#[derive(Iden)]
+enum Accounts {
+    Table,
+    Code,
+    Name,
+    AccountType,
+    IsSystem,
+}
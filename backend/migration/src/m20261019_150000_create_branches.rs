@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Branch every existing record belongs to
+pub const MAIN_BRANCH: (&str, &str) = ("MAIN", "Main clinic");
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Branches::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Branches::BranchId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Branches::Code).string().not_null().unique_key())
+                    .col(ColumnDef::new(Branches::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(Branches::IsActive)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(Branches::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(Branches::Table)
+                    .columns([Branches::Code, Branches::Name])
+                    .values_panic([MAIN_BRANCH.0.into(), MAIN_BRANCH.1.into()])
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Branches::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+enum Branches {
+    Table,
+    BranchId,
+    Code,
+    Name,
+    IsActive,
+    CreatedAt,
+}
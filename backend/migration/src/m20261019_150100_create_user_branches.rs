@@ -0,0 +1,108 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20261019_150000_create_branches::MAIN_BRANCH;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserBranches::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserBranches::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserBranches::UserId).integer().not_null())
+                    .col(ColumnDef::new(UserBranches::BranchId).integer().not_null())
+                    .col(
+                        ColumnDef::new(UserBranches::IsDefault)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_branches-user_id")
+                            .from(UserBranches::Table, UserBranches::UserId)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_branches-branch_id")
+                            .from(UserBranches::Table, UserBranches::BranchId)
+                            .to(Branches::Table, Branches::BranchId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-user_branches-user-branch")
+                    .table(UserBranches::Table)
+                    .col(UserBranches::UserId)
+                    .col(UserBranches::BranchId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing users work at the main branch
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(UserBranches::Table)
+                    .columns([UserBranches::UserId, UserBranches::BranchId, UserBranches::IsDefault])
+                    .select_from(
+                        Query::select()
+                            .column((Users::Table, Users::UserId))
+                            .column((Branches::Table, Branches::BranchId))
+                            .expr(Expr::val(true))
+                            .from(Users::Table)
+                            .from(Branches::Table)
+                            .and_where(Expr::col((Branches::Table, Branches::Code)).eq(MAIN_BRANCH.0))
+                            .to_owned(),
+                    )
+                    .map_err(|e| DbErr::Migration(e.to_string()))?
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(UserBranches::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+enum UserBranches {
+    Table,
+    Id,
+    UserId,
+    BranchId,
+    IsDefault,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+}
+
+#[derive(Iden)]
+enum Branches {
+    Table,
+    BranchId,
+    Code,
+}
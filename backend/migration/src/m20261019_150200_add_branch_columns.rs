@@ -0,0 +1,122 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20261019_150000_create_branches::MAIN_BRANCH;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let main_branch = db
+            .query_one(
+                manager.get_database_backend().build(
+                    Query::select()
+                        .column(Branches::BranchId)
+                        .from(Branches::Table)
+                        .and_where(Expr::col(Branches::Code).eq(MAIN_BRANCH.0)),
+                ),
+            )
+            .await?
+            .ok_or_else(|| DbErr::Migration("The main branch is missing".into()))?;
+        let main_branch_id: i32 = main_branch.try_get("", "branch_id")?;
+
+        // Existing orders, expenses and invoices default to the main branch;
+        // a report without a branch is the consolidated one
+        let columns = [
+            Table::alter()
+                .table(Orders::Table)
+                .add_column(ColumnDef::new(Orders::BranchId).integer().not_null().default(main_branch_id))
+                .to_owned(),
+            Table::alter()
+                .table(Orders::Table)
+                .add_column(ColumnDef::new(Orders::CounterpartyBranchId).integer().null())
+                .to_owned(),
+            Table::alter()
+                .table(Expenses::Table)
+                .add_column(ColumnDef::new(Expenses::BranchId).integer().not_null().default(main_branch_id))
+                .to_owned(),
+            Table::alter()
+                .table(Invoices::Table)
+                .add_column(ColumnDef::new(Invoices::BranchId).integer().not_null().default(main_branch_id))
+                .to_owned(),
+            Table::alter()
+                .table(Reports::Table)
+                .add_column(ColumnDef::new(Reports::BranchId).integer().null())
+                .to_owned(),
+        ];
+        for statement in columns {
+            manager.alter_table(statement).await?;
+        }
+
+        let indexes = [
+            Index::create()
+                .name("idx-orders-branch_id")
+                .table(Orders::Table)
+                .col(Orders::BranchId)
+                .to_owned(),
+            Index::create()
+                .name("idx-expenses-branch_id")
+                .table(Expenses::Table)
+                .col(Expenses::BranchId)
+                .to_owned(),
+        ];
+        for statement in indexes {
+            manager.create_index(statement).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, table) in [("idx-orders-branch_id", "orders"), ("idx-expenses-branch_id", "expenses")] {
+            manager
+                .drop_index(Index::drop().name(name).table(Alias::new(table)).to_owned())
+                .await?;
+        }
+
+        let columns = [
+            Table::alter().table(Reports::Table).drop_column(Reports::BranchId).to_owned(),
+            Table::alter().table(Invoices::Table).drop_column(Invoices::BranchId).to_owned(),
+            Table::alter().table(Expenses::Table).drop_column(Expenses::BranchId).to_owned(),
+            Table::alter().table(Orders::Table).drop_column(Orders::CounterpartyBranchId).to_owned(),
+            Table::alter().table(Orders::Table).drop_column(Orders::BranchId).to_owned(),
+        ];
+        for statement in columns {
+            manager.alter_table(statement).await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Branches {
+    Table,
+    BranchId,
+    Code,
+}
+
+#[derive(Iden)]
+enum Orders {
+    Table,
+    BranchId,
+    CounterpartyBranchId,
+}
+
+#[derive(Iden)]
+enum Expenses {
+    Table,
+    BranchId,
+}
+
+#[derive(Iden)]
+enum Invoices {
+    Table,
+    BranchId,
+}
+
+#[derive(Iden)]
+enum Reports {
+    Table,
+    BranchId,
+}
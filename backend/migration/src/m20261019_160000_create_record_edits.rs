@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecordEdits::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RecordEdits::EditId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RecordEdits::RecordType).string_len(16).not_null())
+                    .col(ColumnDef::new(RecordEdits::RecordId).integer().not_null())
+                    .col(ColumnDef::new(RecordEdits::Action).string_len(16).not_null())
+                    .col(ColumnDef::new(RecordEdits::UserId).integer().null())
+                    .col(ColumnDef::new(RecordEdits::BranchId).integer().not_null())
+                    .col(
+                        ColumnDef::new(RecordEdits::EditedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-record_edits-user_id")
+                            .from(RecordEdits::Table, RecordEdits::UserId)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-record_edits-edited_at")
+                    .table(RecordEdits::Table)
+                    .col(RecordEdits::EditedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecordEdits::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum RecordEdits {
+    Table,
+    EditId,
+    RecordType,
+    RecordId,
+    Action,
+    UserId,
+    BranchId,
+    EditedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+}
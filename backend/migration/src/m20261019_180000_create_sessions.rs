@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Sessions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Sessions::SessionId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Sessions::UserId).integer().not_null())
+                    .col(ColumnDef::new(Sessions::RefreshTokenHash).string_len(64).not_null().unique_key())
+                    .col(ColumnDef::new(Sessions::PreviousTokenHash).string_len(64).null())
+                    .col(ColumnDef::new(Sessions::UserAgent).string().null())
+                    .col(ColumnDef::new(Sessions::IpAddress).string_len(45).null())
+                    .col(
+                        ColumnDef::new(Sessions::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Sessions::LastUsedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Sessions::ExpiresAt).date_time().not_null())
+                    .col(ColumnDef::new(Sessions::RevokedAt).date_time().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-sessions-user_id")
+                            .from(Sessions::Table, Sessions::UserId)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-sessions-user_id")
+                    .table(Sessions::Table)
+                    .col(Sessions::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Sessions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Sessions {
+    Table,
+    SessionId,
+    UserId,
+    RefreshTokenHash,
+    PreviousTokenHash,
+    UserAgent,
+    IpAddress,
+    CreatedAt,
+    LastUsedAt,
+    ExpiresAt,
+    RevokedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Invitations::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Invitations::InvitationId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Invitations::Email).string().not_null())
+                    .col(ColumnDef::new(Invitations::Role).string_len(16).not_null())
+                    .col(ColumnDef::new(Invitations::CodeHash).string_len(64).not_null().unique_key())
+                    .col(ColumnDef::new(Invitations::InvitedBy).integer().null())
+                    .col(
+                        ColumnDef::new(Invitations::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Invitations::ExpiresAt).date_time().not_null())
+                    .col(ColumnDef::new(Invitations::AcceptedAt).date_time().null())
+                    .col(ColumnDef::new(Invitations::AcceptedBy).integer().null())
+                    .col(ColumnDef::new(Invitations::RevokedAt).date_time().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-invitations-invited_by")
+                            .from(Invitations::Table, Invitations::InvitedBy)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-invitations-accepted_by")
+                            .from(Invitations::Table, Invitations::AcceptedBy)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-invitations-email")
+                    .table(Invitations::Table)
+                    .col(Invitations::Email)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Invitations::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Invitations {
+    Table,
+    InvitationId,
+    Email,
+    Role,
+    CodeHash,
+    InvitedBy,
+    CreatedAt,
+    ExpiresAt,
+    AcceptedAt,
+    AcceptedBy,
+    RevokedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+}
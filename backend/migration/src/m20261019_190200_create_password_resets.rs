@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PasswordResets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PasswordResets::ResetId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PasswordResets::UserId).integer().not_null())
+                    .col(ColumnDef::new(PasswordResets::TokenHash).string_len(64).not_null().unique_key())
+                    .col(
+                        ColumnDef::new(PasswordResets::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(PasswordResets::ExpiresAt).date_time().not_null())
+                    .col(ColumnDef::new(PasswordResets::UsedAt).date_time().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-password_resets-user_id")
+                            .from(PasswordResets::Table, PasswordResets::UserId)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PasswordResets::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum PasswordResets {
+    Table,
+    ResetId,
+    UserId,
+    TokenHash,
+    CreatedAt,
+    ExpiresAt,
+    UsedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+}
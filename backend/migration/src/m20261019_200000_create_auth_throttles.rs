@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuthThrottles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuthThrottles::ThrottleId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuthThrottles::Action).string_len(32).not_null())
+                    .col(ColumnDef::new(AuthThrottles::Scope).string_len(16).not_null())
+                    .col(ColumnDef::new(AuthThrottles::Subject).string().not_null())
+                    .col(ColumnDef::new(AuthThrottles::Failures).integer().not_null().default(0))
+                    .col(ColumnDef::new(AuthThrottles::LastFailureAt).date_time().not_null())
+                    .col(ColumnDef::new(AuthThrottles::LockedUntil).date_time().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-auth_throttles-action-scope-subject")
+                    .table(AuthThrottles::Table)
+                    .col(AuthThrottles::Action)
+                    .col(AuthThrottles::Scope)
+                    .col(AuthThrottles::Subject)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuthThrottles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum AuthThrottles {
+    Table,
+    ThrottleId,
+    Action,
+    Scope,
+    Subject,
+    Failures,
+    LastFailureAt,
+    LockedUntil,
+}
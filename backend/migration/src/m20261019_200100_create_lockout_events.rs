@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LockoutEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LockoutEvents::EventId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LockoutEvents::Action).string_len(32).not_null())
+                    .col(ColumnDef::new(LockoutEvents::Scope).string_len(16).not_null())
+                    .col(ColumnDef::new(LockoutEvents::Subject).string().not_null())
+                    .col(ColumnDef::new(LockoutEvents::UserId).integer().null())
+                    .col(ColumnDef::new(LockoutEvents::IpAddress).string_len(45).null())
+                    .col(ColumnDef::new(LockoutEvents::Failures).integer().not_null())
+                    .col(
+                        ColumnDef::new(LockoutEvents::LockedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(LockoutEvents::LockedUntil).date_time().not_null())
+                    .col(ColumnDef::new(LockoutEvents::UnlockedAt).date_time().null())
+                    .col(ColumnDef::new(LockoutEvents::UnlockedBy).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-lockout_events-user_id")
+                            .from(LockoutEvents::Table, LockoutEvents::UserId)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-lockout_events-unlocked_by")
+                            .from(LockoutEvents::Table, LockoutEvents::UnlockedBy)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-lockout_events-locked_at")
+                    .table(LockoutEvents::Table)
+                    .col(LockoutEvents::LockedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LockoutEvents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum LockoutEvents {
+    Table,
+    EventId,
+    Action,
+    Scope,
+    Subject,
+    UserId,
+    IpAddress,
+    Failures,
+    LockedAt,
+    LockedUntil,
+    UnlockedAt,
+    UnlockedBy,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+}
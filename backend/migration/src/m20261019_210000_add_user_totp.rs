@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A secret without a confirmation date is an enrollment in progress
+        let columns = [
+            Table::alter()
+                .table(Users::Table)
+                .add_column(ColumnDef::new(Users::TotpSecret).string_len(64).null())
+                .to_owned(),
+            Table::alter()
+                .table(Users::Table)
+                .add_column(ColumnDef::new(Users::TotpConfirmedAt).date_time().null())
+                .to_owned(),
+            Table::alter()
+                .table(Users::Table)
+                .add_column(ColumnDef::new(Users::TotpLastStep).big_integer().null())
+                .to_owned(),
+        ];
+        for statement in columns {
+            manager.alter_table(statement).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            Table::alter().table(Users::Table).drop_column(Users::TotpLastStep).to_owned(),
+            Table::alter().table(Users::Table).drop_column(Users::TotpConfirmedAt).to_owned(),
+            Table::alter().table(Users::Table).drop_column(Users::TotpSecret).to_owned(),
+        ];
+        for statement in columns {
+            manager.alter_table(statement).await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    TotpSecret,
+    TotpConfirmedAt,
+    TotpLastStep,
+}
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecoveryCodes::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RecoveryCodes::CodeId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RecoveryCodes::UserId).integer().not_null())
+                    .col(ColumnDef::new(RecoveryCodes::CodeHash).string_len(64).not_null())
+                    .col(
+                        ColumnDef::new(RecoveryCodes::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(RecoveryCodes::UsedAt).date_time().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-recovery_codes-user_id")
+                            .from(RecoveryCodes::Table, RecoveryCodes::UserId)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-recovery_codes-user_id")
+                    .table(RecoveryCodes::Table)
+                    .col(RecoveryCodes::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecoveryCodes::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum RecoveryCodes {
+    Table,
+    CodeId,
+    UserId,
+    CodeHash,
+    CreatedAt,
+    UsedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+}
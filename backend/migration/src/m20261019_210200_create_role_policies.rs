@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A role without a row has the defaults
+        manager
+            .create_table(
+                Table::create()
+                    .table(RolePolicies::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RolePolicies::Role)
+                            .string_len(16)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(RolePolicies::RequireTwoFactor)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(RolePolicies::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RolePolicies::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum RolePolicies {
+    Table,
+    Role,
+    RequireTwoFactor,
+    UpdatedAt,
+}
@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Users are never deleted outright: orders and expenses keep pointing at them
+        let columns = [
+            Table::alter()
+                .table(Users::Table)
+                .add_column(ColumnDef::new(Users::LastLoginAt).date_time().null())
+                .to_owned(),
+            Table::alter()
+                .table(Users::Table)
+                .add_column(ColumnDef::new(Users::DeactivatedAt).date_time().null())
+                .to_owned(),
+            Table::alter()
+                .table(Users::Table)
+                .add_column(ColumnDef::new(Users::DeletedAt).date_time().null())
+                .to_owned(),
+        ];
+        for statement in columns {
+            manager.alter_table(statement).await?;
+        }
+
+        // The latest session tells when existing users last signed in
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Users::Table)
+                    .value(
+                        Users::LastLoginAt,
+                        SimpleExpr::SubQuery(
+                            None,
+                            Box::new(
+                                Query::select()
+                                    .expr(Expr::col((Sessions::Table, Sessions::CreatedAt)).max())
+                                    .from(Sessions::Table)
+                                    .and_where(
+                                        Expr::col((Sessions::Table, Sessions::UserId))
+                                            .equals((Users::Table, Users::UserId)),
+                                    )
+                                    .to_owned()
+                                    .into_sub_query_statement(),
+                            ),
+                        ),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            Table::alter().table(Users::Table).drop_column(Users::DeletedAt).to_owned(),
+            Table::alter().table(Users::Table).drop_column(Users::DeactivatedAt).to_owned(),
+            Table::alter().table(Users::Table).drop_column(Users::LastLoginAt).to_owned(),
+        ];
+        for statement in columns {
+            manager.alter_table(statement).await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+    LastLoginAt,
+    DeactivatedAt,
+    DeletedAt,
+}
+
+#[derive(Iden)]
+enum Sessions {
+    Table,
+    UserId,
+    CreatedAt,
+}
@@ -0,0 +1,83 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Every key acts as its own service account user, so whatever it records names the key
+        manager
+            .create_table(
+                Table::create()
+                    .table(ApiKeys::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ApiKeys::ApiKeyId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ApiKeys::Name).string_len(100).not_null().unique_key())
+                    .col(ColumnDef::new(ApiKeys::KeyPrefix).string_len(16).not_null())
+                    .col(ColumnDef::new(ApiKeys::KeyHash).string_len(64).not_null().unique_key())
+                    .col(ColumnDef::new(ApiKeys::Permissions).json_binary().not_null())
+                    .col(ColumnDef::new(ApiKeys::UserId).integer().not_null().unique_key())
+                    .col(ColumnDef::new(ApiKeys::CreatedBy).integer().null())
+                    .col(
+                        ColumnDef::new(ApiKeys::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ApiKeys::ExpiresAt).date_time().null())
+                    .col(ColumnDef::new(ApiKeys::LastUsedAt).date_time().null())
+                    .col(ColumnDef::new(ApiKeys::RevokedAt).date_time().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-api_keys-user_id")
+                            .from(ApiKeys::Table, ApiKeys::UserId)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-api_keys-created_by")
+                            .from(ApiKeys::Table, ApiKeys::CreatedBy)
+                            .to(Users::Table, Users::UserId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ApiKeys::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ApiKeys {
+    Table,
+    ApiKeyId,
+    Name,
+    KeyPrefix,
+    KeyHash,
+    Permissions,
+    UserId,
+    CreatedBy,
+    CreatedAt,
+    ExpiresAt,
+    LastUsedAt,
+    RevokedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    UserId,
+}
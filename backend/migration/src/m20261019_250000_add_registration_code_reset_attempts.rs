@@ -0,0 +1,152 @@
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, SaltString};
+use argon2::Argon2;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{DbBackend, TransactionTrait};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite runs migrations outside a transaction, so this one brings its own: a
+        // failure part way must not leave the table half altered
+        let txn = manager.get_connection().begin().await?;
+        let schema = SchemaManager::new(&txn);
+
+        // Wrong guesses at a verification code are counted on the code itself. SQLite can't
+        // add a column defaulting to CURRENT_TIMESTAMP to a table with rows, so `created_at`
+        // starts with a constant and existing codes are stamped afterwards.
+        let columns = [
+            Table::alter()
+                .table(RegistrationCodeResets::Table)
+                .add_column(
+                    ColumnDef::new(RegistrationCodeResets::Attempts)
+                        .integer()
+                        .not_null()
+                        .default(0),
+                )
+                .to_owned(),
+            Table::alter()
+                .table(RegistrationCodeResets::Table)
+                .add_column(
+                    ColumnDef::new(RegistrationCodeResets::CreatedAt)
+                        .date_time()
+                        .not_null()
+                        .default("1970-01-01 00:00:00"),
+                )
+                .to_owned(),
+        ];
+        for statement in columns {
+            schema.alter_table(statement).await?;
+        }
+
+        // Codes were stored in plaintext and never expired; none of them can be trusted
+        schema
+            .exec_stmt(
+                Query::update()
+                    .table(RegistrationCodeResets::Table)
+                    .value(RegistrationCodeResets::Used, true)
+                    .value(RegistrationCodeResets::CreatedAt, Expr::current_timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        // New codes are stamped by the application; PostgreSQL can also default to now,
+        // while SQLite can't change a column's default after the fact
+        if schema.get_database_backend() == DbBackend::Postgres {
+            txn.execute_unprepared(
+                "ALTER TABLE registration_code_resets ALTER COLUMN created_at SET DEFAULT CURRENT_TIMESTAMP",
+            )
+            .await?;
+        }
+
+        // Registration codes set before they were hashed are hashed once here, so
+        // checking a code never has to compare plaintext
+        let codes = txn
+            .query_all(
+                schema.get_database_backend().build(
+                    Query::select()
+                        .columns([RegistrationCodes::Id, RegistrationCodes::CodeHash])
+                        .from(RegistrationCodes::Table),
+                ),
+            )
+            .await?;
+        for row in codes {
+            let id: i32 = row.try_get("", "id")?;
+            let code: String = row.try_get("", "code_hash")?;
+            if PasswordHash::new(&code).is_ok() {
+                continue;
+            }
+
+            let salt = SaltString::generate(&mut OsRng);
+            let code_hash = Argon2::default()
+                .hash_password(code.as_bytes(), &salt)
+                .map_err(|e| DbErr::Migration(format!("Failed to hash the registration code: {}", e)))?
+                .to_string();
+            schema
+                .exec_stmt(
+                    Query::update()
+                        .table(RegistrationCodes::Table)
+                        .value(RegistrationCodes::CodeHash, code_hash)
+                        .and_where(Expr::col(RegistrationCodes::Id).eq(id))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        schema
+            .create_index(
+                Index::create()
+                    .name("idx-registration_code_resets-email")
+                    .table(RegistrationCodeResets::Table)
+                    .col(RegistrationCodeResets::Email)
+                    .to_owned(),
+            )
+            .await?;
+
+        txn.commit().await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-registration_code_resets-email")
+                    .table(RegistrationCodeResets::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        let columns = [
+            Table::alter()
+                .table(RegistrationCodeResets::Table)
+                .drop_column(RegistrationCodeResets::CreatedAt)
+                .to_owned(),
+            Table::alter()
+                .table(RegistrationCodeResets::Table)
+                .drop_column(RegistrationCodeResets::Attempts)
+                .to_owned(),
+        ];
+        for statement in columns {
+            manager.alter_table(statement).await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum RegistrationCodes {
+    Table,
+    Id,
+    CodeHash,
+}
+
+#[derive(Iden)]
+enum RegistrationCodeResets {
+    Table,
+    Email,
+    Used,
+    Attempts,
+    CreatedAt,
+}
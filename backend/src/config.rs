@@ -1,12 +1,42 @@
-use dotenvy::dotenv;
 use std::env;
+use std::net::IpAddr;
 
 #[derive(Clone, Debug)]
 pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
+    pub access_token_minutes: i64,
+    pub refresh_token_days: i64,
+    pub invitation_days: i64,
+    pub password_reset_minutes: i64,
+    pub company_email: Option<String>,
+    pub registration_reset_minutes: i64,
+    pub registration_reset_max_attempts: i32,
+    pub password_min_length: usize,
+    pub password_require_mixed_case: bool,
+    pub password_require_digit: bool,
+    pub password_require_symbol: bool,
+    pub auth_max_failures: i32,
+    pub auth_lockout_minutes: i64,
+    pub totp_issuer: String,
+    pub app_name: String,
+    pub mail_transport: String,
+    pub mail_from: String,
+    pub mail_file_dir: String,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub smtp_starttls: bool,
     pub server_host: String,
     pub server_port: u16,
+    /// Reverse proxies whose `X-Forwarded-For` is believed; nobody's by default
+    pub trusted_proxies: Vec<IpAddr>,
+    pub report_debounce_secs: i64,
+    pub report_max_wait_secs: i64,
+    pub report_max_attempts: i32,
+    pub base_currency: String,
+    pub fiscal_year_start_month: u32,
 }
 
 impl Config {
@@ -14,11 +44,104 @@ impl Config {
         Ok(Self {
             database_url: env::var("DATABASE_URL")?,
             jwt_secret: env::var("JWT_SECRET")?,
+            access_token_minutes: env::var("ACCESS_TOKEN_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .unwrap(),
+            refresh_token_days: env::var("REFRESH_TOKEN_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap(),
+            invitation_days: env::var("INVITATION_DAYS")
+                .unwrap_or_else(|_| "7".to_string())
+                .parse()
+                .unwrap(),
+            password_reset_minutes: env::var("PASSWORD_RESET_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap(),
+            company_email: env::var("COMPANY_EMAIL").ok().filter(|email| !email.trim().is_empty()),
+            registration_reset_minutes: env::var("REGISTRATION_RESET_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .unwrap(),
+            registration_reset_max_attempts: env::var("REGISTRATION_RESET_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap(),
+            password_min_length: env::var("PASSWORD_MIN_LENGTH")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .unwrap(),
+            password_require_mixed_case: env::var("PASSWORD_REQUIRE_MIXED_CASE")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap(),
+            password_require_digit: env::var("PASSWORD_REQUIRE_DIGIT")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap(),
+            password_require_symbol: env::var("PASSWORD_REQUIRE_SYMBOL")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap(),
+            auth_max_failures: env::var("AUTH_MAX_FAILURES")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap(),
+            auth_lockout_minutes: env::var("AUTH_LOCKOUT_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .unwrap(),
+            totp_issuer: env::var("TOTP_ISSUER").unwrap_or_else(|_| "Financial Tracker".to_string()),
+            app_name: env::var("APP_NAME").unwrap_or_else(|_| "Financial Tracker".to_string()),
+            // No default: stdout has to be asked for, so production never prints its mail
+            mail_transport: env::var("MAIL_TRANSPORT")?,
+            mail_from: env::var("MAIL_FROM")
+                .unwrap_or_else(|_| "Financial Tracker <no-reply@localhost>".to_string()),
+            mail_file_dir: env::var("MAIL_FILE_DIR").unwrap_or_else(|_| "mail".to_string()),
+            smtp_host: env::var("SMTP_HOST").ok(),
+            smtp_port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse()
+                .unwrap(),
+            smtp_username: env::var("SMTP_USERNAME").ok(),
+            smtp_password: env::var("SMTP_PASSWORD").ok(),
+            smtp_starttls: env::var("SMTP_STARTTLS")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap(),
             server_host: env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
             server_port: env::var("SERVER_PORT")
                 .unwrap_or_else(|_| "8080".to_string())
                 .parse()
                 .unwrap(),
+            trusted_proxies: env::var("TRUSTED_PROXIES")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|ip| !ip.is_empty())
+                .map(|ip| ip.parse().expect("TRUSTED_PROXIES must be a comma-separated list of IP addresses"))
+                .collect(),
+            report_debounce_secs: env::var("REPORT_DEBOUNCE_SECS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap(),
+            report_max_wait_secs: env::var("REPORT_MAX_WAIT_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap(),
+            report_max_attempts: env::var("REPORT_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap(),
+            base_currency: env::var("BASE_CURRENCY").unwrap_or_else(|_| "PHP".to_string()),
+            fiscal_year_start_month: env::var("FISCAL_YEAR_START_MONTH")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .ok()
+                .filter(|month| (1..=12).contains(month))
+                .expect("FISCAL_YEAR_START_MONTH must be a month number from 1 to 12"),
         })
     }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use sea_orm::{Database, DatabaseConnection};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection};
 use actix_web::web::Data;
 use crate::config::Config;
 
@@ -10,6 +10,17 @@ pub async fn connect(config: &Config) -> DatabaseConnection {
         .unwrap_or_else(|e| panic!("❌ Failed to connect to database '{}': {}", db_url, e))
 }
 
+/// Like `connect`, without logging every statement; for background loops that would
+/// otherwise fill the log while idle
+pub async fn connect_quiet(config: &Config) -> DatabaseConnection {
+    let db_url = &config.database_url;
+    let mut options = ConnectOptions::new(db_url.clone());
+    options.sqlx_logging(false);
+    Database::connect(options)
+        .await
+        .unwrap_or_else(|e| panic!("❌ Failed to connect to database '{}': {}", db_url, e))
+}
+
 /// Convenience wrapper to return Actix-compatible `Data<DatabaseConnection>`
 pub async fn connect_and_wrap(config: Data<Config>) -> Data<DatabaseConnection> {
     let conn = connect(&config).await;
@@ -1,8 +1,10 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.16
 
 use sea_orm::entity::prelude::*;
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "expenses")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -10,10 +12,16 @@ pub struct Model {
     pub description: String,
     pub label: String,
     #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
-    pub amount: Decimal,
+    pub amount: Money,
+    pub original_currency: String,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub original_amount: Money,
+    #[sea_orm(column_type = "Decimal(Some((16, 6)))")]
+    pub exchange_rate: Decimal,
     pub expense_date: Date,
     pub created_by: Option<i32>,
     pub modified_by: Option<i32>,
+    pub branch_id: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,6 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.16
 
 use sea_orm::entity::prelude::*;
+use crate::money::Money;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "invoices")]
@@ -11,8 +12,9 @@ pub struct Model {
     pub transaction_id: String,
     pub invoice_date: Date,
     #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
-    pub total_amount: Decimal,
+    pub total_amount: Money,
     pub description: String,
+    pub branch_id: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -25,6 +27,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Orders,
+    #[sea_orm(has_many = "super::payments::Entity")]
+    Payments,
 }
 
 impl Related<super::orders::Entity> for Entity {
@@ -33,4 +37,10 @@ impl Related<super::orders::Entity> for Entity {
     }
 }
 
+impl Related<super::payments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Payments.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -2,10 +2,28 @@
 
 pub mod prelude;
 
+pub mod accounts;
+pub mod api_keys;
+pub mod auth_throttles;
+pub mod branches;
+pub mod exchange_rates;
 pub mod expenses;
+pub mod invitations;
 pub mod invoices;
+pub mod journal_entries;
+pub mod journal_lines;
+pub mod lockout_events;
 pub mod orders;
+pub mod password_resets;
+pub mod payments;
+pub mod record_edits;
+pub mod recovery_codes;
 pub mod registration_code_resets;
 pub mod registration_codes;
+pub mod report_refresh_queue;
 pub mod reports;
+pub mod role_policies;
+pub mod sessions;
+pub mod statement_mappings;
+pub mod user_branches;
 pub mod users;
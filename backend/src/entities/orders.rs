@@ -1,8 +1,10 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.16
 
 use sea_orm::entity::prelude::*;
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "orders")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -10,10 +12,17 @@ pub struct Model {
     pub patient_name: String,
     pub order_date: Date,
     #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
-    pub total_amount: Decimal,
+    pub total_amount: Money,
+    pub original_currency: String,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub original_amount: Money,
+    #[sea_orm(column_type = "Decimal(Some((16, 6)))")]
+    pub exchange_rate: Decimal,
     pub description: String,
     pub created_by: Option<i32>,
     pub modified_by: Option<i32>,
+    pub branch_id: i32,
+    pub counterparty_branch_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,9 +1,27 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.16
 
+pub use super::accounts::Entity as Accounts;
+pub use super::api_keys::Entity as ApiKeys;
+pub use super::auth_throttles::Entity as AuthThrottles;
+pub use super::branches::Entity as Branches;
+pub use super::exchange_rates::Entity as ExchangeRates;
 pub use super::expenses::Entity as Expenses;
+pub use super::invitations::Entity as Invitations;
 pub use super::invoices::Entity as Invoices;
+pub use super::journal_entries::Entity as JournalEntries;
+pub use super::journal_lines::Entity as JournalLines;
+pub use super::lockout_events::Entity as LockoutEvents;
 pub use super::orders::Entity as Orders;
+pub use super::password_resets::Entity as PasswordResets;
+pub use super::payments::Entity as Payments;
+pub use super::record_edits::Entity as RecordEdits;
+pub use super::recovery_codes::Entity as RecoveryCodes;
 pub use super::registration_code_resets::Entity as RegistrationCodeResets;
 pub use super::registration_codes::Entity as RegistrationCodes;
+pub use super::report_refresh_queue::Entity as ReportRefreshQueue;
 pub use super::reports::Entity as Reports;
+pub use super::role_policies::Entity as RolePolicies;
+pub use super::sessions::Entity as Sessions;
+pub use super::statement_mappings::Entity as StatementMappings;
+pub use super::user_branches::Entity as UserBranches;
 pub use super::users::Entity as Users;
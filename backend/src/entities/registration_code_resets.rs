@@ -11,6 +11,8 @@ pub struct Model {
     pub hashed_verification_code: String,
     pub expires_at: DateTime,
     pub used: bool,
+    pub attempts: i32,
+    pub created_at: DateTime,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
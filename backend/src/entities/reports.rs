@@ -1,20 +1,26 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.16
 
 use sea_orm::entity::prelude::*;
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "reports")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub report_id: i32,
     pub month: Date,
+    pub branch_id: Option<i32>,
     pub total_orders: i32,
     #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
-    pub total_income: Decimal,
+    pub total_income: Money,
     #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
-    pub total_expenses: Decimal,
+    pub total_expenses: Money,
     #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
-    pub net_profit: Decimal,
+    pub net_profit: Money,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub fx_gain_loss: Money,
+    pub currency: String,
     pub generated_at: DateTime,
     #[sea_orm(column_type = "JsonBinary")]
     pub daily_data: Json,
@@ -10,6 +10,16 @@ pub struct Model {
     #[sea_orm(unique)]
     pub username: String,
     pub password_hash: String,
+    pub role: String,
+    #[sea_orm(unique)]
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub totp_secret: Option<String>,
+    pub totp_confirmed_at: Option<DateTime>,
+    pub totp_last_step: Option<i64>,
+    pub last_login_at: Option<DateTime>,
+    pub deactivated_at: Option<DateTime>,
+    pub deleted_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
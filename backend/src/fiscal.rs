@@ -0,0 +1,247 @@
+//! Fiscal years and named accounting periods.
+//!
+//! A fiscal year starts on the first day of the configured month and is named after
+//! the calendar year it ends in: with an April start, `FY2026` runs from 2025-04-01
+//! to 2026-03-31. With the default January start fiscal and calendar years coincide.
+//!
+//! Period names accepted by `Period::parse`:
+//! - `2026-05`: a calendar month;
+//! - `FY2026`: a whole fiscal year;
+//! - `FY2026-Q2`: a fiscal quarter (1-4);
+//! - `FY2026-P05`: a fiscal month, counted from the start of the year (1-12).
+
+use std::sync::OnceLock;
+use chrono::{Datelike, Months, NaiveDate};
+use serde::Serialize;
+use crate::errors::AppError;
+
+/// Fiscal years start in January when `FISCAL_YEAR_START_MONTH` is not configured
+pub const DEFAULT_START_MONTH: u32 = 1;
+
+static START_MONTH: OnceLock<u32> = OnceLock::new();
+
+/// Set the month (1-12) fiscal years start in; called once at startup
+pub fn init_start_month(month: u32) {
+    let _ = START_MONTH.set(month);
+}
+
+/// Month (1-12) fiscal years start in
+pub fn start_month() -> u32 {
+    START_MONTH.get().copied().unwrap_or(DEFAULT_START_MONTH)
+}
+
+/// First day of the month containing `date`
+pub fn start_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("Every month has a first day")
+}
+
+/// Last day of the month containing `date`
+pub fn end_of_month(date: NaiveDate) -> NaiveDate {
+    (start_of_month(date) + Months::new(1))
+        .pred_opt()
+        .expect("Failed to get last day")
+}
+
+/// Name (ending calendar year) of the fiscal year containing `date`
+pub fn fiscal_year_of(date: NaiveDate) -> i32 {
+    if start_month() == 1 || date.month() < start_month() {
+        date.year()
+    } else {
+        date.year() + 1
+    }
+}
+
+/// First day of a fiscal year
+pub fn fiscal_year_start(fiscal_year: i32) -> NaiveDate {
+    let start_year = if start_month() == 1 { fiscal_year } else { fiscal_year - 1 };
+    NaiveDate::from_ymd_opt(start_year, start_month(), 1).expect("Invalid fiscal year start")
+}
+
+/// An inclusive date range with the name it was asked for by
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Period {
+    pub name: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Period {
+    /// `count` whole months starting at `from`
+    fn months(name: String, from: NaiveDate, count: u32) -> Self {
+        Period {
+            name,
+            from,
+            to: (from + Months::new(count)).pred_opt().expect("Failed to get last day"),
+        }
+    }
+
+    /// Calendar month containing `date`
+    pub fn month(date: NaiveDate) -> Self {
+        let from = start_of_month(date);
+        Self::months(from.format("%Y-%m").to_string(), from, 1)
+    }
+
+    pub fn fiscal_year(fiscal_year: i32) -> Self {
+        Self::months(format!("FY{}", fiscal_year), fiscal_year_start(fiscal_year), 12)
+    }
+
+    /// Quarter 1-4 of a fiscal year
+    pub fn fiscal_quarter(fiscal_year: i32, quarter: u32) -> Result<Self, AppError> {
+        if !(1..=4).contains(&quarter) {
+            return Err(AppError::BadRequest(format!("Quarter must be 1 to 4, got {}", quarter)));
+        }
+        let from = fiscal_year_start(fiscal_year) + Months::new((quarter - 1) * 3);
+        Ok(Self::months(format!("FY{}-Q{}", fiscal_year, quarter), from, 3))
+    }
+
+    /// Month 1-12 of a fiscal year
+    pub fn fiscal_month(fiscal_year: i32, period: u32) -> Result<Self, AppError> {
+        if !(1..=12).contains(&period) {
+            return Err(AppError::BadRequest(format!("Fiscal month must be 1 to 12, got {}", period)));
+        }
+        let from = fiscal_year_start(fiscal_year) + Months::new(period - 1);
+        Ok(Self::months(format!("FY{}-P{:02}", fiscal_year, period), from, 1))
+    }
+
+    /// Start of the fiscal year containing `as_of` up to and including `as_of`
+    pub fn year_to_date(as_of: NaiveDate) -> Self {
+        let fiscal_year = fiscal_year_of(as_of);
+        Period {
+            name: format!("FY{}-YTD", fiscal_year),
+            from: fiscal_year_start(fiscal_year),
+            to: as_of,
+        }
+    }
+
+    /// The year, its quarters and its months, in that order
+    pub fn periods_of(fiscal_year: i32) -> Result<Vec<Self>, AppError> {
+        let mut periods = vec![Self::fiscal_year(fiscal_year)];
+        for quarter in 1..=4 {
+            periods.push(Self::fiscal_quarter(fiscal_year, quarter)?);
+        }
+        for period in 1..=12 {
+            periods.push(Self::fiscal_month(fiscal_year, period)?);
+        }
+        Ok(periods)
+    }
+
+    /// True when the period is exactly one calendar month
+    pub fn is_month(&self) -> bool {
+        self.from.day() == 1 && self.to == end_of_month(self.from)
+    }
+
+    /// Parse `YYYY-MM`, `FY2026`, `FY2026-Q2` or `FY2026-P05`
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        let value = value.trim();
+        let invalid = || {
+            AppError::BadRequest(format!(
+                "Invalid period '{}', expected YYYY-MM, FY2026, FY2026-Q2 or FY2026-P05",
+                value
+            ))
+        };
+
+        let Some(fiscal) = value.strip_prefix("FY").or_else(|| value.strip_prefix("fy")) else {
+            let month = NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d").map_err(|_| invalid())?;
+            return Ok(Self::month(month));
+        };
+
+        let (year, part) = match fiscal.split_once('-') {
+            Some((year, part)) => (year, Some(part)),
+            None => (fiscal, None),
+        };
+        let fiscal_year: i32 = year
+            .parse()
+            .ok()
+            .filter(|year| (1000..=9999).contains(year))
+            .ok_or_else(invalid)?;
+
+        match part {
+            None => Ok(Self::fiscal_year(fiscal_year)),
+            Some(part) => {
+                let number: u32 = part.get(1..).and_then(|n| n.parse().ok()).ok_or_else(invalid)?;
+                match part.get(..1) {
+                    Some("Q" | "q") => Self::fiscal_quarter(fiscal_year, number),
+                    Some("P" | "p") => Self::fiscal_month(fiscal_year, number),
+                    _ => Err(invalid()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The start month is process-wide, so every test here runs with an April start
+    fn april_start() {
+        init_start_month(4);
+        assert_eq!(start_month(), 4);
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn fiscal_year_is_named_after_the_year_it_ends_in() {
+        april_start();
+        assert_eq!(fiscal_year_of(date(2026, 3, 31)), 2026);
+        assert_eq!(fiscal_year_of(date(2026, 4, 1)), 2027);
+        assert_eq!(fiscal_year_start(2026), date(2025, 4, 1));
+    }
+
+    #[test]
+    fn fiscal_year_spans_the_calendar_year_boundary() {
+        april_start();
+        let year = Period::parse("FY2026").unwrap();
+        assert_eq!((year.from, year.to), (date(2025, 4, 1), date(2026, 3, 31)));
+    }
+
+    #[test]
+    fn quarters_and_months_count_from_the_start_month() {
+        april_start();
+        let q3 = Period::parse("FY2026-Q3").unwrap();
+        assert_eq!((q3.from, q3.to), (date(2025, 10, 1), date(2025, 12, 31)));
+        let q4 = Period::parse("fy2026-q4").unwrap();
+        assert_eq!((q4.from, q4.to), (date(2026, 1, 1), date(2026, 3, 31)));
+
+        let p10 = Period::parse("FY2026-P10").unwrap();
+        assert_eq!((p10.from, p10.to), (date(2026, 1, 1), date(2026, 1, 31)));
+        assert_eq!(p10.name, "FY2026-P10");
+        assert!(p10.is_month());
+    }
+
+    #[test]
+    fn calendar_months_ignore_the_fiscal_year() {
+        april_start();
+        let month = Period::parse("2024-02").unwrap();
+        assert_eq!((month.from, month.to), (date(2024, 2, 1), date(2024, 2, 29)));
+        assert_eq!(month.name, "2024-02");
+    }
+
+    #[test]
+    fn year_to_date_starts_in_the_previous_calendar_year() {
+        april_start();
+        let ytd = Period::year_to_date(date(2026, 2, 15));
+        assert_eq!(ytd.name, "FY2026-YTD");
+        assert_eq!((ytd.from, ytd.to), (date(2025, 4, 1), date(2026, 2, 15)));
+    }
+
+    #[test]
+    fn periods_of_lists_year_quarters_and_months() {
+        april_start();
+        let periods = Period::periods_of(2026).unwrap();
+        assert_eq!(periods.len(), 17);
+        assert_eq!(periods[0].name, "FY2026");
+        assert_eq!(periods[16].from, date(2026, 3, 1));
+    }
+
+    #[test]
+    fn rejects_malformed_periods() {
+        april_start();
+        for value in ["FY2026-Q5", "FY2026-P13", "FY2026-P0", "FY26", "FY2026-X1", "2026-13", "soon"] {
+            assert!(Period::parse(value).is_err(), "{} should not parse", value);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use actix_web::{web, HttpResponse};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+use crate::{
+    middleware::auth::Authorized,
+    permissions::require,
+    handlers::reports::parse_date,
+    services::analytics::{AnalyticsService, Bucket, GroupBy, Metric},
+    services::branches::BranchQuery,
+    services::reports::ReportScope,
+    errors::AppError,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct TimeSeriesQuery {
+    pub metric: Option<String>,   // income (default), expenses, net or orders
+    pub from: String,             // YYYY-MM-DD
+    pub to: String,               // YYYY-MM-DD
+    pub bucket: Option<String>,   // day (default), week or month
+    pub group_by: Option<String>, // label or user
+}
+
+/// GET /analytics/timeseries?metric=income|expenses|net|orders&from=&to=&bucket=day|week|month&group_by=label|user&branch_id=&consolidated=true
+/// Chart data in aligned buckets, empty buckets filled with zero
+pub async fn timeseries(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ReportsRead>,
+    query: web::Query<TimeSeriesQuery>,
+    branch: web::Query<BranchQuery>,
+) -> Result<HttpResponse, AppError> {
+    let metric = Metric::parse(query.metric.as_deref())?;
+    let bucket = Bucket::parse(query.bucket.as_deref())?;
+    let group_by = GroupBy::parse(query.group_by.as_deref())?;
+    let from = parse_date(&query.from)?;
+    let to = parse_date(&query.to)?;
+
+    let scope = ReportScope::from_branch(user.branch_scope(db.get_ref(), &branch).await?);
+    let service = AnalyticsService::new(db.get_ref().clone(), scope);
+    let series = service.timeseries(metric, bucket, group_by, from, to).await?;
+    Ok(HttpResponse::Ok().json(series))
+}
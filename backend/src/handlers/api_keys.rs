@@ -0,0 +1,46 @@
+use actix_web::{web, HttpResponse};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    middleware::auth::Authorized,
+    permissions::require,
+    services::api_keys::{ApiKeyQuery, ApiKeysService, CreateApiKeyRequest},
+    errors::AppError,
+};
+
+/// POST /api-keys
+/// Create a key for an integration; the key is only shown in this response
+pub async fn create_api_key(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::UsersAdmin>,
+    payload: web::Json<CreateApiKeyRequest>,
+) -> Result<HttpResponse, AppError> {
+    user.session()?;
+    let service = ApiKeysService::new(db.get_ref().clone());
+    let api_key = service.create_api_key(payload.into_inner(), user.user_id).await?;
+    Ok(HttpResponse::Created().json(api_key))
+}
+
+/// GET /api-keys?all=true
+/// Usable keys, or every key with `all`
+pub async fn list_api_keys(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::UsersAdmin>,
+    query: web::Query<ApiKeyQuery>,
+) -> Result<HttpResponse, AppError> {
+    let service = ApiKeysService::new(db.get_ref().clone());
+    let api_keys = service.get_api_keys(query.all.unwrap_or(false)).await?;
+    Ok(HttpResponse::Ok().json(api_keys))
+}
+
+/// DELETE /api-keys/{id}
+/// Revoke a key
+pub async fn revoke_api_key(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = ApiKeysService::new(db.get_ref().clone());
+    service.revoke_api_key(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json("API key revoked successfully"))
+}
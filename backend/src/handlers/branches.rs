@@ -0,0 +1,110 @@
+use actix_web::{web, HttpResponse};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+use crate::{
+    middleware::auth::{AuthenticatedUser, Authorized},
+    permissions::require,
+    services::branches::{
+        BranchesService, CreateBranchRequest, UpdateBranchRequest, AssignBranchRequest as ServiceAssignRequest,
+    },
+    errors::AppError,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct AssignBranchRequest {
+    pub branch_id: i32,
+    pub is_default: Option<bool>,
+}
+
+/// GET /branches
+/// Fetch all branches
+pub async fn list_branches(
+    db: web::Data<DatabaseConnection>,
+    _user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let service = BranchesService::new(db.get_ref().clone());
+    let branches = service.get_branches().await?;
+    Ok(HttpResponse::Ok().json(branches))
+}
+
+/// POST /branches
+/// Open a new branch
+pub async fn create_branch(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::BranchesWrite>,
+    payload: web::Json<CreateBranchRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = BranchesService::new(db.get_ref().clone());
+    let branch = service.create_branch(payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(branch))
+}
+
+/// PUT /branches/{id}
+/// Rename a branch or close / reopen it
+pub async fn update_branch(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::BranchesWrite>,
+    path: web::Path<i32>,
+    payload: web::Json<UpdateBranchRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = BranchesService::new(db.get_ref().clone());
+    let branch = service.update_branch(path.into_inner(), payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(branch))
+}
+
+/// GET /me/branches
+/// Branches the signed-in user can work in
+pub async fn my_branches(
+    db: web::Data<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let service = BranchesService::new(db.get_ref().clone());
+    let branches = service.get_user_branches(user.user_id).await?;
+    Ok(HttpResponse::Ok().json(branches))
+}
+
+/// GET /users/{id}/branches
+/// Branches a user is assigned to
+pub async fn list_user_branches(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::BranchesWrite>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = BranchesService::new(db.get_ref().clone());
+    let branches = service.get_user_branches(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(branches))
+}
+
+/// PUT /users/{id}/branches
+/// Assign a user to a branch
+pub async fn assign_user_branch(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::BranchesWrite>,
+    path: web::Path<i32>,
+    payload: web::Json<AssignBranchRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = BranchesService::new(db.get_ref().clone());
+
+    let req = ServiceAssignRequest {
+        user_id: path.into_inner(),
+        branch_id: payload.branch_id,
+        is_default: payload.is_default.unwrap_or(false),
+    };
+
+    let branches = service.assign_user(req).await?;
+    Ok(HttpResponse::Ok().json(branches))
+}
+
+/// DELETE /users/{id}/branches/{branch_id}
+/// Remove a user from a branch
+pub async fn unassign_user_branch(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::BranchesWrite>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, branch_id) = path.into_inner();
+    let service = BranchesService::new(db.get_ref().clone());
+    service.unassign_user(user_id, branch_id).await?;
+    Ok(HttpResponse::Ok().json("User removed from branch successfully"))
+}
@@ -1,10 +1,25 @@
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sea_orm::DatabaseConnection;
 
-#[get("/dashboard/summary")]
-async fn summary() -> impl Responder {
-    HttpResponse::Ok().body("Dashboard summary endpoint")
-}
+use crate::{
+    middleware::auth::Authorized,
+    permissions::require,
+    services::dashboard::DashboardService,
+    services::branches::BranchQuery,
+    services::reports::ReportScope,
+    errors::AppError,
+};
 
-pub fn init(cfg: &mut web::ServiceConfig) {
-    cfg.service(summary);
-}
\ No newline at end of file
+/// GET /dashboard?branch_id=&consolidated=true
+/// Today's and month-to-date KPIs, receivables, top expense labels and a 30-day sparkline
+pub async fn summary(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ReportsRead>,
+    branch: web::Query<BranchQuery>,
+) -> Result<HttpResponse, AppError> {
+    let scope = ReportScope::from_branch(user.branch_scope(db.get_ref(), &branch).await?);
+    let service = DashboardService::new(db.get_ref().clone(), scope);
+    let summary = service.summary(Utc::now().date_naive()).await?;
+    Ok(HttpResponse::Ok().json(summary))
+}
@@ -0,0 +1,79 @@
+use actix_web::{web, HttpResponse};
+use sea_orm::DatabaseConnection;
+use sea_orm::prelude::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    middleware::auth::Authorized,
+    permissions::require,
+    handlers::reports::parse_date,
+    services::exchange_rates::{ExchangeRatesService, SaveRateRequest as ServiceSaveRateRequest},
+    errors::AppError,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct RatesQuery {
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveRateRequest {
+    pub currency: String,
+    pub rate_date: String, // YYYY-MM-DD
+    pub rate: Decimal,     // base-currency units per unit of `currency`
+}
+
+/// GET /exchange-rates?currency=USD
+/// Fetch exchange rates, newest first
+pub async fn list_rates(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerRead>,
+    query: web::Query<RatesQuery>,
+) -> Result<HttpResponse, AppError> {
+    let service = ExchangeRatesService::new(db.get_ref().clone());
+    let rates = service.get_rates(query.currency.as_deref()).await?;
+    Ok(HttpResponse::Ok().json(rates))
+}
+
+/// PUT /exchange-rates
+/// Create or replace the rate of a currency on a date
+pub async fn save_rate(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerWrite>,
+    payload: web::Json<SaveRateRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = ExchangeRatesService::new(db.get_ref().clone());
+
+    let req = ServiceSaveRateRequest {
+        currency: payload.currency.clone(),
+        rate_date: parse_date(&payload.rate_date)?,
+        rate: payload.rate,
+    };
+
+    let rate = service.save_rate(req).await?;
+    Ok(HttpResponse::Ok().json(rate))
+}
+
+/// POST /exchange-rates/import
+/// Import rates from a CSV body with a `currency,rate_date,rate` header
+pub async fn import_rates(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerWrite>,
+    body: String,
+) -> Result<HttpResponse, AppError> {
+    let service = ExchangeRatesService::new(db.get_ref().clone());
+    let summary = service.import_csv(&body).await?;
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+/// DELETE /exchange-rates/{id}
+/// Delete a rate
+pub async fn delete_rate(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerWrite>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = ExchangeRatesService::new(db.get_ref().clone());
+    service.delete_rate(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json("Exchange rate deleted successfully"))
+}
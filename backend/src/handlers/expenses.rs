@@ -4,16 +4,21 @@ use serde::Deserialize;
 use chrono::NaiveDate;
 
 use crate::{
-    middleware::auth::AuthenticatedUser,
+    middleware::auth::Authorized,
+    permissions::require,
     services::expenses::{ExpensesService, CreateExpenseRequest as ServiceCreateRequest, UpdateExpenseRequest as ServiceUpdateRequest},
+    services::export::{ExportFormat, ExportQuery},
+    services::branches::BranchQuery,
     errors::AppError,
+    money::Money,
 };
 
 #[derive(Debug, Deserialize)]
 pub struct CreateExpenseRequest {
     pub description: String,
     pub label: String,
-    pub amount: f64,
+    pub amount: Money,
+    pub currency: Option<String>, // ISO 4217, defaults to the base currency
     pub expense_date: String, // YYYY-MM-DD
 }
 
@@ -21,14 +26,15 @@ pub struct CreateExpenseRequest {
 pub struct UpdateExpenseRequest {
     pub description: Option<String>,
     pub label: Option<String>,
-    pub amount: Option<f64>,
+    pub amount: Option<Money>,
+    pub currency: Option<String>,
     pub expense_date: Option<String>, // YYYY-MM-DD
 }
 
 /// POST /expenses
 pub async fn create_expense(
     db: web::Data<DatabaseConnection>,
-    user: AuthenticatedUser,
+    user: Authorized<require::ExpensesWrite>,
     payload: web::Json<CreateExpenseRequest>,
 ) -> Result<HttpResponse, AppError> {
     let service = ExpensesService::new(db.get_ref().clone());
@@ -37,39 +43,53 @@ pub async fn create_expense(
         description: payload.description.clone(),
         label: payload.label.clone(),
         amount: payload.amount,
+        currency: payload.currency.clone(),
         expense_date: NaiveDate::parse_from_str(&payload.expense_date, "%Y-%m-%d")
         .map_err(|_| AppError::BadRequest("Invalid date format, expected YYYY-MM-DD".into()))?,
         created_by: user.user_id,
+        branch_id: user.active_branch()?,
     };
 
     let expense = service.create_expense(req).await?;
     Ok(HttpResponse::Ok().json(expense))
 }
 
-/// GET /expenses
+/// GET /expenses?format=json|csv|xlsx&branch_id=&consolidated=true
 pub async fn list_expenses(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ExpensesRead>,
+    query: web::Query<ExportQuery>,
+    branch: web::Query<BranchQuery>,
 ) -> Result<HttpResponse, AppError> {
     let service = ExpensesService::new(db.get_ref().clone());
-    let result = service.get_expenses().await?;
+    let branch_id = user.branch_scope(db.get_ref(), &branch).await?;
+
+    let format = ExportFormat::parse(query.format.as_deref())?;
+    if format != ExportFormat::Json {
+        let file = service.export_expenses(format, branch_id).await?;
+        return Ok(file.into_response());
+    }
+
+    let result = service.get_expenses(branch_id).await?;
     Ok(HttpResponse::Ok().json(result))
 }
 
 /// GET /expenses/{id}
 pub async fn get_expense(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ExpensesRead>,
     path: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
     let service = ExpensesService::new(db.get_ref().clone());
-    let expense = service.get_expense_by_id(id).await?;
+    let expense = service.get_expense_by_id(id, user.record_scope()?).await?;
     Ok(HttpResponse::Ok().json(expense))
 }
 
 /// PUT /expenses/{id}
 pub async fn update_expense(
     db: web::Data<DatabaseConnection>,
-    user: AuthenticatedUser,
+    user: Authorized<require::ExpensesWrite>,
     path: web::Path<i32>,
     payload: web::Json<UpdateExpenseRequest>,
 ) -> Result<HttpResponse, AppError> {
@@ -80,6 +100,7 @@ pub async fn update_expense(
         description: payload.description.clone(),
         label: payload.label.clone(),
         amount: payload.amount,
+        currency: payload.currency.clone(),
         expense_date: match &payload.expense_date {
             Some(d) => Some(
                 NaiveDate::parse_from_str(d, "%Y-%m-%d")
@@ -89,17 +110,20 @@ pub async fn update_expense(
         },
     };
 
-    let updated = service.update_expense(id, req, user.user_id).await?;
+    let updated = service
+        .update_expense(id, user.active_branch()?, req, user.user_id)
+        .await?;
     Ok(HttpResponse::Ok().json(updated))
 }
 
 /// DELETE /expenses/{id}
 pub async fn delete_expense(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ExpensesDelete>,
     path: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
     let service = ExpensesService::new(db.get_ref().clone());
-    service.delete_expense(id).await?;
+    service.delete_expense(id, user.active_branch()?, user.user_id).await?;
     Ok(HttpResponse::Ok().json("Expense deleted successfully"))
 }
\ No newline at end of file
@@ -0,0 +1,53 @@
+use actix_web::{web, HttpResponse};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    middleware::auth::Authorized,
+    permissions::require,
+    services::invitations::{CreateInvitationRequest, InvitationQuery, InvitationsService},
+    config::Config,
+    mail::Mailer,
+    errors::AppError,
+};
+
+/// POST /invitations
+/// Invite someone by email with a role; the code is only returned here
+pub async fn create_invitation(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    user: Authorized<require::UsersAdmin>,
+    payload: web::Json<CreateInvitationRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = InvitationsService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let invitation = service.create_invitation(payload.into_inner(), user.user_id).await?;
+    Ok(HttpResponse::Created().json(invitation))
+}
+
+/// GET /invitations?all=true
+/// Pending invitations, or every invitation with `all`
+pub async fn list_invitations(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    _user: Authorized<require::UsersAdmin>,
+    query: web::Query<InvitationQuery>,
+) -> Result<HttpResponse, AppError> {
+    let service = InvitationsService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let invitations = service.get_invitations(query.all.unwrap_or(false)).await?;
+    Ok(HttpResponse::Ok().json(invitations))
+}
+
+/// DELETE /invitations/{id}
+/// Revoke an invitation that hasn't been accepted
+pub async fn revoke_invitation(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    _user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = InvitationsService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    service.revoke_invitation(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json("Invitation revoked successfully"))
+}
@@ -4,9 +4,15 @@ use chrono::NaiveDate;
 use serde::Deserialize;
 
 use crate::{
-    middleware::auth::AuthenticatedUser,
+    middleware::auth::Authorized,
+    permissions::require,
     services::invoices::{InvoicesService, CreateInvoiceRequest as ServiceCreateRequest},
+    services::export::{ExportFormat, ExportQuery},
+    services::branches::BranchQuery,
+    services::orders::OrdersService,
+    services::payments::{PaymentsService, CreatePaymentRequest as ServiceCreatePaymentRequest},
     errors::AppError,
+    money::Money,
 };
 
 #[derive(Debug, Deserialize)]
@@ -14,7 +20,7 @@ pub struct CreateInvoiceRequest {
     pub order_id: i32,
     pub transaction_id: String,
     pub invoice_date: String, // YYYY-MM-DD
-    pub total_amount: f64,
+    pub total_amount: Money,
     pub description: String,
 }
 
@@ -22,9 +28,14 @@ pub struct CreateInvoiceRequest {
 /// Create a new invoice
 pub async fn create_invoice(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::OrdersWrite>,
     payload: web::Json<CreateInvoiceRequest>,
 ) -> Result<HttpResponse, AppError> {
     let service = InvoicesService::new(db.get_ref().clone());
+    // An invoice belongs to the branch of its order
+    let order = OrdersService::new(db.get_ref().clone())
+        .get_order_by_id(payload.order_id, Some(user.active_branch()?))
+        .await?;
 
     let req = ServiceCreateRequest {
         order_id: payload.order_id,
@@ -33,19 +44,31 @@ pub async fn create_invoice(
             .map_err(|_| AppError::BadRequest("Invalid date format, expected YYYY-MM-DD".into()))?,
         total_amount: payload.total_amount,
         description: payload.description.clone(),
+        branch_id: order.branch_id,
     };
 
     let invoice = service.create_invoice(req).await?;
     Ok(HttpResponse::Ok().json(invoice))
 }
 
-/// GET /invoices
+/// GET /invoices?format=json|csv|xlsx&branch_id=&consolidated=true
 /// Fetch all invoices
 pub async fn list_invoices(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::InvoicesRead>,
+    query: web::Query<ExportQuery>,
+    branch: web::Query<BranchQuery>,
 ) -> Result<HttpResponse, AppError> {
     let service = InvoicesService::new(db.get_ref().clone());
-    let invoices = service.get_all_invoices().await?;
+    let branch_id = user.branch_scope(db.get_ref(), &branch).await?;
+
+    let format = ExportFormat::parse(query.format.as_deref())?;
+    if format != ExportFormat::Json {
+        let file = service.export_invoices(format, branch_id).await?;
+        return Ok(file.into_response());
+    }
+
+    let invoices = service.get_all_invoices(branch_id).await?;
     Ok(HttpResponse::Ok().json(invoices))
 }
 
@@ -53,12 +76,12 @@ pub async fn list_invoices(
 /// Fetch a single invoice by ID
 pub async fn get_invoice(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::InvoicesRead>,
     path: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
     let service = InvoicesService::new(db.get_ref().clone());
-    let invoice = service.get_all_invoices().await?; // or filter by ID if needed
-    let invoice = invoice.into_iter().find(|i| i.invoice_id == id).ok_or(AppError::NotFound("Invoice not found.".into()))?;
+    let invoice = service.get_invoice(id, user.record_scope()?).await?;
     Ok(HttpResponse::Ok().json(invoice))
 }
 
@@ -66,11 +89,12 @@ pub async fn get_invoice(
 /// Fetch invoices linked to a specific order
 pub async fn get_invoice_by_order(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::InvoicesRead>,
     path: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
     let order_id = path.into_inner();
     let service = InvoicesService::new(db.get_ref().clone());
-    let invoices = service.get_invoices_by_order(order_id).await?;
+    let invoices = service.get_invoices_by_order(order_id, user.record_scope()?).await?;
     Ok(HttpResponse::Ok().json(invoices))
 }
 
@@ -78,10 +102,68 @@ pub async fn get_invoice_by_order(
 /// Delete an invoice
 pub async fn delete_invoice(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::OrdersDelete>,
     path: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
     let invoice_id = path.into_inner();
     let service = InvoicesService::new(db.get_ref().clone());
-    service.delete_invoice(invoice_id).await?;
+    service.delete_invoice(invoice_id, user.active_branch()?).await?;
     Ok(HttpResponse::Ok().json("Invoice deleted successfully"))
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePaymentRequest {
+    pub amount: Money,
+    pub currency: Option<String>, // defaults to the order's currency
+    pub payment_date: String,     // YYYY-MM-DD
+    pub method: String,           // cash, bank transfer, card, ...
+}
+
+/// POST /invoices/{id}/payments
+/// Record a payment against an invoice
+pub async fn create_payment(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::PaymentsWrite>,
+    path: web::Path<i32>,
+    payload: web::Json<CreatePaymentRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = PaymentsService::new(db.get_ref().clone());
+
+    let req = ServiceCreatePaymentRequest {
+        invoice_id: path.into_inner(),
+        amount: payload.amount,
+        currency: payload.currency.clone(),
+        payment_date: NaiveDate::parse_from_str(&payload.payment_date, "%Y-%m-%d")
+            .map_err(|_| AppError::BadRequest("Invalid date format, expected YYYY-MM-DD".into()))?,
+        method: payload.method.clone(),
+        created_by: user.user_id,
+        branch_id: user.active_branch()?,
+    };
+
+    let payment = service.create_payment(req).await?;
+    Ok(HttpResponse::Ok().json(payment))
+}
+
+/// GET /invoices/{id}/payments
+/// Fetch the payments of an invoice and its outstanding balance
+pub async fn list_payments(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::InvoicesRead>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = PaymentsService::new(db.get_ref().clone());
+    let payments = service.get_invoice_payments(path.into_inner(), user.record_scope()?).await?;
+    Ok(HttpResponse::Ok().json(payments))
+}
+
+/// DELETE /payments/{id}
+/// Delete a payment
+pub async fn delete_payment(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::PaymentsWrite>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = PaymentsService::new(db.get_ref().clone());
+    service.delete_payment(path.into_inner(), user.active_branch()?).await?;
+    Ok(HttpResponse::Ok().json("Payment deleted successfully"))
+}
@@ -0,0 +1,203 @@
+use actix_web::{web, HttpResponse};
+use chrono::{NaiveDate, Utc};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+use crate::{
+    middleware::auth::Authorized,
+    permissions::require,
+    handlers::reports::{parse_date, parse_month},
+    services::ledger::{
+        LedgerService, CreateAccountRequest, CreateJournalEntryRequest as ServiceCreateEntryRequest,
+        JournalLineRequest, OpeningBalance, OpeningBalancesRequest as ServiceOpeningBalancesRequest,
+    },
+    errors::AppError,
+    money::Money,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateJournalLine {
+    pub account_id: i32,
+    pub debit: Option<Money>,
+    pub credit: Option<Money>,
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateJournalEntryRequest {
+    pub entry_date: String, // YYYY-MM-DD
+    pub description: String,
+    pub lines: Vec<CreateJournalLine>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpeningBalanceLine {
+    pub account_id: i32,
+    pub amount: Money,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpeningBalancesRequest {
+    pub as_of: String, // YYYY-MM-DD
+    pub balances: Vec<OpeningBalanceLine>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EntriesQuery {
+    pub from: String, // YYYY-MM-DD
+    pub to: String,   // YYYY-MM-DD
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrialBalanceQuery {
+    pub as_of: Option<String>, // YYYY-MM-DD, defaults to today
+}
+
+/// GET /ledger/accounts
+/// Fetch the chart of accounts
+pub async fn list_accounts(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerRead>,
+) -> Result<HttpResponse, AppError> {
+    let service = LedgerService::new(db.get_ref().clone());
+    let accounts = service.get_accounts().await?;
+    Ok(HttpResponse::Ok().json(accounts))
+}
+
+/// POST /ledger/accounts
+/// Add an account to the chart of accounts
+pub async fn create_account(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerWrite>,
+    payload: web::Json<CreateAccountRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = LedgerService::new(db.get_ref().clone());
+    let account = service.create_account(payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(account))
+}
+
+/// GET /ledger/entries?from=YYYY-MM-DD&to=YYYY-MM-DD
+/// Fetch journal entries with their lines
+pub async fn list_entries(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerRead>,
+    query: web::Query<EntriesQuery>,
+) -> Result<HttpResponse, AppError> {
+    let from = parse_date(&query.from)?;
+    let to = parse_date(&query.to)?;
+    let service = LedgerService::new(db.get_ref().clone());
+
+    let entries = service.get_entries(from, to).await?;
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+/// POST /ledger/entries
+/// Record a manual journal entry; debits and credits must balance
+pub async fn create_entry(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::LedgerWrite>,
+    payload: web::Json<CreateJournalEntryRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = LedgerService::new(db.get_ref().clone());
+    let payload = payload.into_inner();
+
+    let req = ServiceCreateEntryRequest {
+        entry_date: parse_date(&payload.entry_date)?,
+        description: payload.description,
+        lines: payload
+            .lines
+            .into_iter()
+            .map(|line| JournalLineRequest {
+                account_id: line.account_id,
+                debit: line.debit,
+                credit: line.credit,
+                memo: line.memo,
+            })
+            .collect(),
+        created_by: user.user_id,
+    };
+
+    let entry = service.create_entry(req).await?;
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+/// PUT /ledger/opening-balances
+/// Set the balances the books start from; replaces earlier opening balances
+pub async fn set_opening_balances(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::LedgerWrite>,
+    payload: web::Json<OpeningBalancesRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = LedgerService::new(db.get_ref().clone());
+    let payload = payload.into_inner();
+
+    let req = ServiceOpeningBalancesRequest {
+        as_of: parse_date(&payload.as_of)?,
+        balances: payload
+            .balances
+            .into_iter()
+            .map(|line| OpeningBalance {
+                account_id: line.account_id,
+                amount: line.amount,
+            })
+            .collect(),
+        created_by: user.user_id,
+    };
+
+    let entry = service.set_opening_balances(req).await?;
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+/// DELETE /ledger/entries/{id}
+/// Delete a manual journal entry
+pub async fn delete_entry(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerWrite>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = LedgerService::new(db.get_ref().clone());
+    service.delete_entry(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json("Journal entry deleted successfully"))
+}
+
+/// GET /ledger/trial-balance?as_of=YYYY-MM-DD
+/// Balance of every account as of a date
+pub async fn get_trial_balance(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerRead>,
+    query: web::Query<TrialBalanceQuery>,
+) -> Result<HttpResponse, AppError> {
+    let as_of: NaiveDate = match &query.as_of {
+        Some(date) => parse_date(date)?,
+        None => Utc::now().date_naive(),
+    };
+    let service = LedgerService::new(db.get_ref().clone());
+
+    let trial_balance = service.trial_balance(as_of).await?;
+    Ok(HttpResponse::Ok().json(trial_balance))
+}
+
+/// GET /ledger/reconciliation/{month}
+/// Compare a month's (YYYY-MM) report figures with the ones rebuilt from the ledger
+pub async fn get_reconciliation(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerRead>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let month = parse_month(&path.into_inner())?;
+    let service = LedgerService::new(db.get_ref().clone());
+
+    let reconciliation = service.reconcile_month(month).await?;
+    Ok(HttpResponse::Ok().json(reconciliation))
+}
+
+/// POST /ledger/rebuild
+/// Re-post every order, expense and payment to the ledger
+pub async fn rebuild(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerWrite>,
+) -> Result<HttpResponse, AppError> {
+    let service = LedgerService::new(db.get_ref().clone());
+    let summary = service.rebuild().await?;
+    Ok(HttpResponse::Ok().json(summary))
+}
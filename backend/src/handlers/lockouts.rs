@@ -0,0 +1,49 @@
+use actix_web::{web, HttpResponse};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    middleware::auth::Authorized,
+    permissions::require,
+    services::throttle::{LockoutQuery, ThrottleService},
+    config::Config,
+    errors::AppError,
+};
+
+/// GET /lockouts?all=true
+/// Lockouts in force, or every recorded lockout with `all`
+pub async fn list_lockouts(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    _user: Authorized<require::UsersAdmin>,
+    query: web::Query<LockoutQuery>,
+) -> Result<HttpResponse, AppError> {
+    let service = ThrottleService::new(db.get_ref().clone(), &config);
+    let lockouts = service.get_lockouts(query.all.unwrap_or(false)).await?;
+    Ok(HttpResponse::Ok().json(lockouts))
+}
+
+/// DELETE /lockouts/{id}
+/// Lift a lockout of an account or address
+pub async fn lift_lockout(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = ThrottleService::new(db.get_ref().clone(), &config);
+    service.unlock(path.into_inner(), user.user_id).await?;
+    Ok(HttpResponse::Ok().json("Lockout lifted successfully"))
+}
+
+/// POST /users/{id}/unlock
+/// Lift every lockout of a user's account
+pub async fn unlock_user(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = ThrottleService::new(db.get_ref().clone(), &config);
+    service.unlock_user(path.into_inner(), user.user_id).await?;
+    Ok(HttpResponse::Ok().json("User unlocked successfully"))
+}
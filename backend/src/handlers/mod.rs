@@ -4,4 +4,16 @@ pub mod orders;
 pub mod expenses;
 pub mod invoices;
 pub mod reports;
-pub mod registration;
\ No newline at end of file
+pub mod registration;
+pub mod ledger;
+pub mod statements;
+pub mod exchange_rates;
+pub mod branches;
+pub mod analytics;
+pub mod roles;
+pub mod sessions;
+pub mod invitations;
+pub mod lockouts;
+pub mod two_factor;
+pub mod user_admin;
+pub mod api_keys;
@@ -5,32 +5,38 @@ use chrono::NaiveDate;
 use serde_json::json;
 
 use crate::{
-    middleware::auth::AuthenticatedUser,
+    middleware::auth::Authorized,
+    permissions::require,
     services::orders::{OrdersService, CreateOrderRequest as ServiceCreateRequest, UpdateOrderRequest as ServiceUpdateRequest},
     errors::AppError,
-    services::invoices::InvoicesService,
+    money::Money,
+    services::export::{ExportFormat, ExportQuery},
+    services::branches::BranchQuery,
 };
 
 #[derive(Debug, Deserialize)]
 pub struct CreateOrderRequest {
     pub patient_name: String,
     pub order_date: String,      // YYYY-MM-DD
-    pub total_amount: f64,
+    pub total_amount: Money,
+    pub currency: Option<String>, // ISO 4217, defaults to the base currency
     pub description: String,
+    pub counterparty_branch_id: Option<i32>, // bill another branch (inter-branch transfer)
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateOrderRequest {
     pub patient_name: Option<String>,
     pub order_date: Option<String>, // YYYY-MM-DD
-    pub total_amount: Option<f64>,
+    pub total_amount: Option<Money>,
+    pub currency: Option<String>,
     pub description: Option<String>,
 }
 
 /// POST /orders
 pub async fn create_order(
     db: web::Data<DatabaseConnection>,
-    user: AuthenticatedUser,
+    user: Authorized<require::OrdersWrite>,
     payload: web::Json<CreateOrderRequest>,
 ) -> Result<HttpResponse, AppError> {
     let service = OrdersService::new(db.get_ref().clone());
@@ -40,8 +46,11 @@ pub async fn create_order(
         order_date: NaiveDate::parse_from_str(&payload.order_date, "%Y-%m-%d")
             .map_err(|_| AppError::BadRequest("Invalid date format, expected YYYY-MM-DD".into()))?,
         total_amount: payload.total_amount,
+        currency: payload.currency.clone(),
         description: payload.description.clone(),
         created_by: user.user_id,
+        branch_id: user.active_branch()?,
+        counterparty_branch_id: payload.counterparty_branch_id,
     };
 
     // Create order and auto-generate invoice
@@ -55,30 +64,42 @@ pub async fn create_order(
 }
 
 
-/// GET /orders
+/// GET /orders?format=json|csv|xlsx&branch_id=&consolidated=true
 pub async fn list_orders(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::OrdersRead>,
+    query: web::Query<ExportQuery>,
+    branch: web::Query<BranchQuery>,
 ) -> Result<HttpResponse, AppError> {
     let service = OrdersService::new(db.get_ref().clone());
-    let result = service.get_orders().await?;
+    let branch_id = user.branch_scope(db.get_ref(), &branch).await?;
+
+    let format = ExportFormat::parse(query.format.as_deref())?;
+    if format != ExportFormat::Json {
+        let file = service.export_orders(format, branch_id).await?;
+        return Ok(file.into_response());
+    }
+
+    let result = service.get_orders(branch_id).await?;
     Ok(HttpResponse::Ok().json(result))
 }
 
 /// GET /orders/{id}
 pub async fn get_order(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::OrdersRead>,
     path: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
     let service = OrdersService::new(db.get_ref().clone());
-    let order = service.get_order_by_id(id).await?;
+    let order = service.get_order_by_id(id, user.record_scope()?).await?;
     Ok(HttpResponse::Ok().json(order))
 }
 
 /// PUT /orders/{id}
 pub async fn update_order(
     db: web::Data<DatabaseConnection>,
-    user: AuthenticatedUser,
+    user: Authorized<require::OrdersWrite>,
     path: web::Path<i32>,
     payload: web::Json<UpdateOrderRequest>,
 ) -> Result<HttpResponse, AppError> {
@@ -95,10 +116,13 @@ pub async fn update_order(
             None => None,
         },
         total_amount: payload.total_amount,
+        currency: payload.currency.clone(),
         description: payload.description.clone(),
     };
 
-    let (updated_order, updated_invoice) = service.update_order(id, req, user.user_id).await?;
+    let (updated_order, updated_invoice) = service
+        .update_order(id, user.active_branch()?, req, user.user_id)
+        .await?;
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "order": updated_order,
@@ -109,10 +133,11 @@ pub async fn update_order(
 /// DELETE /orders/{id}
 pub async fn delete_order(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::OrdersDelete>,
     path: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
     let service = OrdersService::new(db.get_ref().clone());
-    service.delete_order(id).await?;
+    service.delete_order(id, user.active_branch()?, user.user_id).await?;
     Ok(HttpResponse::Ok().json("Order deleted successfully"))
 }
\ No newline at end of file
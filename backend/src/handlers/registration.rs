@@ -1,9 +1,15 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use sea_orm::DatabaseConnection;
 use serde::Deserialize;
 
 use crate::{
     services::registration::{RegistrationService, ForgotRegistrationRequest, ResetRegistrationRequest},
+    services::sessions::ClientInfo,
+    services::throttle::{
+        Attempt, ThrottleService, ACTION_FORGOT_REGISTRATION_CODE, ACTION_RESET_REGISTRATION_CODE,
+    },
+    config::Config,
+    mail::Mailer,
     errors::AppError,
 };
 
@@ -22,25 +28,42 @@ pub struct ResetCodeRequest {
 /// POST /registration/forgot-code
 pub async fn forgot_code(
     db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    http: HttpRequest,
     payload: web::Json<ForgotCodeRequest>,
 ) -> Result<HttpResponse, AppError> {
-    let service = RegistrationService::new(db.get_ref().clone());
+    let service = RegistrationService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let throttle = ThrottleService::new(db.get_ref().clone(), &config);
+
+    // Every request counts, so the company mailbox can't be flooded with codes
+    let attempt = Attempt::new(Some(&payload.email), ClientInfo::from_request(&http).ip_address);
+    throttle.check(ACTION_FORGOT_REGISTRATION_CODE, &attempt).await?;
+    throttle.record_failure(ACTION_FORGOT_REGISTRATION_CODE, &attempt, None).await?;
 
     let req = ForgotRegistrationRequest {
         email: payload.email.clone(),
     };
 
-    let code = service.forgot_registration_code(req).await?;
+    service.forgot_registration_code(req).await?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({ "verification_code": code })))
+    Ok(HttpResponse::Ok().json("If the email is the company email, a verification code has been sent"))
 }
 
 /// POST /registration/reset-code
 pub async fn reset_code(
     db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    http: HttpRequest,
     payload: web::Json<ResetCodeRequest>,
 ) -> Result<HttpResponse, AppError> {
-    let service = RegistrationService::new(db.get_ref().clone());
+    let service = RegistrationService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let throttle = ThrottleService::new(db.get_ref().clone(), &config);
+
+    // Guessing verification codes is what the throttle is there to stop
+    let attempt = Attempt::new(Some(&payload.email), ClientInfo::from_request(&http).ip_address);
+    throttle.check(ACTION_RESET_REGISTRATION_CODE, &attempt).await?;
 
     let req = ResetRegistrationRequest {
         email: payload.email.clone(),
@@ -48,7 +71,17 @@ pub async fn reset_code(
         new_registration_code: payload.new_registration_code.clone(),
     };
 
-    let message = service.reset_registration_code(req).await?;
+    let message = match service.reset_registration_code(req).await {
+        Ok(message) => {
+            throttle.record_success(ACTION_RESET_REGISTRATION_CODE, &attempt).await?;
+            message
+        }
+        Err(AppError::BadRequest(reason)) => {
+            throttle.record_failure(ACTION_RESET_REGISTRATION_CODE, &attempt, None).await?;
+            return Err(AppError::BadRequest(reason));
+        }
+        Err(e) => return Err(e),
+    };
 
     Ok(HttpResponse::Ok().json(serde_json::json!({ "message": message })))
 }
\ No newline at end of file
@@ -1,57 +1,269 @@
 use actix_web::{web, HttpResponse};
-use chrono::NaiveDate;
+use chrono::{NaiveDate, Utc};
 use serde::Deserialize;
 use sea_orm::DatabaseConnection;
 
 use crate::{
-    middleware::auth::AuthenticatedUser,
-    services::reports::{ReportsService, MonthlyReport as ServiceMonthlyReport},
+    middleware::auth::Authorized,
+    permissions::require,
+    services::reports::{ReportScope, ReportsService, DEFAULT_TOP_EXPENSES},
+    services::branches::BranchQuery,
+    services::export::{ExportFormat, ExportQuery},
+    services::report_queue::ReportQueue,
+    services::staff_activity::StaffActivityService,
+    config::Config,
     errors::AppError,
+    fiscal::{self, Period},
 };
 
 #[derive(Debug, Deserialize)]
 pub struct GenerateReportRequest {
     pub month: String, // YYYY-MM
+    pub branch_id: Option<i32>, // omitted for the active branch
+    #[serde(default)]
+    pub consolidated: bool, // the report of every branch
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RangeQuery {
+    pub from: Option<String>,   // YYYY-MM-DD
+    pub to: Option<String>,     // YYYY-MM-DD
+    pub period: Option<String>, // instead of from/to: YYYY-MM, FY2026, FY2026-Q2 or FY2026-P05
+    pub top: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StaffActivityQuery {
+    pub from: Option<String>,   // YYYY-MM-DD
+    pub to: Option<String>,     // YYYY-MM-DD
+    pub period: Option<String>, // instead of from/to
+}
+
+#[derive(Debug, Deserialize)]
+pub struct YearToDateQuery {
+    pub as_of: Option<String>, // YYYY-MM-DD, defaults to today
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FiscalPeriodsQuery {
+    pub fiscal_year: Option<i32>, // defaults to the current fiscal year
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BreakdownQuery {
+    pub top: Option<usize>,
+}
+
+/// Parse YYYY-MM into NaiveDate (first day of month)
+pub(crate) fn parse_month(month: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map_err(|_| AppError::BadRequest("Invalid month format, expected YYYY-MM".into()))
+}
+
+pub(crate) fn parse_date(date: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| AppError::BadRequest("Invalid date format, expected YYYY-MM-DD".into()))
+}
+
+/// Date range of either a named `period` or explicit `from` and `to` dates
+pub(crate) fn parse_range(
+    from: Option<&str>,
+    to: Option<&str>,
+    period: Option<&str>,
+) -> Result<(NaiveDate, NaiveDate), AppError> {
+    match (period, from, to) {
+        (Some(period), None, None) => {
+            let period = Period::parse(period)?;
+            Ok((period.from, period.to))
+        }
+        (None, Some(from), Some(to)) => Ok((parse_date(from)?, parse_date(to)?)),
+        _ => Err(AppError::BadRequest(
+            "Give either 'period' (e.g. FY2026-Q2) or both 'from' and 'to'".into(),
+        )),
+    }
 }
 
 /// POST /reports
 /// Generate a monthly report
 pub async fn generate_report(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ReportsWrite>,
     payload: web::Json<GenerateReportRequest>,
 ) -> Result<HttpResponse, AppError> {
-    let service = ReportsService::new(db.get_ref().clone());
+    let branch = BranchQuery { branch_id: payload.branch_id, consolidated: payload.consolidated };
+    let scope = ReportScope::from_branch(user.branch_scope(db.get_ref(), &branch).await?);
+    let service = ReportsService::new(db.get_ref().clone()).with_scope(scope);
 
-    // Parse YYYY-MM into NaiveDate (first day of month)
-    let month = NaiveDate::parse_from_str(&(payload.month.clone() + "-01"), "%Y-%m-%d")
-    .map_err(|_| AppError::BadRequest("Invalid month format, expected YYYY-MM".into()))?;
+    let month = parse_month(&payload.month)?;
 
     let report = service.generate_monthly_report(month).await?;
     Ok(HttpResponse::Ok().json(report))
 }
 
-/// GET /reports
+/// GET /reports?branch_id=&consolidated=true
 /// Fetch all generated reports
 pub async fn list_reports(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ReportsRead>,
+    branch: web::Query<BranchQuery>,
 ) -> Result<HttpResponse, AppError> {
-    let service = ReportsService::new(db.get_ref().clone());
+    let scope = ReportScope::from_branch(user.branch_scope(db.get_ref(), &branch).await?);
+    let service = ReportsService::new(db.get_ref().clone()).with_scope(scope);
     let reports = service.get_all_reports().await?;
     Ok(HttpResponse::Ok().json(reports))
 }
 
-/// GET /reports/{month}
-/// Fetch report for a specific month (YYYY-MM)
+/// GET /reports/{period}?format=json|csv|xlsx&branch_id=&consolidated=true
+/// Fetch the stored report of a month (YYYY-MM), or export any period (e.g. FY2026-Q2)
 pub async fn get_report_by_month(
     db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ReportsRead>,
+    path: web::Path<String>,
+    query: web::Query<ExportQuery>,
+    branch: web::Query<BranchQuery>,
+) -> Result<HttpResponse, AppError> {
+    let period = Period::parse(&path.into_inner())?;
+    let scope = ReportScope::from_branch(user.branch_scope(db.get_ref(), &branch).await?);
+    let service = ReportsService::new(db.get_ref().clone()).with_scope(scope);
+
+    let format = ExportFormat::parse(query.format.as_deref())?;
+    if format != ExportFormat::Json {
+        let file = service.export_period(&period, format).await?;
+        return Ok(file.into_response());
+    }
+
+    // Only monthly reports are stored; other periods are computed by /comparison
+    if !period.is_month() {
+        return Err(AppError::BadRequest(format!(
+            "Stored reports are monthly; use /reports/{}/comparison for this period",
+            period.name
+        )));
+    }
+
+    let report = service.get_report_by_month(period.from).await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// GET /reports/{period}/comparison?branch_id=&consolidated=true
+/// Compare a month (YYYY-MM) or fiscal period (FY2026, FY2026-Q2, FY2026-P05)
+/// with the period before it and the same period last year
+pub async fn get_month_comparison(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ReportsRead>,
+    path: web::Path<String>,
+    branch: web::Query<BranchQuery>,
+) -> Result<HttpResponse, AppError> {
+    let period = Period::parse(&path.into_inner())?;
+    let scope = ReportScope::from_branch(user.branch_scope(db.get_ref(), &branch).await?);
+    let service = ReportsService::new(db.get_ref().clone()).with_scope(scope);
+
+    let comparison = service.compare_period(period.from, period.to).await?;
+    Ok(HttpResponse::Ok().json(comparison))
+}
+
+/// GET /reports/{period}/breakdown?top=N&branch_id=&consolidated=true
+/// Expenses by label, income by service and the N largest expenses of a month or fiscal period
+pub async fn get_month_breakdown(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ReportsRead>,
     path: web::Path<String>,
+    query: web::Query<BreakdownQuery>,
+    branch: web::Query<BranchQuery>,
+) -> Result<HttpResponse, AppError> {
+    let period = Period::parse(&path.into_inner())?;
+    let scope = ReportScope::from_branch(user.branch_scope(db.get_ref(), &branch).await?);
+    let service = ReportsService::new(db.get_ref().clone()).with_scope(scope);
+
+    let breakdown = service
+        .breakdown_period(period.from, period.to, query.top.unwrap_or(DEFAULT_TOP_EXPENSES))
+        .await?;
+    Ok(HttpResponse::Ok().json(breakdown))
+}
+
+/// GET /reports/ytd?as_of=YYYY-MM-DD&branch_id=&consolidated=true
+/// Fiscal year to date against the same stretch of the previous fiscal year
+pub async fn get_year_to_date(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ReportsRead>,
+    query: web::Query<YearToDateQuery>,
+    branch: web::Query<BranchQuery>,
+) -> Result<HttpResponse, AppError> {
+    let as_of = match &query.as_of {
+        Some(date) => parse_date(date)?,
+        None => Utc::now().date_naive(),
+    };
+    let scope = ReportScope::from_branch(user.branch_scope(db.get_ref(), &branch).await?);
+    let service = ReportsService::new(db.get_ref().clone()).with_scope(scope);
+
+    let comparison = service.year_to_date(as_of).await?;
+    Ok(HttpResponse::Ok().json(comparison))
+}
+
+/// GET /reports/periods?fiscal_year=2026
+/// A fiscal year with its quarters and months
+pub async fn list_fiscal_periods(
+    _user: Authorized<require::ReportsRead>,
+    query: web::Query<FiscalPeriodsQuery>,
 ) -> Result<HttpResponse, AppError> {
-    let month_str = path.into_inner();
-    let service = ReportsService::new(db.get_ref().clone());
+    let fiscal_year = query
+        .fiscal_year
+        .unwrap_or_else(|| fiscal::fiscal_year_of(Utc::now().date_naive()));
+    let periods = Period::periods_of(fiscal_year)?;
 
-    // Parse YYYY-MM into NaiveDate (first day of month)
-    let month = NaiveDate::parse_from_str(&(month_str + "-01"), "%Y-%m-%d")?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "fiscal_year": fiscal_year,
+        "start_month": fiscal::start_month(),
+        "periods": periods
+    })))
+}
 
-    let report = service.get_report_by_month(month).await?;
+/// GET /reports/range?from=YYYY-MM-DD&to=YYYY-MM-DD&top=N&branch_id=&consolidated=true
+/// GET /reports/range?period=FY2026-Q2&top=N&branch_id=&consolidated=true
+/// Summarize a date range against the previous period and the same period last year
+pub async fn get_range_report(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ReportsRead>,
+    query: web::Query<RangeQuery>,
+    branch: web::Query<BranchQuery>,
+) -> Result<HttpResponse, AppError> {
+    let (from, to) = parse_range(query.from.as_deref(), query.to.as_deref(), query.period.as_deref())?;
+    let scope = ReportScope::from_branch(user.branch_scope(db.get_ref(), &branch).await?);
+    let service = ReportsService::new(db.get_ref().clone()).with_scope(scope);
+
+    let report = service
+        .range_report(from, to, query.top.unwrap_or(DEFAULT_TOP_EXPENSES))
+        .await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// GET /reports/staff?from=YYYY-MM-DD&to=YYYY-MM-DD&branch_id=&consolidated=true
+/// GET /reports/staff?period=FY2026-Q2&branch_id=&consolidated=true
+/// Orders, revenue and expenses entered and edits made per user
+pub async fn get_staff_activity(
+    db: web::Data<DatabaseConnection>,
+    user: Authorized<require::ReportsRead>,
+    query: web::Query<StaffActivityQuery>,
+    branch: web::Query<BranchQuery>,
+) -> Result<HttpResponse, AppError> {
+    let (from, to) = parse_range(query.from.as_deref(), query.to.as_deref(), query.period.as_deref())?;
+    let service = StaffActivityService::new(db.get_ref().clone());
+
+    let branch_id = user.branch_scope(db.get_ref(), &branch).await?;
+    let report = service.report(from, to, branch_id).await?;
     Ok(HttpResponse::Ok().json(report))
-}
\ No newline at end of file
+}
+
+/// GET /reports/{month}/status
+/// Whether the stored report of a month is fresh or waiting for recomputation
+pub async fn get_report_status(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::ReportsRead>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let month = parse_month(&path.into_inner())?;
+    let queue = ReportQueue::new(db.get_ref().clone());
+
+    let status = queue.status(month, config.report_max_attempts).await?;
+    Ok(HttpResponse::Ok().json(status))
+}
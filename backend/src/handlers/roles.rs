@@ -0,0 +1,33 @@
+use actix_web::{web, HttpResponse};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    middleware::auth::Authorized,
+    permissions::require,
+    services::roles::{AssignRoleRequest, RolesService},
+    errors::AppError,
+};
+
+/// GET /roles
+/// Roles with the permissions they grant
+pub async fn list_roles(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::UsersAdmin>,
+) -> Result<HttpResponse, AppError> {
+    let service = RolesService::new(db.get_ref().clone());
+    let roles = service.get_roles().await?;
+    Ok(HttpResponse::Ok().json(roles))
+}
+
+/// PUT /users/{id}/role
+/// Give a user another role
+pub async fn assign_role(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+    payload: web::Json<AssignRoleRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = RolesService::new(db.get_ref().clone());
+    let user = service.assign_role(path.into_inner(), payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
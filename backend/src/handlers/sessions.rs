@@ -0,0 +1,78 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    middleware::auth::{AuthenticatedUser, Authorized},
+    permissions::require,
+    services::sessions::{ClientInfo, RefreshRequest, SessionsService},
+    config::Config,
+    errors::AppError,
+};
+
+/// POST /refresh
+/// Trade a refresh token for a new access token and refresh token
+pub async fn refresh(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    http: HttpRequest,
+    payload: web::Json<RefreshRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = SessionsService::new(db.get_ref().clone(), &config);
+    let tokens = service
+        .refresh(&payload.refresh_token, ClientInfo::from_request(&http))
+        .await?;
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+/// POST /logout
+/// End the session of the access token used
+pub async fn logout(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let session_id = user.session()?;
+    let service = SessionsService::new(db.get_ref().clone(), &config);
+    service.revoke(session_id).await?;
+    Ok(HttpResponse::Ok().json("Logged out successfully"))
+}
+
+/// DELETE /users/{id}/sessions
+/// Sign a user out everywhere
+pub async fn revoke_user_sessions(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    _user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = SessionsService::new(db.get_ref().clone(), &config);
+    let revoked = service.revoke_all(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "revoked": revoked })))
+}
+
+/// GET /me/sessions
+/// Where the user is signed in
+pub async fn list_my_sessions(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let session_id = user.session()?;
+    let service = SessionsService::new(db.get_ref().clone(), &config);
+    let sessions = service.get_active(user.user_id, session_id).await?;
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+/// DELETE /me/sessions/{id}
+/// Sign one of the user's own sessions out
+pub async fn revoke_my_session(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    user.session()?;
+    let service = SessionsService::new(db.get_ref().clone(), &config);
+    service.revoke_own(user.user_id, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json("Session revoked successfully"))
+}
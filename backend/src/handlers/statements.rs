@@ -0,0 +1,123 @@
+use actix_web::{web, HttpResponse};
+use chrono::{NaiveDate, Utc};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+use crate::{
+    middleware::auth::Authorized,
+    permissions::require,
+    handlers::reports::{parse_date, parse_range},
+    services::statements::{self, StatementsService, SaveMappingRequest},
+    errors::AppError,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct StatementQuery {
+    pub from: Option<String>,   // YYYY-MM-DD
+    pub to: Option<String>,     // YYYY-MM-DD
+    pub period: Option<String>, // instead of from/to: YYYY-MM, FY2026, FY2026-Q2 or FY2026-P05
+    pub format: Option<String>, // json (default) or html
+}
+
+/// GET /statements/income?from=YYYY-MM-DD&to=YYYY-MM-DD&format=json|html
+/// GET /statements/income?period=FY2026-Q2&format=json|html
+/// Profit-and-loss statement for a date range or fiscal period
+pub async fn get_income_statement(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerRead>,
+    query: web::Query<StatementQuery>,
+) -> Result<HttpResponse, AppError> {
+    let (from, to) = parse_range(query.from.as_deref(), query.to.as_deref(), query.period.as_deref())?;
+    if from > to {
+        return Err(AppError::BadRequest("'from' must not be after 'to'".into()));
+    }
+    let service = StatementsService::new(db.get_ref().clone());
+
+    let statement = service.income_statement(from, to).await?;
+
+    match query.format.as_deref() {
+        None | Some("json") => Ok(HttpResponse::Ok().json(statement)),
+        Some("html") => Ok(HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(statements::render_income_statement_html(&statement))),
+        Some(other) => Err(AppError::BadRequest(format!(
+            "Unsupported format '{}', expected json or html",
+            other
+        ))),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AsOfQuery {
+    pub as_of: Option<String>, // YYYY-MM-DD, defaults to today
+}
+
+fn as_of_date(query: &AsOfQuery) -> Result<NaiveDate, AppError> {
+    match &query.as_of {
+        Some(date) => parse_date(date),
+        None => Ok(Utc::now().date_naive()),
+    }
+}
+
+/// GET /statements/balance-sheet?as_of=YYYY-MM-DD
+/// Assets, liabilities and equity as of a date
+pub async fn get_balance_sheet(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerRead>,
+    query: web::Query<AsOfQuery>,
+) -> Result<HttpResponse, AppError> {
+    let as_of = as_of_date(&query)?;
+    let service = StatementsService::new(db.get_ref().clone());
+
+    let balance_sheet = service.balance_sheet(as_of).await?;
+    Ok(HttpResponse::Ok().json(balance_sheet))
+}
+
+/// GET /cash-position?as_of=YYYY-MM-DD
+/// Cash and bank balances across all accounts, today unless a date is given
+pub async fn get_cash_position(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerRead>,
+    query: web::Query<AsOfQuery>,
+) -> Result<HttpResponse, AppError> {
+    let as_of = as_of_date(&query)?;
+    let service = StatementsService::new(db.get_ref().clone());
+
+    let cash_position = service.cash_position(as_of).await?;
+    Ok(HttpResponse::Ok().json(cash_position))
+}
+
+/// GET /statements/mappings
+/// Fetch the expense label → statement line mapping
+pub async fn list_mappings(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerRead>,
+) -> Result<HttpResponse, AppError> {
+    let service = StatementsService::new(db.get_ref().clone());
+    let mappings = service.get_mappings().await?;
+    Ok(HttpResponse::Ok().json(mappings))
+}
+
+/// PUT /statements/mappings
+/// Create or replace the mapping of an expense label
+pub async fn save_mapping(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerWrite>,
+    payload: web::Json<SaveMappingRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = StatementsService::new(db.get_ref().clone());
+    let mapping = service.save_mapping(payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(mapping))
+}
+
+/// DELETE /statements/mappings/{id}
+/// Delete a mapping
+pub async fn delete_mapping(
+    db: web::Data<DatabaseConnection>,
+    _user: Authorized<require::LedgerWrite>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = StatementsService::new(db.get_ref().clone());
+    service.delete_mapping(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json("Mapping deleted successfully"))
+}
@@ -0,0 +1,141 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    middleware::auth::{AuthenticatedUser, Authorized},
+    permissions::{require, Role},
+    services::sessions::ClientInfo,
+    services::two_factor::{CodeRequest, SetTwoFactorPolicyRequest, TwoFactorService},
+    services::users::{ChallengeRequest, TwoFactorLoginRequest, UserService},
+    config::Config,
+    mail::Mailer,
+    errors::AppError,
+};
+
+/// POST /login/2fa
+/// Second sign-in step with an authenticator or recovery code
+pub async fn verify_login(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    http: HttpRequest,
+    payload: web::Json<TwoFactorLoginRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = UserService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let auth = service
+        .verify_two_factor(payload.into_inner(), ClientInfo::from_request(&http))
+        .await?;
+    Ok(HttpResponse::Ok().json(auth))
+}
+
+/// POST /login/2fa/enroll
+/// Get a secret during sign-in when the role requires two-factor authentication
+pub async fn enroll_login(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    payload: web::Json<ChallengeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = UserService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let enrollment = service.enroll_two_factor(payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(enrollment))
+}
+
+/// POST /login/2fa/confirm
+/// Confirm the new secret and finish signing in
+pub async fn confirm_login(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    http: HttpRequest,
+    payload: web::Json<TwoFactorLoginRequest>,
+) -> Result<HttpResponse, AppError> {
+    let service = UserService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let enrolled = service
+        .confirm_two_factor(payload.into_inner(), ClientInfo::from_request(&http))
+        .await?;
+    Ok(HttpResponse::Ok().json(enrolled))
+}
+
+/// POST /me/2fa
+/// Start enrolling an authenticator app
+pub async fn begin_enrollment(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    user.session()?;
+    let service = TwoFactorService::new(db.get_ref().clone(), &config);
+    let enrollment = service.begin_enrollment(user.user_id).await?;
+    Ok(HttpResponse::Ok().json(enrollment))
+}
+
+/// POST /me/2fa/confirm
+/// Confirm a code from the new secret; returns the recovery codes
+pub async fn confirm_enrollment(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    user: AuthenticatedUser,
+    payload: web::Json<CodeRequest>,
+) -> Result<HttpResponse, AppError> {
+    user.session()?;
+    let service = TwoFactorService::new(db.get_ref().clone(), &config);
+    let codes = service.confirm_enrollment(user.user_id, &payload.code).await?;
+    Ok(HttpResponse::Ok().json(codes))
+}
+
+/// DELETE /me/2fa
+/// Turn two-factor authentication off with a current code
+pub async fn disable(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    user: AuthenticatedUser,
+    payload: web::Json<CodeRequest>,
+) -> Result<HttpResponse, AppError> {
+    user.session()?;
+    let service = TwoFactorService::new(db.get_ref().clone(), &config);
+    service.disable(user.user_id, &payload.code).await?;
+    Ok(HttpResponse::Ok().json("Two-factor authentication disabled successfully"))
+}
+
+/// POST /me/2fa/recovery-codes
+/// Replace the recovery codes
+pub async fn regenerate_recovery_codes(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    user: AuthenticatedUser,
+    payload: web::Json<CodeRequest>,
+) -> Result<HttpResponse, AppError> {
+    user.session()?;
+    let service = TwoFactorService::new(db.get_ref().clone(), &config);
+    let codes = service.regenerate_recovery_codes(user.user_id, &payload.code).await?;
+    Ok(HttpResponse::Ok().json(codes))
+}
+
+/// DELETE /users/{id}/2fa
+/// Remove a user's enrollment after they lost their device and recovery codes
+pub async fn reset_user(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    _user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = TwoFactorService::new(db.get_ref().clone(), &config);
+    service.reset(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json("Two-factor authentication reset successfully"))
+}
+
+/// PUT /roles/{role}/two-factor
+/// Require two-factor authentication for a role, or stop requiring it
+pub async fn set_role_policy(
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<Config>,
+    _user: Authorized<require::UsersAdmin>,
+    path: web::Path<String>,
+    payload: web::Json<SetTwoFactorPolicyRequest>,
+) -> Result<HttpResponse, AppError> {
+    let role = Role::parse(&path.into_inner())?;
+    let service = TwoFactorService::new(db.get_ref().clone(), &config);
+    service.set_required(role, payload.required).await?;
+    Ok(HttpResponse::Ok().json("Two-factor policy updated successfully"))
+}
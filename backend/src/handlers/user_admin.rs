@@ -0,0 +1,93 @@
+use actix_web::{web, HttpResponse};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    middleware::auth::Authorized,
+    permissions::require,
+    services::user_admin::{UserAdminService, UserListQuery},
+    config::Config,
+    mail::Mailer,
+    errors::AppError,
+};
+
+/// GET /users?include_deleted=true
+/// Users with their status and last sign-in
+pub async fn list_users(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    _user: Authorized<require::UsersAdmin>,
+    query: web::Query<UserListQuery>,
+) -> Result<HttpResponse, AppError> {
+    let service = UserAdminService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let users = service.get_users(query.include_deleted.unwrap_or(false)).await?;
+    Ok(HttpResponse::Ok().json(users))
+}
+
+/// GET /users/{id}
+pub async fn get_user(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    _user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = UserAdminService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let user = service.get_user(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// POST /users/{id}/deactivate
+/// Stop a user from signing in and end their sessions
+pub async fn deactivate_user(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = UserAdminService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let user = service.deactivate(path.into_inner(), user.user_id).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// POST /users/{id}/reactivate
+pub async fn reactivate_user(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    _user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = UserAdminService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let user = service.reactivate(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// POST /users/{id}/password-reset
+/// Invalidate a user's password and send them a reset token
+pub async fn force_password_reset(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    _user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = UserAdminService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let reset = service.force_password_reset(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(reset))
+}
+
+/// DELETE /users/{id}
+/// Soft-delete a user; their orders and expenses keep naming them
+pub async fn delete_user(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    user: Authorized<require::UsersAdmin>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let service = UserAdminService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    service.delete_user(path.into_inner(), user.user_id).await?;
+    Ok(HttpResponse::Ok().json("User deleted successfully"))
+}
@@ -1,19 +1,21 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use sea_orm::DatabaseConnection;
 use serde::Deserialize;
 use crate::{
-    services::users::{UserService, RegisterRequest as ServiceRegisterRequest, LoginRequest as ServiceLoginRequest, ForgotPasswordRequest, UserResponse},
-    entities::users,
+    services::sessions::ClientInfo,
+    services::users::{UserService, RegisterRequest as ServiceRegisterRequest, LoginRequest as ServiceLoginRequest, ForgotPasswordRequest, ResetPasswordRequest, ChangePasswordRequest, UpdateProfileRequest},
     errors::AppError,
 };
 use crate::middleware::auth::AuthenticatedUser;
 use crate::config::Config;
+use crate::mail::Mailer;
 
 #[derive(Debug, Deserialize)]
 pub struct RegisterRequest {
     pub username: String,
     pub password: String,
-    pub registration_code: String,
+    pub invitation_code: Option<String>,
+    pub registration_code: Option<String>, // first account only
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,27 +26,34 @@ pub struct LoginRequest {
 
 #[derive(Deserialize)]
 pub struct ForgotPasswordPayload {
-    pub username: String,
-    pub registration_code: String,
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordPayload {
+    pub token: String,
     pub new_password: String,
 }
 
 /// POST /register
 pub async fn register(
     db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    http: HttpRequest,
     payload: web::Json<RegisterRequest>,
 ) -> Result<HttpResponse, AppError> {
     let config = Config::from_env().map_err(|_| AppError::InternalError)?; // Load env
-    let service = UserService::new(db.get_ref().clone(), config.jwt_secret.clone());
+    let service = UserService::new(db.get_ref().clone(), &config, mailer.into_inner());
 
     // Create a ServiceRegisterRequest struct
     let req = ServiceRegisterRequest {
         username: payload.username.clone(),
         password: payload.password.clone(),
+        invitation_code: payload.invitation_code.clone(),
         registration_code: payload.registration_code.clone(),
     };
 
-    let auth_response = service.register_user(req).await?;
+    let auth_response = service.register_user(req, ClientInfo::from_request(&http)).await?;
 
     Ok(HttpResponse::Created().json(auth_response))
 }
@@ -52,17 +61,19 @@ pub async fn register(
 /// POST /login
 pub async fn login(
     db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    http: HttpRequest,
     payload: web::Json<LoginRequest>,
 ) -> Result<HttpResponse, AppError> {
     let config = Config::from_env().map_err(|_| AppError::InternalError)?;
-    let service = UserService::new(db.get_ref().clone(), config.jwt_secret.clone());
+    let service = UserService::new(db.get_ref().clone(), &config, mailer.into_inner());
 
     let req = ServiceLoginRequest {
         username: payload.username.clone(),
         password: payload.password.clone(),
     };
 
-    let auth_response = service.login_user(req).await?;
+    let auth_response = service.login_user(req, ClientInfo::from_request(&http)).await?;
 
     Ok(HttpResponse::Ok().json(auth_response))
 }
@@ -71,37 +82,81 @@ pub async fn login(
 pub async fn get_me(
     user: AuthenticatedUser,
     db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
 ) -> Result<HttpResponse, AppError> {
-    use sea_orm::EntityTrait;
-
-    let user_data = users::Entity::find_by_id(user.user_id)
-        .one(db.get_ref())
-        .await?
-        .ok_or(AppError::NotFound("User not found".into()))?;
+    let service = UserService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let profile = service.get_profile(user.user_id).await?;
+    Ok(HttpResponse::Ok().json(profile))
+}
 
-    let response = UserResponse {
-        user_id: user_data.user_id,
-        username: user_data.username,
-    };
+/// PUT /me
+/// Update the display name and email
+pub async fn update_me(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    payload: web::Json<UpdateProfileRequest>,
+) -> Result<HttpResponse, AppError> {
+    user.session()?;
+    let service = UserService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    let profile = service.update_profile(user.user_id, payload.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(profile))
+}
 
-    Ok(HttpResponse::Ok().json(response))
+/// PUT /me/password
+/// Change the password; other sessions are signed out
+pub async fn change_password(
+    user: AuthenticatedUser,
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    config: web::Data<Config>,
+    http: HttpRequest,
+    payload: web::Json<ChangePasswordRequest>,
+) -> Result<HttpResponse, AppError> {
+    let session_id = user.session()?;
+    let service = UserService::new(db.get_ref().clone(), &config, mailer.into_inner());
+    service
+        .change_password(user.user_id, session_id, payload.into_inner(), ClientInfo::from_request(&http))
+        .await?;
+    Ok(HttpResponse::Ok().json("Password changed successfully"))
 }
 
 /// POST /forgot-password
 pub async fn forgot_password(
     db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    http: HttpRequest,
     payload: web::Json<ForgotPasswordPayload>,
 ) -> Result<HttpResponse, AppError> {
     let config = Config::from_env().map_err(|_| AppError::InternalError)?;
-    let service = UserService::new(db.get_ref().clone(), config.jwt_secret.clone());
+    let service = UserService::new(db.get_ref().clone(), &config, mailer.into_inner());
 
     let req = ForgotPasswordRequest {
-        username: payload.username.clone(),
-        registration_code: payload.registration_code.clone(),
+        email: payload.email.clone(),
+    };
+
+    service.forgot_password(req, ClientInfo::from_request(&http)).await?;
+
+    Ok(HttpResponse::Ok().json("If the email belongs to an account, a reset token has been sent"))
+}
+
+/// POST /reset-password
+pub async fn reset_password(
+    db: web::Data<DatabaseConnection>,
+    mailer: web::Data<dyn Mailer>,
+    payload: web::Json<ResetPasswordPayload>,
+) -> Result<HttpResponse, AppError> {
+    let config = Config::from_env().map_err(|_| AppError::InternalError)?;
+    let service = UserService::new(db.get_ref().clone(), &config, mailer.into_inner());
+
+    let req = ResetPasswordRequest {
+        token: payload.token.clone(),
         new_password: payload.new_password.clone(),
     };
 
-    service.forgot_password(req).await?;
+    service.reset_password(req).await?;
 
     Ok(HttpResponse::Ok().json("Password reset successfully"))
 }
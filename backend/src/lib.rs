@@ -0,0 +1,15 @@
+pub mod config;
+pub mod db;
+pub mod errors;
+pub mod money;
+pub mod fiscal;
+pub mod permissions;
+pub mod password_policy;
+pub mod tokens;
+pub mod mail;
+pub mod totp;
+pub mod routes;
+pub mod middleware;
+pub mod handlers;
+pub mod services;
+pub mod entities;
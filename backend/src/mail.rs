@@ -0,0 +1,228 @@
+//! Outgoing email. Every message is rendered from a template in `templates/email`
+//! and handed to the configured `Mailer`: SMTP in production, a directory of `.eml`
+//! files or stdout in development. `MAIL_TRANSPORT` picks one at startup and has no
+//! default, so a server never silently prints its mail; services get the mailer as
+//! `web::Data<dyn Mailer>`.
+
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::Config;
+use crate::errors::AppError;
+
+/// A rendered message to one recipient
+#[derive(Clone, Debug)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), AppError>;
+}
+
+fn build_message(from: &Mailbox, email: &Email) -> Result<Message, AppError> {
+    let to: Mailbox = email
+        .to
+        .parse()
+        .map_err(|_| AppError::BadRequest(format!("Invalid email address '{}'", email.to)))?;
+
+    Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(email.subject.clone())
+        .header(ContentType::TEXT_PLAIN)
+        .body(email.body.clone())
+        .map_err(|e| {
+            tracing::error!("Failed to build email to {}: {}", email.to, e);
+            AppError::InternalError
+        })
+}
+
+/// Sends through an SMTP relay
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &Config, from: Mailbox) -> Result<Self, String> {
+        let host = config
+            .smtp_host
+            .as_deref()
+            .ok_or("SMTP_HOST must be set when MAIL_TRANSPORT is smtp")?;
+
+        let mut builder = if config.smtp_starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(|e| e.to_string())?
+        } else {
+            // Only for a relay on a trusted network, such as a local test server
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        };
+        builder = builder.port(config.smtp_port);
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(SmtpMailer { from, transport: builder.build() })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<(), AppError> {
+        let message = build_message(&self.from, &email)?;
+        self.transport.send(message).await.map_err(|e| {
+            tracing::error!("Failed to send email to {}: {}", email.to, e);
+            AppError::InternalError
+        })?;
+        Ok(())
+    }
+}
+
+/// Writes every message to a `.eml` file in a directory
+pub struct FileMailer {
+    from: Mailbox,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>, from: Mailbox) -> Self {
+        FileMailer { from, dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: Email) -> Result<(), AppError> {
+        let message = build_message(&self.from, &email)?;
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            uuid::Uuid::new_v4()
+        ));
+
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(&path, message.formatted()))
+            .map_err(|e| {
+                tracing::error!("Failed to write email to {}: {}", path.display(), e);
+                AppError::InternalError
+            })?;
+        tracing::info!("Wrote email to {} at {}", email.to, path.display());
+        Ok(())
+    }
+}
+
+/// Prints every message to stdout
+pub struct StdoutMailer {
+    from: Mailbox,
+}
+
+impl StdoutMailer {
+    pub fn new(from: Mailbox) -> Self {
+        StdoutMailer { from }
+    }
+}
+
+#[async_trait]
+impl Mailer for StdoutMailer {
+    async fn send(&self, email: Email) -> Result<(), AppError> {
+        let message = build_message(&self.from, &email)?;
+        println!("{}", String::from_utf8_lossy(&message.formatted()));
+        Ok(())
+    }
+}
+
+static APP_NAME: OnceLock<String> = OnceLock::new();
+
+/// Name messages use for the application when `APP_NAME` is not configured
+pub const DEFAULT_APP_NAME: &str = "Financial Tracker";
+
+/// Build the mailer `MAIL_TRANSPORT` names; called once at startup
+pub fn init_mailer(config: &Config) -> Result<Arc<dyn Mailer>, String> {
+    let from: Mailbox = config
+        .mail_from
+        .parse()
+        .map_err(|_| format!("MAIL_FROM '{}' is not a valid address", config.mail_from))?;
+
+    let mailer: Arc<dyn Mailer> = match config.mail_transport.as_str() {
+        "smtp" => Arc::new(SmtpMailer::new(config, from)?),
+        "file" => Arc::new(FileMailer::new(&config.mail_file_dir, from)),
+        "stdout" => Arc::new(StdoutMailer::new(from)),
+        other => {
+            return Err(format!("Unsupported MAIL_TRANSPORT '{}', expected smtp, file or stdout", other));
+        }
+    };
+    let _ = APP_NAME.set(config.app_name.clone());
+    Ok(mailer)
+}
+
+impl dyn Mailer {
+    /// Render a template and send it
+    pub async fn send_template(&self, template: Template, to: &str, vars: &[(&str, &str)]) -> Result<(), AppError> {
+        self.send(template.render(to, vars)?).await
+    }
+}
+
+/// Templates of every message the backend sends
+#[derive(Clone, Copy, Debug)]
+pub enum Template {
+    /// `code`, `minutes`
+    RegistrationCodeReset,
+    /// `username`, `token`, `minutes`
+    PasswordReset,
+    /// `role`, `code`, `expires_at`
+    Invitation,
+}
+
+impl Template {
+    fn source(&self) -> &'static str {
+        match self {
+            Template::RegistrationCodeReset => include_str!("../templates/email/registration_code_reset.txt"),
+            Template::PasswordReset => include_str!("../templates/email/password_reset.txt"),
+            Template::Invitation => include_str!("../templates/email/invitation.txt"),
+        }
+    }
+
+    /// Fill in the `{{name}}` placeholders; `app_name` is always available.
+    /// The first line of a template is its `Subject:` header.
+    pub fn render(&self, to: &str, vars: &[(&str, &str)]) -> Result<Email, AppError> {
+        let app_name = APP_NAME.get().map(String::as_str).unwrap_or(DEFAULT_APP_NAME);
+        let mut vars = vars.to_vec();
+        vars.push(("app_name", app_name));
+
+        // Placeholders are looked up in the template itself, so values can't inject new ones
+        let mut text = String::new();
+        let mut rest = self.source();
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..].find("}}").map(|end| start + end).ok_or(AppError::InternalError)?;
+            let name = &rest[start + 2..end];
+            let Some((_, value)) = vars.iter().find(|(var, _)| *var == name) else {
+                tracing::error!("Email template {:?} has no value for '{}'", self, name);
+                return Err(AppError::InternalError);
+            };
+            text.push_str(&rest[..start]);
+            text.push_str(value);
+            rest = &rest[end + 2..];
+        }
+        text.push_str(rest);
+
+        let (subject, body) = text
+            .split_once('\n')
+            .and_then(|(first, rest)| Some((first.strip_prefix("Subject:")?, rest)))
+            .ok_or(AppError::InternalError)?;
+
+        Ok(Email {
+            to: to.to_string(),
+            subject: subject.trim().to_string(),
+            body: body.trim_start_matches('\n').to_string(),
+        })
+    }
+}
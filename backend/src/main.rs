@@ -1,21 +1,15 @@
-mod config;
-mod db;
-mod errors;
-mod routes;
-mod middleware;
-mod handlers;
-mod services;
-mod entities;
-
 use actix_web::{App, HttpServer, web, middleware::Logger};
 use actix_cors::Cors;
 use dotenvy::dotenv;
-use std::env;
 use tracing_subscriber::FmtSubscriber;
 
-use crate::config::Config;
-use crate::db::connect;
-use crate::routes::config as route_config;
+use backend::config::Config;
+use backend::db::{connect, connect_quiet};
+use backend::fiscal;
+use backend::mail;
+use backend::money;
+use backend::routes::config as route_config;
+use backend::services::report_queue;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -31,14 +25,23 @@ async fn main() -> std::io::Result<()> {
 
     // Load configuration
     let config = Config::from_env().expect("Failed to load config");
+    money::init_base_currency(&config.base_currency);
+    fiscal::init_start_month(config.fiscal_year_start_month);
+    let mailer = mail::init_mailer(&config).expect("Failed to set up the mailer");
 
     // 
     let db = connect(&config).await;
     tracing::info!("Connected to database");
 
+    // Recompute reports of edited months in the background, on a connection of its
+    // own that doesn't log every poll of the queue
+    let worker_db = connect_quiet(&config).await;
+    actix_web::rt::spawn(report_queue::run_worker(worker_db, config.clone()));
+
     // Wrap in Actix `Data` for shared state
     let db_data = web::Data::new(db);
     let config_data = web::Data::new(config.clone());
+    let mailer_data: web::Data<dyn mail::Mailer> = web::Data::from(mailer);
 
     HttpServer::new(move || {
         let cors = Cors::permissive();
@@ -48,6 +51,7 @@ async fn main() -> std::io::Result<()> {
             .wrap(cors)
             .app_data(db_data.clone())
             .app_data(config_data.clone())
+            .app_data(mailer_data.clone())
             .configure(route_config)
     })
     .bind(("127.0.0.1", 8080))?
@@ -1,20 +1,136 @@
-use actix_web::{dev::Payload, Error, FromRequest, HttpRequest};
-use futures::future::{ready, Ready};
+use actix_web::{dev::Payload, web, Error, FromRequest, HttpRequest};
 use jsonwebtoken::{decode, DecodingKey, Validation};
+use sea_orm::{DatabaseConnection, EntityTrait};
 use serde::{Deserialize, Serialize};
 use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::Deref;
 use std::pin::Pin;
 use crate::config::Config;
+use crate::entities::users;
 use crate::errors::AppError;
+use crate::permissions::{Permission, RequiredPermission, Role};
+use crate::services::branches::{BranchQuery, BranchesService};
+use crate::services::api_keys::{self, KEY_PREFIX};
+use crate::services::sessions;
+use crate::services::user_admin::is_active;
+
+/// Header selecting the branch a request works in; defaults to the user's default branch
+pub const BRANCH_HEADER: &str = "X-Branch-Id";
+
+/// Header carrying an API key; a key can also be sent as the bearer token
+pub const API_KEY_HEADER: &str = "X-Api-Key";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub user_id: i32,
+    pub session_id: i32,
     pub exp: usize,
 }
 
 pub struct AuthenticatedUser {
+    /// The signed-in user, or the service account of the API key used
     pub user_id: i32,
+    /// Session the access token belongs to; `None` for API keys
+    pub session_id: Option<i32>,
+    /// API key the request was made with
+    pub api_key_id: Option<i32>,
+    /// Branch the request works in; `None` when the user isn't assigned to any
+    pub branch_id: Option<i32>,
+    pub role: Role,
+    /// Permissions of the API key, which apply instead of the role's
+    key_permissions: Option<Vec<Permission>>,
+}
+
+impl AuthenticatedUser {
+    /// The active branch, required by anything that records branch data
+    pub fn active_branch(&self) -> Result<i32, AppError> {
+        self.branch_id
+            .ok_or_else(|| AppError::BadRequest("You are not assigned to any branch".into()))
+    }
+
+    /// Branch filter of a list or report: the active branch unless `?branch_id=` picks another
+    /// one the user is assigned to. Other branches and `?consolidated=true` need `branches:all`.
+    /// `None` means every branch.
+    pub async fn branch_scope(&self, db: &DatabaseConnection, query: &BranchQuery) -> Result<Option<i32>, AppError> {
+        if query.consolidated {
+            if query.branch_id.is_some() {
+                return Err(AppError::BadRequest("Give either 'branch_id' or 'consolidated', not both".into()));
+            }
+            self.require(Permission::BranchesAll)?;
+            return Ok(None);
+        }
+
+        let Some(branch_id) = query.branch_id else {
+            return self.active_branch().map(Some);
+        };
+        if self.branch_id == Some(branch_id) {
+            return Ok(Some(branch_id));
+        }
+
+        let branches = BranchesService::new(db.clone());
+        if self.require(Permission::BranchesAll).is_ok() {
+            branches.find_branch(branch_id).await?;
+        } else if !branches.is_assigned(self.user_id, branch_id).await? {
+            return Err(AppError::Forbidden(format!("You are not assigned to branch {}", branch_id)));
+        }
+        Ok(Some(branch_id))
+    }
+
+    /// Branch filter of a lookup by ID: the active branch, or none with `branches:all`
+    pub fn record_scope(&self) -> Result<Option<i32>, AppError> {
+        if self.require(Permission::BranchesAll).is_ok() {
+            return Ok(None);
+        }
+        self.active_branch().map(Some)
+    }
+
+    /// Fail with `403 Forbidden` unless the user's role grants `permission`
+    pub fn require(&self, permission: Permission) -> Result<(), AppError> {
+        let allowed = match &self.key_permissions {
+            Some(permissions) => permissions.contains(&permission),
+            None => self.role.allows(permission),
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!("Missing permission '{}'", permission.as_str())))
+        }
+    }
+
+    /// The session of a person who signed in; account self-service isn't open to API keys
+    pub fn session(&self) -> Result<i32, AppError> {
+        self.session_id
+            .ok_or_else(|| AppError::Forbidden("This needs a signed-in user, not an API key".into()))
+    }
+}
+
+/// An authenticated user whose role grants `P`; rejects everyone else with `403 Forbidden`
+pub struct Authorized<P: RequiredPermission> {
+    pub user: AuthenticatedUser,
+    permission: PhantomData<P>,
+}
+
+impl<P: RequiredPermission> Deref for Authorized<P> {
+    type Target = AuthenticatedUser;
+
+    fn deref(&self) -> &AuthenticatedUser {
+        &self.user
+    }
+}
+
+impl<P: RequiredPermission + 'static> FromRequest for Authorized<P> {
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload);
+        Box::pin(async move {
+            let user = user.await?;
+            user.require(P::PERMISSION)?;
+            Ok(Authorized { user, permission: PhantomData })
+        })
+    }
 }
 
 impl FromRequest for AuthenticatedUser {
@@ -22,40 +138,95 @@ impl FromRequest for AuthenticatedUser {
     type Future = Pin<Box<dyn Future<Output = Result<Self, Error>>>>;
 
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        // Clone the app data so we can use it inside async
-        let config = req.app_data::<actix_web::web::Data<Config>>().cloned();
+        // Copy out everything the future needs; it can't borrow the request
+        let config = req.app_data::<web::Data<Config>>().cloned();
+        let db = req.app_data::<web::Data<DatabaseConnection>>().cloned();
+        let auth_header = req
+            .headers()
+            .get("Authorization")
+            .and_then(|h| h.to_str().ok())
+            .map(str::to_string);
+        let api_key_header = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.trim().to_string());
+        let branch_header = req
+            .headers()
+            .get(BRANCH_HEADER)
+            .map(|h| h.to_str().ok().and_then(|value| value.trim().parse::<i32>().ok()));
 
         Box::pin(async move {
-            let config = match config {
-                Some(c) => c,
-                None => return Err(AppError::Unauthorized.into()),
+            let (config, db) = match (config, db) {
+                (Some(config), Some(db)) => (config, db),
+                _ => return Err(AppError::Unauthorized.into()),
             };
 
-            let auth_header = req
-                .headers()
-                .get("Authorization")
-                .and_then(|h| h.to_str().ok())
-                .unwrap_or("");
-
             // Expect header format: "Bearer <token>"
-            if !auth_header.starts_with("Bearer ") {
-                return Err(AppError::Unauthorized.into());
-            }
+            let bearer = auth_header
+                .as_deref()
+                .and_then(|h| h.strip_prefix("Bearer "))
+                .map(|token| token.trim().to_string());
+            let api_key = api_key_header.or_else(|| bearer.clone().filter(|token| token.starts_with(KEY_PREFIX)));
 
-            let token = auth_header.trim_start_matches("Bearer ").trim();
+            let requested = match branch_header {
+                Some(Some(branch_id)) => Some(branch_id),
+                Some(None) => {
+                    return Err(AppError::BadRequest(format!("{} must be a branch ID", BRANCH_HEADER)).into());
+                }
+                None => None,
+            };
 
-            let decoding_key = DecodingKey::from_secret(config.jwt_secret.as_bytes());
+            let (user_id, session_id, api_key_id, key_permissions) = match api_key {
+                Some(key) => match api_keys::authenticate(db.get_ref(), &key).await {
+                    Ok(Some(api_key)) => (
+                        api_key.user_id,
+                        None,
+                        Some(api_key.api_key_id),
+                        Some(api_keys::permissions(&api_key)),
+                    ),
+                    Ok(None) => return Err(AppError::Unauthorized.into()),
+                    Err(e) => return Err(e.into()),
+                },
+                None => {
+                    let Some(token) = bearer else {
+                        return Err(AppError::Unauthorized.into());
+                    };
+                    let decoding_key = DecodingKey::from_secret(config.jwt_secret.as_bytes());
+                    let claims = match decode::<Claims>(&token, &decoding_key, &Validation::default()) {
+                        Ok(data) => data.claims,
+                        Err(_) => return Err(AppError::Unauthorized.into()),
+                    };
 
-            match decode::<Claims>(
-                token,
-                &decoding_key,
-                &Validation::default(),
-            ) {
-                Ok(data) => Ok(AuthenticatedUser {
-                    user_id: data.claims.user_id,
-                }),
-                Err(_) => Err(AppError::Unauthorized.into()),
-            }
+                    // Logging out or revoking a session has to stop its access token right away
+                    match sessions::is_active(db.get_ref(), claims.session_id, claims.user_id).await {
+                        Ok(true) => {}
+                        Ok(false) => return Err(AppError::Unauthorized.into()),
+                        Err(e) => return Err(e.into()),
+                    }
+                    (claims.user_id, Some(claims.session_id), None, None)
+                }
+            };
+
+            // The role and status are read on every request so a change applies at once
+            let user = match users::Entity::find_by_id(user_id).one(db.get_ref()).await {
+                Ok(Some(user)) if is_active(&user) => user,
+                Ok(_) => return Err(AppError::Unauthorized.into()),
+                Err(e) => return Err(AppError::from(e).into()),
+            };
+
+            let branch_id = BranchesService::new(db.get_ref().clone())
+                .active_branch(user_id, requested)
+                .await?;
+
+            Ok(AuthenticatedUser {
+                user_id,
+                session_id,
+                api_key_id,
+                branch_id,
+                role: Role::from_column(&user.role),
+                key_permissions,
+            })
         })
     }
-}
\ No newline at end of file
+}
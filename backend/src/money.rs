@@ -0,0 +1,267 @@
+//! Exact money amounts.
+//!
+//! `Money` wraps a `Decimal` that always holds whole cents:
+//! - amounts coming from clients must have at most two decimal places and are rejected otherwise;
+//! - computed amounts (shares, conversions) are rounded half away from zero to cents;
+//! - sums and differences are exact and never need rounding;
+//! - JSON output is a string with exactly two decimals, e.g. `"1250.00"`.
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use sea_orm::prelude::Decimal;
+use sea_orm::sea_query::{ArrayType, ColumnType, Nullable, ValueType, ValueTypeErr};
+use sea_orm::{ColIdx, QueryResult, TryGetError, TryGetable, Value};
+use rust_decimal::RoundingStrategy;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Currency used when `BASE_CURRENCY` is not configured
+pub const DEFAULT_CURRENCY: &str = "PHP";
+
+static BASE_CURRENCY: OnceLock<String> = OnceLock::new();
+
+/// Set the currency every stored amount is expressed in; called once at startup
+pub fn init_base_currency(code: &str) {
+    let _ = BASE_CURRENCY.set(code.to_ascii_uppercase());
+}
+
+/// ISO 4217 code of the base currency
+pub fn base_currency() -> &'static str {
+    BASE_CURRENCY.get().map(String::as_str).unwrap_or(DEFAULT_CURRENCY)
+}
+
+const CENTS: u32 = 2;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(Decimal);
+
+impl Money {
+    pub const ZERO: Money = Money(Decimal::ZERO);
+
+    /// Round a computed value to cents (half away from zero)
+    pub fn round(value: Decimal) -> Self {
+        Money(value.round_dp_with_strategy(CENTS, RoundingStrategy::MidpointAwayFromZero))
+    }
+
+    /// Accept a value only if it is already in whole cents
+    pub fn exact(value: Decimal) -> Option<Self> {
+        (value.normalize().scale() <= CENTS).then(|| Money::round(value))
+    }
+
+    /// Money from whole cents, e.g. `Money::from_cents(1250)` is 12.50
+    pub fn from_cents(cents: i64) -> Self {
+        Money(Decimal::new(cents, CENTS))
+    }
+
+    /// Total of a SQL `SUM`, which is `NULL` when no rows matched
+    pub fn from_sum(sum: Option<Money>) -> Self {
+        sum.unwrap_or(Money::ZERO)
+    }
+
+    /// Convert at an exchange rate (units of the target currency per unit of this one)
+    pub fn at_rate(&self, rate: Decimal) -> Self {
+        Money::round(self.0 * rate)
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0.is_sign_negative() && !self.0.is_zero()
+    }
+
+    pub fn abs(&self) -> Self {
+        Money(self.0.abs())
+    }
+
+    /// `self` as a percentage of `total`, two decimals; `None` when `total` is zero
+    pub fn percentage_of(&self, total: Money) -> Option<Decimal> {
+        if total.is_zero() {
+            return None;
+        }
+        Some((self.0 / total.0 * Decimal::ONE_HUNDRED).round_dp_with_strategy(CENTS, RoundingStrategy::MidpointAwayFromZero))
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2}", self.0)
+    }
+}
+
+impl FromStr for Money {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = Decimal::from_str(s.trim()).map_err(|_| format!("'{}' is not a valid amount", s))?;
+        Money::exact(value).ok_or_else(|| format!("'{}' has more than two decimal places", s))
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Accepts `"12.50"` as well as `12.5`; more than two decimals is an error
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <Decimal as Deserialize>::deserialize(deserializer)?;
+        Money::exact(value).ok_or_else(|| {
+            de::Error::custom(format!("amount {} has more than two decimal places", value))
+        })
+    }
+}
+
+// Stored as DECIMAL(12, 2). SQLite hands decimals back as floats, so reads round to cents.
+
+impl From<Money> for Value {
+    fn from(money: Money) -> Self {
+        Value::Decimal(Some(Box::new(money.0)))
+    }
+}
+
+impl Nullable for Money {
+    fn null() -> Value {
+        Value::Decimal(None)
+    }
+}
+
+impl TryGetable for Money {
+    fn try_get_by<I: ColIdx>(res: &QueryResult, idx: I) -> Result<Self, TryGetError> {
+        Decimal::try_get_by(res, idx).map(Money::round)
+    }
+}
+
+impl ValueType for Money {
+    fn try_from(v: Value) -> Result<Self, ValueTypeErr> {
+        <Decimal as ValueType>::try_from(v).map(Money::round)
+    }
+
+    fn type_name() -> String {
+        "Money".to_owned()
+    }
+
+    fn array_type() -> ArrayType {
+        ArrayType::Decimal
+    }
+
+    fn column_type() -> ColumnType {
+        ColumnType::Decimal(Some((12, 2)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn round_goes_half_away_from_zero() {
+        assert_eq!(Money::round(dec("1.005")), Money::from_cents(101));
+        assert_eq!(Money::round(dec("1.004")), Money::from_cents(100));
+        assert_eq!(Money::round(dec("-1.005")), Money::from_cents(-101));
+        assert_eq!(Money::round(dec("2.675")), Money::from_cents(268));
+    }
+
+    #[test]
+    fn exact_rejects_fractions_of_cents() {
+        assert_eq!(Money::exact(dec("12.5")), Some(Money::from_cents(1250)));
+        assert_eq!(Money::exact(dec("12.500")), Some(Money::from_cents(1250)));
+        assert_eq!(Money::exact(dec("12.505")), None);
+    }
+
+    #[test]
+    fn at_rate_rounds_the_converted_amount() {
+        let amount = Money::from_cents(10000);
+        assert_eq!(amount.at_rate(dec("56.1234")), Money::from_cents(561234));
+        assert_eq!(Money::from_cents(333).at_rate(dec("0.5")), Money::from_cents(167));
+        assert_eq!(Money::from_cents(-333).at_rate(dec("0.5")), Money::from_cents(-167));
+    }
+
+    #[test]
+    fn percentage_of_zero_total_is_none() {
+        assert_eq!(Money::from_cents(100).percentage_of(Money::ZERO), None);
+        assert_eq!(
+            Money::from_cents(100).percentage_of(Money::from_cents(300)),
+            Some(dec("33.33"))
+        );
+    }
+
+    #[test]
+    fn serializes_with_two_decimals() {
+        assert_eq!(serde_json::to_string(&Money::from_cents(1250)).unwrap(), "\"12.50\"");
+        assert_eq!(serde_json::to_string(&Money::from_cents(-5)).unwrap(), "\"-0.05\"");
+        assert_eq!(serde_json::to_string(&Money::ZERO).unwrap(), "\"0.00\"");
+    }
+
+    #[test]
+    fn deserializes_strings_and_numbers() {
+        assert_eq!(serde_json::from_str::<Money>("\"12.50\"").unwrap(), Money::from_cents(1250));
+        assert_eq!(serde_json::from_str::<Money>("12.5").unwrap(), Money::from_cents(1250));
+        assert!(serde_json::from_str::<Money>("\"12.505\"").is_err());
+        assert!(serde_json::from_str::<Money>("\"abc\"").is_err());
+    }
+
+    #[test]
+    fn parses_from_str() {
+        assert_eq!(" 7.10 ".parse::<Money>(), Ok(Money::from_cents(710)));
+        assert!("7.101".parse::<Money>().is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Rules a new password has to meet, wherever one is set: registration,
+//! password reset and password change. Configured through `PASSWORD_*` variables.
+
+use crate::config::Config;
+use crate::errors::AppError;
+
+#[derive(Clone, Debug)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_mixed_case: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+impl PasswordPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        PasswordPolicy {
+            min_length: config.password_min_length,
+            require_mixed_case: config.password_require_mixed_case,
+            require_digit: config.password_require_digit,
+            require_symbol: config.password_require_symbol,
+        }
+    }
+
+    /// Fail with `400 Bad Request` listing everything the password is missing
+    pub fn check(&self, password: &str, username: &str) -> Result<(), AppError> {
+        let mut problems = Vec::new();
+
+        if password.chars().count() < self.min_length {
+            problems.push(format!("be at least {} characters long", self.min_length));
+        }
+        if self.require_mixed_case
+            && !(password.chars().any(char::is_lowercase) && password.chars().any(char::is_uppercase))
+        {
+            problems.push("contain upper and lower case letters".to_string());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            problems.push("contain a digit".to_string());
+        }
+        if self.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            problems.push("contain a symbol".to_string());
+        }
+        if !username.is_empty() && password.to_lowercase().contains(&username.to_lowercase()) {
+            problems.push("not contain the username".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::BadRequest(format!("The password must {}", problems.join(", "))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 12,
+            require_mixed_case: true,
+            require_digit: true,
+            require_symbol: true,
+        }
+    }
+
+    fn length_only(min_length: usize) -> PasswordPolicy {
+        PasswordPolicy {
+            min_length,
+            require_mixed_case: false,
+            require_digit: false,
+            require_symbol: false,
+        }
+    }
+
+    fn problems(policy: &PasswordPolicy, password: &str, username: &str) -> String {
+        match policy.check(password, username) {
+            Err(AppError::BadRequest(message)) => message,
+            Err(_) => panic!("Expected a bad request"),
+            Ok(()) => String::new(),
+        }
+    }
+
+    #[test]
+    fn accepts_a_password_meeting_every_rule() {
+        assert!(strict().check("Correct-Horse-9", "alice").is_ok());
+    }
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        let policy = length_only(4);
+        assert!(policy.check("äöüß", "").is_ok());
+        assert!(policy.check("äöü", "").is_err());
+    }
+
+    #[test]
+    fn lists_every_missing_rule() {
+        assert_eq!(
+            problems(&strict(), "short", "bob"),
+            "The password must be at least 12 characters long, contain upper and lower case letters, \
+             contain a digit, contain a symbol"
+        );
+    }
+
+    #[test]
+    fn whitespace_is_not_a_symbol() {
+        assert_eq!(problems(&strict(), "Correct Horse 9", "alice"), "The password must contain a symbol");
+    }
+
+    #[test]
+    fn rejects_the_username_in_any_case() {
+        assert_eq!(
+            problems(&strict(), "xx-ALICE-Secret-1", "Alice"),
+            "The password must not contain the username"
+        );
+        assert!(strict().check("xx-ALICE-Secret-1", "").is_ok());
+    }
+
+    #[test]
+    fn disabled_rules_are_not_checked() {
+        let policy = length_only(8);
+        assert!(policy.check("lowercaseonly", "alice").is_ok());
+    }
+}
@@ -0,0 +1,175 @@
+//! Roles and the permissions they grant.
+//!
+//! Every user has one role, stored in `users.role`. Handlers state the permission
+//! they need in their signature with `Authorized<require::X>` (see `middleware::auth`);
+//! code that only knows what it needs at run time calls `AuthenticatedUser::require`.
+
+use serde::{Serialize, Serializer};
+use crate::errors::AppError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Everything, including users, roles and branches
+    Admin,
+    /// Records, reports and the books, but not users or branches
+    Accountant,
+    /// Enters orders, expenses and payments
+    Cashier,
+    /// Reads records and reports
+    Viewer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    OrdersRead,
+    OrdersWrite,
+    OrdersDelete,
+    ExpensesRead,
+    ExpensesWrite,
+    ExpensesDelete,
+    InvoicesRead,
+    /// Record and delete payments
+    PaymentsWrite,
+    ReportsRead,
+    /// Generate stored reports
+    ReportsWrite,
+    /// Accounts, journal entries, statements and exchange rates
+    LedgerRead,
+    LedgerWrite,
+    /// Read records and reports of branches one isn't assigned to, or of every branch at once
+    BranchesAll,
+    /// Open and close branches and assign users to them
+    BranchesWrite,
+    /// Users and their roles
+    UsersAdmin,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 15] = [
+        Permission::OrdersRead,
+        Permission::OrdersWrite,
+        Permission::OrdersDelete,
+        Permission::ExpensesRead,
+        Permission::ExpensesWrite,
+        Permission::ExpensesDelete,
+        Permission::InvoicesRead,
+        Permission::PaymentsWrite,
+        Permission::ReportsRead,
+        Permission::ReportsWrite,
+        Permission::LedgerRead,
+        Permission::LedgerWrite,
+        Permission::BranchesAll,
+        Permission::BranchesWrite,
+        Permission::UsersAdmin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::OrdersRead => "orders:read",
+            Permission::OrdersWrite => "orders:write",
+            Permission::OrdersDelete => "orders:delete",
+            Permission::ExpensesRead => "expenses:read",
+            Permission::ExpensesWrite => "expenses:write",
+            Permission::ExpensesDelete => "expenses:delete",
+            Permission::InvoicesRead => "invoices:read",
+            Permission::PaymentsWrite => "payments:write",
+            Permission::ReportsRead => "reports:read",
+            Permission::ReportsWrite => "reports:write",
+            Permission::LedgerRead => "ledger:read",
+            Permission::LedgerWrite => "ledger:write",
+            Permission::BranchesAll => "branches:all",
+            Permission::BranchesWrite => "branches:write",
+            Permission::UsersAdmin => "users:admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        Permission::ALL
+            .into_iter()
+            .find(|permission| permission.as_str() == value.trim())
+            .ok_or_else(|| AppError::BadRequest(format!("Unsupported permission '{}'", value)))
+    }
+}
+
+impl Serialize for Permission {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [Role::Admin, Role::Accountant, Role::Cashier, Role::Viewer];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Accountant => "accountant",
+            Role::Cashier => "cashier",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "admin" => Ok(Role::Admin),
+            "accountant" => Ok(Role::Accountant),
+            "cashier" => Ok(Role::Cashier),
+            "viewer" => Ok(Role::Viewer),
+            other => Err(AppError::BadRequest(format!(
+                "Unsupported role '{}', expected admin, accountant, cashier or viewer",
+                other
+            ))),
+        }
+    }
+
+    /// Role stored on a user row; unknown values grant nothing beyond viewing
+    pub fn from_column(value: &str) -> Self {
+        Role::parse(value).unwrap_or(Role::Viewer)
+    }
+
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Role::Admin => &Permission::ALL,
+            Role::Accountant => &[
+                OrdersRead, OrdersWrite, OrdersDelete, ExpensesRead, ExpensesWrite, ExpensesDelete,
+                InvoicesRead, PaymentsWrite, ReportsRead, ReportsWrite, LedgerRead, LedgerWrite, BranchesAll,
+            ],
+            Role::Cashier => &[OrdersRead, OrdersWrite, ExpensesRead, ExpensesWrite, InvoicesRead, PaymentsWrite],
+            Role::Viewer => &[OrdersRead, ExpensesRead, InvoicesRead, ReportsRead, LedgerRead],
+        }
+    }
+
+    pub fn allows(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+/// A permission named at the type level, for `Authorized<P>`
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+macro_rules! required_permissions {
+    ($($name:ident),* $(,)?) => {
+        $(
+            pub struct $name;
+
+            impl RequiredPermission for $name {
+                const PERMISSION: Permission = Permission::$name;
+            }
+        )*
+    };
+}
+
+/// Markers for `Authorized<P>`, one per permission
+pub mod require {
+    use super::{Permission, RequiredPermission};
+
+    required_permissions!(
+        OrdersRead, OrdersWrite, OrdersDelete, ExpensesRead, ExpensesWrite, ExpensesDelete,
+        InvoicesRead, PaymentsWrite, ReportsRead, ReportsWrite, LedgerRead, LedgerWrite,
+        BranchesAll, BranchesWrite, UsersAdmin,
+    );
+}
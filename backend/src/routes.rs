@@ -1,6 +1,8 @@
-use actix_web::{web, HttpResponse};
+use actix_web::web;
 use crate::handlers::{
-    users, orders, expenses, invoices, reports, dashboard, registration,
+    users, orders, expenses, invoices, reports, dashboard, registration, ledger, statements,
+    exchange_rates, branches, analytics, roles, sessions, invitations, lockouts, two_factor,
+    user_admin, api_keys,
 };
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -9,9 +11,20 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             // 👤 User routes
             .route("/register", web::post().to(users::register))
             .route("/login", web::post().to(users::login))
+            .route("/login/2fa", web::post().to(two_factor::verify_login))
+            .route("/login/2fa/enroll", web::post().to(two_factor::enroll_login))
+            .route("/login/2fa/confirm", web::post().to(two_factor::confirm_login))
+            .route("/refresh", web::post().to(sessions::refresh))
+            .route("/logout", web::post().to(sessions::logout))
             .route("/forgot-password", web::post().to(users::forgot_password))
+            .route("/reset-password", web::post().to(users::reset_password))
             .route("/forgot-registration-code", web::post().to(registration::forgot_code))
             .route("/reset-registration-code", web::post().to(registration::reset_code))
+            .route("/me", web::get().to(users::get_me))
+            .route("/me", web::put().to(users::update_me))
+            .route("/me/password", web::put().to(users::change_password))
+            .route("/me/sessions", web::get().to(sessions::list_my_sessions))
+            .route("/me/sessions/{id}", web::delete().to(sessions::revoke_my_session))
 
             // 📦 Orders routes
             .route("/orders", web::post().to(orders::create_order))
@@ -31,13 +44,96 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/invoices", web::get().to(invoices::list_invoices))
             .route("/invoices/{id}", web::get().to(invoices::get_invoice))
             .route("/invoices/order/{order_id}", web::get().to(invoices::get_invoice_by_order))
+            .route("/invoices/{id}/payments", web::post().to(invoices::create_payment))
+            .route("/invoices/{id}/payments", web::get().to(invoices::list_payments))
+            .route("/payments/{id}", web::delete().to(invoices::delete_payment))
 
             // 📊 Reports routes
             .route("/reports", web::post().to(reports::generate_report))
             .route("/reports", web::get().to(reports::list_reports))
-            .route("/reports/{month}", web::get().to(reports::get_report_by_month))
+            .route("/reports/range", web::get().to(reports::get_range_report))
+            .route("/reports/ytd", web::get().to(reports::get_year_to_date))
+            .route("/reports/periods", web::get().to(reports::list_fiscal_periods))
+            .route("/reports/staff", web::get().to(reports::get_staff_activity))
+            .route("/reports/{period}", web::get().to(reports::get_report_by_month))
+            .route("/reports/{period}/comparison", web::get().to(reports::get_month_comparison))
+            .route("/reports/{period}/breakdown", web::get().to(reports::get_month_breakdown))
+            .route("/reports/{month}/status", web::get().to(reports::get_report_status))
+
+            // 📒 Ledger routes
+            .route("/ledger/accounts", web::get().to(ledger::list_accounts))
+            .route("/ledger/accounts", web::post().to(ledger::create_account))
+            .route("/ledger/entries", web::get().to(ledger::list_entries))
+            .route("/ledger/entries", web::post().to(ledger::create_entry))
+            .route("/ledger/entries/{id}", web::delete().to(ledger::delete_entry))
+            .route("/ledger/opening-balances", web::put().to(ledger::set_opening_balances))
+            .route("/ledger/trial-balance", web::get().to(ledger::get_trial_balance))
+            .route("/ledger/reconciliation/{month}", web::get().to(ledger::get_reconciliation))
+            .route("/ledger/rebuild", web::post().to(ledger::rebuild))
+
+            // 💱 Exchange rates
+            .route("/exchange-rates", web::get().to(exchange_rates::list_rates))
+            .route("/exchange-rates", web::put().to(exchange_rates::save_rate))
+            .route("/exchange-rates/import", web::post().to(exchange_rates::import_rates))
+            .route("/exchange-rates/{id}", web::delete().to(exchange_rates::delete_rate))
+
+            // 🏥 Branches
+            .route("/branches", web::get().to(branches::list_branches))
+            .route("/branches", web::post().to(branches::create_branch))
+            .route("/branches/{id}", web::put().to(branches::update_branch))
+            .route("/me/branches", web::get().to(branches::my_branches))
+            .route("/users/{id}/branches", web::get().to(branches::list_user_branches))
+            .route("/users/{id}/branches", web::put().to(branches::assign_user_branch))
+            .route("/users/{id}/branches/{branch_id}", web::delete().to(branches::unassign_user_branch))
+
+            // 🧑‍💼 User management
+            .route("/users", web::get().to(user_admin::list_users))
+            .route("/users/{id}", web::get().to(user_admin::get_user))
+            .route("/users/{id}", web::delete().to(user_admin::delete_user))
+            .route("/users/{id}/deactivate", web::post().to(user_admin::deactivate_user))
+            .route("/users/{id}/reactivate", web::post().to(user_admin::reactivate_user))
+            .route("/users/{id}/password-reset", web::post().to(user_admin::force_password_reset))
+
+            // 🗝️ API keys
+            .route("/api-keys", web::post().to(api_keys::create_api_key))
+            .route("/api-keys", web::get().to(api_keys::list_api_keys))
+            .route("/api-keys/{id}", web::delete().to(api_keys::revoke_api_key))
+
+            // 🔐 Roles
+            .route("/roles", web::get().to(roles::list_roles))
+            .route("/users/{id}/role", web::put().to(roles::assign_role))
+            .route("/users/{id}/sessions", web::delete().to(sessions::revoke_user_sessions))
+
+            // ✉️ Invitations
+            .route("/invitations", web::post().to(invitations::create_invitation))
+            .route("/invitations", web::get().to(invitations::list_invitations))
+            .route("/invitations/{id}", web::delete().to(invitations::revoke_invitation))
+
+            // 🚫 Lockouts
+            .route("/lockouts", web::get().to(lockouts::list_lockouts))
+            .route("/lockouts/{id}", web::delete().to(lockouts::lift_lockout))
+            .route("/users/{id}/unlock", web::post().to(lockouts::unlock_user))
+
+            // 🔑 Two-factor authentication
+            .route("/me/2fa", web::post().to(two_factor::begin_enrollment))
+            .route("/me/2fa/confirm", web::post().to(two_factor::confirm_enrollment))
+            .route("/me/2fa", web::delete().to(two_factor::disable))
+            .route("/me/2fa/recovery-codes", web::post().to(two_factor::regenerate_recovery_codes))
+            .route("/users/{id}/2fa", web::delete().to(two_factor::reset_user))
+            .route("/roles/{role}/two-factor", web::put().to(two_factor::set_role_policy))
+
+            // 📑 Financial statements
+            .route("/statements/income", web::get().to(statements::get_income_statement))
+            .route("/statements/balance-sheet", web::get().to(statements::get_balance_sheet))
+            .route("/cash-position", web::get().to(statements::get_cash_position))
+            .route("/statements/mappings", web::get().to(statements::list_mappings))
+            .route("/statements/mappings", web::put().to(statements::save_mapping))
+            .route("/statements/mappings/{id}", web::delete().to(statements::delete_mapping))
 
             // 📈 Dashboard summary
             .route("/dashboard", web::get().to(dashboard::summary))
+
+            // 📉 Analytics
+            .route("/analytics/timeseries", web::get().to(analytics::timeseries))
     );
 }
@@ -0,0 +1,426 @@
+use std::collections::{BTreeMap, HashMap};
+use sea_orm::{DatabaseConnection, EntityTrait, ColumnTrait, QueryFilter, QuerySelect, Condition};
+use sea_orm::prelude::Decimal;
+use sea_orm::sea_query::Expr;
+use serde::Serialize;
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use crate::{
+    entities::{expenses, orders, users},
+    errors::AppError,
+    fiscal,
+    money::{self, Money},
+    services::reports::{ReportScope, ReportsService, TRANSFER_LABEL},
+};
+
+/// Upper bound on the buckets of one series, so a daily series can't span decades
+const MAX_BUCKETS: usize = 1000;
+
+#[derive(Clone)]
+pub struct AnalyticsService {
+    reports: ReportsService,
+}
+
+/// Figure charted by a time series
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    Income,
+    Expenses,
+    Net,
+    Orders,
+}
+
+impl Metric {
+    pub fn parse(metric: Option<&str>) -> Result<Self, AppError> {
+        match metric.map(|m| m.to_ascii_lowercase()).as_deref() {
+            None | Some("income") => Ok(Metric::Income),
+            Some("expenses") => Ok(Metric::Expenses),
+            Some("net") => Ok(Metric::Net),
+            Some("orders") => Ok(Metric::Orders),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "Unsupported metric '{}', expected income, expenses, net or orders",
+                other
+            ))),
+        }
+    }
+}
+
+/// Width of one bucket; weeks start on Monday, months on the 1st
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bucket {
+    Day,
+    Week,
+    Month,
+}
+
+impl Bucket {
+    pub fn parse(bucket: Option<&str>) -> Result<Self, AppError> {
+        match bucket.map(|b| b.to_ascii_lowercase()).as_deref() {
+            None | Some("day") => Ok(Bucket::Day),
+            Some("week") => Ok(Bucket::Week),
+            Some("month") => Ok(Bucket::Month),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "Unsupported bucket '{}', expected day, week or month",
+                other
+            ))),
+        }
+    }
+
+    /// First day of the bucket containing `date`
+    fn start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Bucket::Day => date,
+            Bucket::Week => date - Duration::days(date.weekday().num_days_from_monday().into()),
+            Bucket::Month => fiscal::start_of_month(date),
+        }
+    }
+
+    fn next(&self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Bucket::Day => start + Duration::days(1),
+            Bucket::Week => start + Duration::days(7),
+            Bucket::Month => start + Months::new(1),
+        }
+    }
+}
+
+/// How a time series is split into several series
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// Expense label, or service (order description) for income and orders
+    Label,
+    /// User who entered the order or expense
+    User,
+}
+
+impl GroupBy {
+    pub fn parse(group_by: Option<&str>) -> Result<Option<Self>, AppError> {
+        match group_by.map(|g| g.to_ascii_lowercase()).as_deref() {
+            None | Some("") => Ok(None),
+            Some("label") => Ok(Some(GroupBy::Label)),
+            Some("user") => Ok(Some(GroupBy::User)),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "Unsupported group_by '{}', expected label or user",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(untagged)]
+pub enum SeriesValue {
+    Count(i64),
+    Amount(Money),
+}
+
+impl SeriesValue {
+    fn sort_key(&self) -> Decimal {
+        match self {
+            SeriesValue::Count(count) => Decimal::from(*count),
+            SeriesValue::Amount(amount) => amount.amount(),
+        }
+    }
+}
+
+/// One line of a chart; `values` lines up with `TimeSeries::buckets`
+#[derive(Serialize)]
+pub struct Series {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i32>,
+    pub values: Vec<SeriesValue>,
+    pub total: SeriesValue,
+}
+
+#[derive(Serialize)]
+pub struct TimeSeries {
+    pub metric: Metric,
+    pub bucket: Bucket,
+    pub group_by: Option<GroupBy>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// `None` for the consolidated figures
+    pub branch_id: Option<i32>,
+    /// First day of every bucket, oldest first; the first and last may be partial
+    pub buckets: Vec<NaiveDate>,
+    /// Largest total first
+    pub series: Vec<Series>,
+    pub currency: &'static str,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum SeriesKey {
+    Total,
+    Label(String),
+    User(Option<i32>),
+}
+
+/// Count and amount of one day and series key
+struct DayTotal {
+    date: NaiveDate,
+    key: SeriesKey,
+    count: i64,
+    amount: Money,
+}
+
+/// Running count and amount of one bucket
+#[derive(Clone, Copy, Default)]
+struct BucketTotal {
+    count: i64,
+    amount: Money,
+}
+
+impl DayTotal {
+    fn new(date: NaiveDate, key: SeriesKey, count: i64, amount: Option<Money>) -> Self {
+        DayTotal { date, key, count, amount: Money::from_sum(amount) }
+    }
+}
+
+impl AnalyticsService {
+    pub fn new(db: DatabaseConnection, scope: ReportScope) -> Self {
+        Self {
+            reports: ReportsService::new(db).with_scope(scope),
+        }
+    }
+
+    /// Per-day order totals matching `condition`, split by `group_by`
+    async fn order_days(
+        &self,
+        condition: Condition,
+        group_by: Option<GroupBy>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DayTotal>, AppError> {
+        let query = orders::Entity::find()
+            .select_only()
+            .column(orders::Column::OrderDate)
+            .column_as(Expr::col(orders::Column::OrderId).count(), "count")
+            .column_as(Expr::col(orders::Column::TotalAmount).sum(), "amount")
+            .filter(orders::Column::OrderDate.between(from, to))
+            .filter(condition)
+            .group_by(orders::Column::OrderDate);
+        let db = &self.reports.db;
+
+        let days = match group_by {
+            None => query
+                .into_tuple::<(NaiveDate, i64, Option<Money>)>()
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|(date, count, amount)| DayTotal::new(date, SeriesKey::Total, count, amount))
+                .collect(),
+            Some(GroupBy::Label) => query
+                .column(orders::Column::Description)
+                .group_by(orders::Column::Description)
+                .into_tuple::<(NaiveDate, i64, Option<Money>, String)>()
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|(date, count, amount, label)| DayTotal::new(date, SeriesKey::Label(label), count, amount))
+                .collect(),
+            Some(GroupBy::User) => query
+                .column(orders::Column::CreatedBy)
+                .group_by(orders::Column::CreatedBy)
+                .into_tuple::<(NaiveDate, i64, Option<Money>, Option<i32>)>()
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|(date, count, amount, user_id)| DayTotal::new(date, SeriesKey::User(user_id), count, amount))
+                .collect(),
+        };
+        Ok(days)
+    }
+
+    /// Per-day expense totals of the scope, split by `group_by`.
+    /// A branch also pays for the transfers billed to it, listed under their own label.
+    async fn expense_days(
+        &self,
+        group_by: Option<GroupBy>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DayTotal>, AppError> {
+        let query = expenses::Entity::find()
+            .select_only()
+            .column(expenses::Column::ExpenseDate)
+            .column_as(Expr::col(expenses::Column::ExpenseId).count(), "count")
+            .column_as(Expr::col(expenses::Column::Amount).sum(), "amount")
+            .filter(expenses::Column::ExpenseDate.between(from, to))
+            .filter(self.reports.expense_scope())
+            .group_by(expenses::Column::ExpenseDate);
+        let db = &self.reports.db;
+
+        let mut days: Vec<DayTotal> = match group_by {
+            None => query
+                .into_tuple::<(NaiveDate, i64, Option<Money>)>()
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|(date, count, amount)| DayTotal::new(date, SeriesKey::Total, count, amount))
+                .collect(),
+            Some(GroupBy::Label) => query
+                .column(expenses::Column::Label)
+                .group_by(expenses::Column::Label)
+                .into_tuple::<(NaiveDate, i64, Option<Money>, String)>()
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|(date, count, amount, label)| DayTotal::new(date, SeriesKey::Label(label), count, amount))
+                .collect(),
+            Some(GroupBy::User) => query
+                .column(expenses::Column::CreatedBy)
+                .group_by(expenses::Column::CreatedBy)
+                .into_tuple::<(NaiveDate, i64, Option<Money>, Option<i32>)>()
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|(date, count, amount, user_id)| DayTotal::new(date, SeriesKey::User(user_id), count, amount))
+                .collect(),
+        };
+
+        // Transfers are entered by the billing branch, so no user of this one gets them
+        if let Some(billed) = self.reports.transfers_billed().filter(|_| group_by != Some(GroupBy::User)) {
+            let transfers = self.order_days(billed, None, from, to).await?;
+            days.extend(transfers.into_iter().map(|day| DayTotal {
+                key: match group_by {
+                    Some(GroupBy::Label) => SeriesKey::Label(TRANSFER_LABEL.to_string()),
+                    _ => SeriesKey::Total,
+                },
+                ..day
+            }));
+        }
+        Ok(days)
+    }
+
+    /// Display names of the users behind `User` keys
+    async fn user_names(&self, keys: impl Iterator<Item = &SeriesKey>) -> Result<HashMap<i32, String>, AppError> {
+        let ids: Vec<i32> = keys
+            .filter_map(|key| match key {
+                SeriesKey::User(Some(user_id)) => Some(*user_id),
+                _ => None,
+            })
+            .collect();
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let names = users::Entity::find()
+            .filter(users::Column::UserId.is_in(ids))
+            .all(&self.reports.db)
+            .await?
+            .into_iter()
+            .map(|user| (user.user_id, user.username))
+            .collect();
+        Ok(names)
+    }
+
+    /// Chart data for `metric` between two dates, in aligned buckets with empty ones as zero.
+    /// Totals are grouped per day in SQL and rolled up into weeks or months here,
+    /// which works the same on SQLite and PostgreSQL.
+    pub async fn timeseries(
+        &self,
+        metric: Metric,
+        bucket: Bucket,
+        group_by: Option<GroupBy>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<TimeSeries, AppError> {
+        if from > to {
+            return Err(AppError::BadRequest("Start date must not be after end date".into()));
+        }
+        if metric == Metric::Net && group_by == Some(GroupBy::Label) {
+            return Err(AppError::BadRequest(
+                "Net can't be grouped by label: income is split by service and expenses by label".into(),
+            ));
+        }
+
+        let mut buckets = Vec::new();
+        let mut start = bucket.start(from);
+        while start <= to {
+            if buckets.len() == MAX_BUCKETS {
+                return Err(AppError::BadRequest(format!(
+                    "Too many buckets (more than {}); choose a wider bucket or a shorter range",
+                    MAX_BUCKETS
+                )));
+            }
+            buckets.push(start);
+            start = bucket.next(start);
+        }
+
+        // Expenses count negative towards net; on their own they stay positive
+        let mut days: Vec<(DayTotal, bool)> = Vec::new();
+        if matches!(metric, Metric::Income | Metric::Net | Metric::Orders) {
+            let income = self.order_days(self.reports.order_scope(), group_by, from, to).await?;
+            days.extend(income.into_iter().map(|day| (day, false)));
+        }
+        if matches!(metric, Metric::Expenses | Metric::Net) {
+            let spent = self.expense_days(group_by, from, to).await?;
+            let negative = metric == Metric::Net;
+            days.extend(spent.into_iter().map(|day| (day, negative)));
+        }
+
+        let mut totals: BTreeMap<SeriesKey, Vec<BucketTotal>> = BTreeMap::new();
+        if group_by.is_none() {
+            totals.insert(SeriesKey::Total, vec![BucketTotal::default(); buckets.len()]);
+        }
+        for (day, negative) in days {
+            // Buckets are sorted, so the day belongs to the last one starting on or before it
+            let index = buckets.partition_point(|start| *start <= day.date) - 1;
+            let series = totals
+                .entry(day.key)
+                .or_insert_with(|| vec![BucketTotal::default(); buckets.len()]);
+            series[index].count += day.count;
+            if negative {
+                series[index].amount -= day.amount;
+            } else {
+                series[index].amount += day.amount;
+            }
+        }
+
+        let names = self.user_names(totals.keys()).await?;
+        let value = |total: BucketTotal| match metric {
+            Metric::Orders => SeriesValue::Count(total.count),
+            Metric::Income | Metric::Expenses | Metric::Net => SeriesValue::Amount(total.amount),
+        };
+
+        let mut series: Vec<Series> = totals
+            .into_iter()
+            .map(|(key, bucket_totals)| {
+                let (name, user_id) = match key {
+                    SeriesKey::Total => ("total".to_string(), None),
+                    SeriesKey::Label(label) => (label, None),
+                    SeriesKey::User(Some(user_id)) => (
+                        names.get(&user_id).cloned().unwrap_or_else(|| format!("User #{}", user_id)),
+                        Some(user_id),
+                    ),
+                    SeriesKey::User(None) => ("Unknown".to_string(), None),
+                };
+                let sum = bucket_totals.iter().fold(BucketTotal::default(), |sum, total| BucketTotal {
+                    count: sum.count + total.count,
+                    amount: sum.amount + total.amount,
+                });
+                Series {
+                    name,
+                    user_id,
+                    values: bucket_totals.into_iter().map(value).collect(),
+                    total: value(sum),
+                }
+            })
+            .collect();
+        series.sort_by_key(|point| std::cmp::Reverse(point.total.sort_key()));
+
+        Ok(TimeSeries {
+            metric,
+            bucket,
+            group_by,
+            from,
+            to,
+            branch_id: self.reports.scope.branch_id(),
+            buckets,
+            series,
+            currency: money::base_currency(),
+        })
+    }
+}
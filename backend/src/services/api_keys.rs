@@ -0,0 +1,286 @@
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use sea_orm::sea_query::{Expr, Query, SimpleExpr};
+use serde::{Deserialize, Serialize};
+use chrono::{Duration, NaiveDateTime, Utc};
+
+use crate::{
+    entities::{api_keys, users},
+    errors::AppError,
+    permissions::{Permission, Role},
+    services::branches::{AssignBranchRequest, BranchesService},
+    services::users::hash_password,
+    tokens,
+};
+
+/// Keys start with this, which tells them apart from access tokens and makes leaked ones easy to spot
+pub const KEY_PREFIX: &str = "ftk_";
+
+/// Characters of a key kept in the clear to recognise it by
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// API keys let scripts and other systems call the API without a person signing in.
+/// A key grants only the permissions it was created with, and acts as a service account
+/// user of its own, so everything it records is attributed to the key.
+#[derive(Clone)]
+pub struct ApiKeysService {
+    pub db: DatabaseConnection,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// Permissions such as `orders:write` or `reports:read`
+    pub permissions: Vec<String>,
+    /// Never expires when omitted
+    pub expires_in_days: Option<i64>,
+    /// Branch the key records into; the oldest active branch when omitted
+    pub branch_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiKeyQuery {
+    /// Include revoked and expired keys
+    pub all: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ApiKeyResponse {
+    pub api_key_id: i32,
+    pub name: String,
+    /// Start of the key, to recognise it by
+    pub key_prefix: String,
+    pub permissions: Vec<Permission>,
+    /// active, revoked or expired
+    pub status: &'static str,
+    /// Service account the key acts as; what it records names this user
+    pub user_id: i32,
+    pub created_by: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+/// A new key, which is shown only this once
+#[derive(Serialize)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    pub key: String,
+}
+
+fn status(api_key: &api_keys::Model, now: NaiveDateTime) -> &'static str {
+    if api_key.revoked_at.is_some() {
+        "revoked"
+    } else if api_key.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        "expired"
+    } else {
+        "active"
+    }
+}
+
+/// Permissions stored on a key row; unknown names grant nothing
+pub fn permissions(api_key: &api_keys::Model) -> Vec<Permission> {
+    serde_json::from_value::<Vec<String>>(api_key.permissions.clone())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|permission| Permission::parse(permission).ok())
+        .collect()
+}
+
+impl From<api_keys::Model> for ApiKeyResponse {
+    fn from(api_key: api_keys::Model) -> Self {
+        ApiKeyResponse {
+            status: status(&api_key, Utc::now().naive_utc()),
+            permissions: permissions(&api_key),
+            api_key_id: api_key.api_key_id,
+            name: api_key.name,
+            key_prefix: api_key.key_prefix,
+            user_id: api_key.user_id,
+            created_by: api_key.created_by,
+            created_at: api_key.created_at,
+            expires_at: api_key.expires_at,
+            last_used_at: api_key.last_used_at,
+            revoked_at: api_key.revoked_at,
+        }
+    }
+}
+
+impl ApiKeysService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn create_api_key(&self, req: CreateApiKeyRequest, created_by: i32) -> Result<CreatedApiKey, AppError> {
+        let name = req.name.trim().to_string();
+        if name.is_empty() || name.chars().count() > 100 {
+            return Err(AppError::BadRequest("The name must be 1 to 100 characters".into()));
+        }
+
+        let mut granted = Vec::new();
+        for permission in &req.permissions {
+            let permission = Permission::parse(permission)?;
+            if permission == Permission::UsersAdmin {
+                return Err(AppError::BadRequest("API keys cannot manage users".into()));
+            }
+            if !granted.contains(&permission) {
+                granted.push(permission);
+            }
+        }
+        if granted.is_empty() {
+            return Err(AppError::BadRequest("An API key needs at least one permission".into()));
+        }
+
+        let now = Utc::now().naive_utc();
+        let expires_at = match req.expires_in_days {
+            Some(days) if days <= 0 => {
+                return Err(AppError::BadRequest("expires_in_days must be positive".into()));
+            }
+            Some(days) => Some(now + Duration::days(days)),
+            None => None,
+        };
+
+        let existing = api_keys::Entity::find()
+            .filter(api_keys::Column::Name.eq(name.clone()))
+            .one(&self.db)
+            .await?;
+        let username = format!("api-key:{}", name);
+        let taken = users::Entity::find()
+            .filter(users::Column::Username.eq(username.clone()))
+            .one(&self.db)
+            .await?;
+        if existing.is_some() || taken.is_some() {
+            return Err(AppError::BadRequest(format!("An API key named '{}' already exists", name)));
+        }
+
+        if let Some(branch_id) = req.branch_id {
+            BranchesService::new(self.db.clone()).find_branch(branch_id).await?;
+        }
+
+        // The service account, its branch and the key are created together or not at all
+        let txn = self.db.begin().await?;
+
+        // Nobody knows the service account's password, so it can't sign in
+        let service_account = users::ActiveModel {
+            username: Set(username),
+            password_hash: Set(hash_password(&tokens::generate())?),
+            role: Set(Role::Viewer.as_str().to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        match req.branch_id {
+            Some(branch_id) => {
+                BranchesService::assign_user_on(
+                    &txn,
+                    AssignBranchRequest {
+                        user_id: service_account.user_id,
+                        branch_id,
+                        is_default: true,
+                    },
+                )
+                .await?;
+            }
+            None => BranchesService::assign_default_branch(&txn, service_account.user_id).await?,
+        }
+
+        let key = format!("{}{}", KEY_PREFIX, tokens::generate());
+        let names: Vec<&str> = granted.iter().map(Permission::as_str).collect();
+        let api_key = api_keys::ActiveModel {
+            name: Set(name),
+            key_prefix: Set(key[..DISPLAY_PREFIX_LEN].to_string()),
+            key_hash: Set(tokens::hash(&key)),
+            permissions: Set(serde_json::json!(names)),
+            user_id: Set(service_account.user_id),
+            created_by: Set(Some(created_by)),
+            created_at: Set(now),
+            expires_at: Set(expires_at),
+            last_used_at: Set(None),
+            revoked_at: Set(None),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(CreatedApiKey {
+            api_key: api_key.into(),
+            key,
+        })
+    }
+
+    /// Usable keys, newest first; every key with `include_all`
+    pub async fn get_api_keys(&self, include_all: bool) -> Result<Vec<ApiKeyResponse>, AppError> {
+        let api_keys = api_keys::Entity::find()
+            .order_by_desc(api_keys::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+
+        let now = Utc::now().naive_utc();
+        Ok(api_keys
+            .into_iter()
+            .filter(|api_key| include_all || status(api_key, now) == "active")
+            .map(ApiKeyResponse::from)
+            .collect())
+    }
+
+    /// Stop a key from working; its service account is deactivated along with it
+    pub async fn revoke_api_key(&self, api_key_id: i32) -> Result<(), AppError> {
+        let api_key = api_keys::Entity::find_by_id(api_key_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("API key not found".into()))?;
+        if api_key.revoked_at.is_some() {
+            return Ok(());
+        }
+
+        let now = Utc::now().naive_utc();
+        let user_id = api_key.user_id;
+        let mut active: api_keys::ActiveModel = api_key.into();
+        active.revoked_at = Set(Some(now));
+        active.update(&self.db).await?;
+
+        users::Entity::update_many()
+            .col_expr(users::Column::DeactivatedAt, Expr::value(now))
+            .filter(users::Column::UserId.eq(user_id))
+            .filter(users::Column::DeactivatedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
+
+/// The usable key a request presented, recording that it was used
+pub async fn authenticate(db: &DatabaseConnection, key: &str) -> Result<Option<api_keys::Model>, AppError> {
+    let now = Utc::now().naive_utc();
+    let api_key = api_keys::Entity::find()
+        .filter(api_keys::Column::KeyHash.eq(tokens::hash(key)))
+        .one(db)
+        .await?
+        .filter(|api_key| status(api_key, now) == "active");
+
+    if let Some(api_key) = &api_key {
+        api_keys::Entity::update_many()
+            .col_expr(api_keys::Column::LastUsedAt, Expr::value(now))
+            .filter(api_keys::Column::ApiKeyId.eq(api_key.api_key_id))
+            .exec(db)
+            .await?;
+    }
+    Ok(api_key)
+}
+
+/// Condition matching the service accounts behind API keys, which are managed through
+/// their key rather than as users
+pub fn service_accounts() -> SimpleExpr {
+    users::Column::UserId.in_subquery(
+        Query::select()
+            .column(api_keys::Column::UserId)
+            .from(api_keys::Entity)
+            .to_owned(),
+    )
+}
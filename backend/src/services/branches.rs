@@ -0,0 +1,277 @@
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder, ConnectionTrait,
+};
+use sea_orm::sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use chrono::Utc;
+use crate::{
+    entities::{branches, user_branches, users},
+    errors::AppError,
+};
+
+#[derive(Clone)]
+pub struct BranchesService {
+    pub db: DatabaseConnection,
+}
+
+#[derive(Deserialize)]
+pub struct CreateBranchRequest {
+    pub code: String,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateBranchRequest {
+    pub name: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct AssignBranchRequest {
+    pub user_id: i32,
+    pub branch_id: i32,
+    /// Make this the branch the user works in when no other is selected
+    pub is_default: bool,
+}
+
+/// `?branch_id=` / `?consolidated=true` on list and report endpoints; omitted means
+/// the active branch. See `AuthenticatedUser::branch_scope`.
+#[derive(Debug, Default, Deserialize)]
+pub struct BranchQuery {
+    pub branch_id: Option<i32>,
+    /// Every branch at once; needs `branches:all`
+    #[serde(default)]
+    pub consolidated: bool,
+}
+
+#[derive(Serialize)]
+pub struct UserBranchResponse {
+    pub branch_id: i32,
+    pub code: String,
+    pub name: String,
+    pub is_active: bool,
+    pub is_default: bool,
+}
+
+impl BranchesService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Fetch a branch, failing with `NotFound`
+    pub async fn find_branch(&self, branch_id: i32) -> Result<branches::Model, AppError> {
+        branches::Entity::find_by_id(branch_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound("Branch not found".into()))
+    }
+
+    /// Fetch all branches
+    pub async fn get_branches(&self) -> Result<Vec<branches::Model>, AppError> {
+        let branches_list = branches::Entity::find()
+            .order_by_asc(branches::Column::BranchId)
+            .all(&self.db)
+            .await?;
+        Ok(branches_list)
+    }
+
+    /// IDs of the branches that are still operating
+    pub async fn active_branch_ids(&self) -> Result<Vec<i32>, AppError> {
+        let ids = branches::Entity::find()
+            .filter(branches::Column::IsActive.eq(true))
+            .order_by_asc(branches::Column::BranchId)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|branch| branch.branch_id)
+            .collect();
+        Ok(ids)
+    }
+
+    /// Create a branch; codes are unique and stored upper-case
+    pub async fn create_branch(&self, req: CreateBranchRequest) -> Result<branches::Model, AppError> {
+        let code = req.code.trim().to_ascii_uppercase();
+        let name = req.name.trim().to_string();
+        if code.is_empty() || name.is_empty() {
+            return Err(AppError::BadRequest("Branch code and name are required".into()));
+        }
+
+        let existing = branches::Entity::find()
+            .filter(branches::Column::Code.eq(code.as_str()))
+            .one(&self.db)
+            .await?;
+        if existing.is_some() {
+            return Err(AppError::BadRequest(format!("Branch code '{}' is already in use", code)));
+        }
+
+        let branch = branches::ActiveModel {
+            code: Set(code),
+            name: Set(name),
+            is_active: Set(true),
+            created_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+        Ok(branch)
+    }
+
+    /// Rename a branch or close / reopen it
+    pub async fn update_branch(&self, branch_id: i32, req: UpdateBranchRequest) -> Result<branches::Model, AppError> {
+        let mut active: branches::ActiveModel = self.find_branch(branch_id).await?.into();
+
+        if let Some(name) = req.name {
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                return Err(AppError::BadRequest("Branch name is required".into()));
+            }
+            active.name = Set(name);
+        }
+        if let Some(is_active) = req.is_active {
+            active.is_active = Set(is_active);
+        }
+
+        Ok(active.update(&self.db).await?)
+    }
+
+    /// Branches a user is assigned to, default first
+    pub async fn get_user_branches(&self, user_id: i32) -> Result<Vec<UserBranchResponse>, AppError> {
+        let assignments = user_branches::Entity::find()
+            .filter(user_branches::Column::UserId.eq(user_id))
+            .find_also_related(branches::Entity)
+            .order_by_desc(user_branches::Column::IsDefault)
+            .order_by_asc(user_branches::Column::BranchId)
+            .all(&self.db)
+            .await?;
+
+        Ok(assignments
+            .into_iter()
+            .filter_map(|(assignment, branch)| {
+                let branch = branch?;
+                Some(UserBranchResponse {
+                    branch_id: branch.branch_id,
+                    code: branch.code,
+                    name: branch.name,
+                    is_active: branch.is_active,
+                    is_default: assignment.is_default,
+                })
+            })
+            .collect())
+    }
+
+    /// Assign a user to a branch, or change whether it is their default
+    pub async fn assign_user(&self, req: AssignBranchRequest) -> Result<Vec<UserBranchResponse>, AppError> {
+        let user_id = req.user_id;
+        Self::assign_user_on(&self.db, req).await?;
+        self.get_user_branches(user_id).await
+    }
+
+    /// `assign_user` on the caller's connection, so it commits with the caller's other writes
+    pub(crate) async fn assign_user_on<C: ConnectionTrait>(conn: &C, req: AssignBranchRequest) -> Result<(), AppError> {
+        users::Entity::find_by_id(req.user_id)
+            .one(conn)
+            .await?
+            .ok_or(AppError::NotFound("User not found".into()))?;
+        branches::Entity::find_by_id(req.branch_id)
+            .one(conn)
+            .await?
+            .ok_or(AppError::NotFound("Branch not found".into()))?;
+
+        // A user has at most one default branch
+        if req.is_default {
+            user_branches::Entity::update_many()
+                .col_expr(user_branches::Column::IsDefault, Expr::value(false))
+                .filter(user_branches::Column::UserId.eq(req.user_id))
+                .exec(conn)
+                .await?;
+        }
+
+        let existing = user_branches::Entity::find()
+            .filter(user_branches::Column::UserId.eq(req.user_id))
+            .filter(user_branches::Column::BranchId.eq(req.branch_id))
+            .one(conn)
+            .await?;
+
+        match existing {
+            Some(existing) => {
+                let mut active: user_branches::ActiveModel = existing.into();
+                active.is_default = Set(req.is_default);
+                active.update(conn).await?;
+            }
+            None => {
+                user_branches::ActiveModel {
+                    user_id: Set(req.user_id),
+                    branch_id: Set(req.branch_id),
+                    is_default: Set(req.is_default),
+                    ..Default::default()
+                }
+                .insert(conn)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a user from a branch
+    pub async fn unassign_user(&self, user_id: i32, branch_id: i32) -> Result<(), AppError> {
+        let result = user_branches::Entity::delete_many()
+            .filter(user_branches::Column::UserId.eq(user_id))
+            .filter(user_branches::Column::BranchId.eq(branch_id))
+            .exec(&self.db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(AppError::NotFound("User is not assigned to this branch".into()));
+        }
+        Ok(())
+    }
+
+    /// Give a new user the oldest active branch as their default, on the caller's connection
+    /// so it commits with the account
+    pub async fn assign_default_branch<C: ConnectionTrait>(conn: &C, user_id: i32) -> Result<(), AppError> {
+        let oldest = branches::Entity::find()
+            .filter(branches::Column::IsActive.eq(true))
+            .order_by_asc(branches::Column::BranchId)
+            .one(conn)
+            .await?;
+
+        if let Some(branch) = oldest {
+            user_branches::ActiveModel {
+                user_id: Set(user_id),
+                branch_id: Set(branch.branch_id),
+                is_default: Set(true),
+                ..Default::default()
+            }
+            .insert(conn)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Whether a user is assigned to a branch, open or closed
+    pub async fn is_assigned(&self, user_id: i32, branch_id: i32) -> Result<bool, AppError> {
+        let assignment = user_branches::Entity::find()
+            .filter(user_branches::Column::UserId.eq(user_id))
+            .filter(user_branches::Column::BranchId.eq(branch_id))
+            .one(&self.db)
+            .await?;
+        Ok(assignment.is_some())
+    }
+
+    /// Branch a request works in: the one it asks for, which the user must be assigned to,
+    /// otherwise the user's default (or first) branch. `None` when the user has no branch.
+    pub async fn active_branch(&self, user_id: i32, requested: Option<i32>) -> Result<Option<i32>, AppError> {
+        let branches_list = self.get_user_branches(user_id).await?;
+
+        match requested {
+            Some(branch_id) => branches_list
+                .iter()
+                .find(|branch| branch.branch_id == branch_id && branch.is_active)
+                .map(|branch| Some(branch.branch_id))
+                .ok_or_else(|| AppError::BadRequest(format!("You are not assigned to branch {}", branch_id))),
+            None => Ok(branches_list
+                .iter()
+                .find(|branch| branch.is_active)
+                .map(|branch| branch.branch_id)),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, QuerySelect, RelationTrait, JoinType};
+use sea_orm::prelude::Decimal;
+use sea_orm::sea_query::Expr;
+use serde::Serialize;
+use chrono::{Duration, NaiveDate};
+use crate::{
+    entities::{invoices, payments},
+    errors::AppError,
+    fiscal,
+    money::{self, Money},
+    services::reports::{BreakdownLine, DailyTotal, PeriodSummary, ReportScope, ReportsService},
+};
+
+/// Number of expense labels listed on the dashboard
+const TOP_LABELS: usize = 5;
+/// Days covered by the sparkline, ending today
+const SPARKLINE_DAYS: i64 = 30;
+
+#[derive(Clone)]
+pub struct DashboardService {
+    reports: ReportsService,
+}
+
+/// Headline figures of a stretch of days
+#[derive(Serialize)]
+pub struct Kpis {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub orders: i32,
+    pub income: Money,
+    pub expenses: Money,
+    pub net: Money,
+}
+
+#[derive(Serialize)]
+pub struct DashboardSummary {
+    pub date: NaiveDate,
+    /// `None` for the consolidated figures
+    pub branch_id: Option<i32>,
+    pub today: Kpis,
+    pub month_to_date: Kpis,
+    /// Month-to-date income per order
+    pub average_order_value: Money,
+    /// Invoiced but not yet paid, across all dates
+    pub outstanding_receivables: Money,
+    /// Largest expense labels of the month so far
+    pub top_expense_labels: Vec<BreakdownLine>,
+    /// One entry per day of the last 30, oldest first, including days without activity
+    pub sparkline: Vec<DailyTotal>,
+    pub currency: &'static str,
+}
+
+impl From<PeriodSummary> for Kpis {
+    fn from(summary: PeriodSummary) -> Self {
+        Kpis {
+            from: summary.from,
+            to: summary.to,
+            orders: summary.total_orders,
+            income: summary.total_income,
+            expenses: summary.total_expenses,
+            net: summary.net_profit,
+        }
+    }
+}
+
+impl DashboardService {
+    pub fn new(db: DatabaseConnection, scope: ReportScope) -> Self {
+        Self {
+            reports: ReportsService::new(db).with_scope(scope),
+        }
+    }
+
+    /// Invoice totals minus what payments settled, for the orders in scope
+    async fn outstanding_receivables(&self) -> Result<Money, AppError> {
+        let db = &self.reports.db;
+
+        let invoiced: Option<Option<Money>> = invoices::Entity::find()
+            .select_only()
+            .column_as(Expr::col((invoices::Entity, invoices::Column::TotalAmount)).sum(), "amount")
+            .join(JoinType::InnerJoin, invoices::Relation::Orders.def())
+            .filter(self.reports.order_scope())
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        // A foreign-currency payment settles its amount less the exchange difference
+        let settled: Option<Option<Money>> = payments::Entity::find()
+            .select_only()
+            .column_as(
+                Expr::expr(
+                    Expr::col((payments::Entity, payments::Column::Amount))
+                        .sub(Expr::col((payments::Entity, payments::Column::FxGainLoss))),
+                )
+                .sum(),
+                "amount",
+            )
+            .join(JoinType::InnerJoin, payments::Relation::Invoices.def())
+            .join(JoinType::InnerJoin, invoices::Relation::Orders.def())
+            .filter(self.reports.order_scope())
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        Ok(Money::from_sum(invoiced.flatten()) - Money::from_sum(settled.flatten()))
+    }
+
+    /// Daily totals of the last `SPARKLINE_DAYS` days with the quiet days filled in
+    async fn sparkline(&self, today: NaiveDate) -> Result<Vec<DailyTotal>, AppError> {
+        let from = today - Duration::days(SPARKLINE_DAYS - 1);
+        let mut days: BTreeMap<NaiveDate, DailyTotal> = self
+            .reports
+            .daily_totals(from, today)
+            .await?
+            .into_iter()
+            .map(|day| (day.date, day))
+            .collect();
+
+        Ok(from
+            .iter_days()
+            .take_while(|date| *date <= today)
+            .map(|date| {
+                days.remove(&date).unwrap_or(DailyTotal {
+                    date,
+                    orders: 0,
+                    income: Money::ZERO,
+                    expenses: Money::ZERO,
+                    net: Money::ZERO,
+                })
+            })
+            .collect())
+    }
+
+    /// Live figures for the day, the month so far and the last 30 days
+    pub async fn summary(&self, today: NaiveDate) -> Result<DashboardSummary, AppError> {
+        let month_start = fiscal::start_of_month(today);
+
+        let today_kpis = Kpis::from(self.reports.summarize_period(today, today).await?);
+        let month_to_date = Kpis::from(self.reports.summarize_period(month_start, today).await?);
+
+        let average_order_value = if month_to_date.orders > 0 {
+            Money::round(month_to_date.income.amount() / Decimal::from(month_to_date.orders))
+        } else {
+            Money::ZERO
+        };
+
+        Ok(DashboardSummary {
+            date: today,
+            branch_id: self.reports.scope.branch_id(),
+            average_order_value,
+            outstanding_receivables: self.outstanding_receivables().await?,
+            top_expense_labels: self.reports.top_expense_labels(month_start, today, TOP_LABELS).await?,
+            sparkline: self.sparkline(today).await?,
+            today: today_kpis,
+            month_to_date,
+            currency: money::base_currency(),
+        })
+    }
+}
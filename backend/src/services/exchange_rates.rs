@@ -0,0 +1,234 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder,
+    ConnectionTrait, TransactionTrait,
+};
+use sea_orm::prelude::Decimal;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use crate::{
+    entities::exchange_rates,
+    errors::AppError,
+    money::{self, Money},
+};
+
+#[derive(Clone)]
+pub struct ExchangeRatesService {
+    pub db: DatabaseConnection,
+}
+
+/// `rate` is the number of base-currency units one unit of `currency` buys
+#[derive(Deserialize)]
+pub struct SaveRateRequest {
+    pub currency: String,
+    pub rate_date: NaiveDate,
+    pub rate: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub currencies: Vec<String>,
+}
+
+/// One line of an imported file: `currency,rate_date,rate`
+#[derive(Deserialize)]
+struct RateRow {
+    currency: String,
+    rate_date: String,
+    rate: String,
+}
+
+/// An amount in the currency it was entered in and its base-currency value
+pub struct Conversion {
+    pub currency: String,
+    pub original_amount: Money,
+    pub rate: Decimal,
+    pub amount: Money,
+}
+
+/// Upper-case ISO 4217 code; `None` or an empty code means the base currency
+pub fn currency_code(code: Option<&str>) -> Result<String, AppError> {
+    let code = code.map(str::trim).unwrap_or_default();
+    if code.is_empty() {
+        return Ok(money::base_currency().to_string());
+    }
+    if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(AppError::BadRequest(format!(
+            "Invalid currency code '{}', expected three letters such as USD",
+            code
+        )));
+    }
+    Ok(code.to_ascii_uppercase())
+}
+
+impl ExchangeRatesService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Reject the base currency and rates that aren't positive
+    fn validate_rate(currency: &str, rate: Decimal) -> Result<(), AppError> {
+        if currency == money::base_currency() {
+            return Err(AppError::BadRequest(format!(
+                "{} is the base currency and always has a rate of 1",
+                currency
+            )));
+        }
+        if rate <= Decimal::ZERO {
+            return Err(AppError::BadRequest("Exchange rate must be greater than zero".into()));
+        }
+        Ok(())
+    }
+
+    /// Insert or replace the rate of a currency on a date
+    async fn upsert_on<C: ConnectionTrait>(
+        conn: &C,
+        currency: String,
+        rate_date: NaiveDate,
+        rate: Decimal,
+    ) -> Result<exchange_rates::Model, AppError> {
+        let existing = exchange_rates::Entity::find()
+            .filter(exchange_rates::Column::Currency.eq(currency.as_str()))
+            .filter(exchange_rates::Column::RateDate.eq(rate_date))
+            .one(conn)
+            .await?;
+
+        let saved = match existing {
+            Some(existing) => {
+                let mut active: exchange_rates::ActiveModel = existing.into();
+                active.rate = Set(rate);
+                active.update(conn).await?
+            }
+            None => {
+                exchange_rates::ActiveModel {
+                    currency: Set(currency),
+                    rate_date: Set(rate_date),
+                    rate: Set(rate),
+                    ..Default::default()
+                }
+                .insert(conn)
+                .await?
+            }
+        };
+        Ok(saved)
+    }
+
+    /// Fetch rates, newest first, optionally for one currency
+    pub async fn get_rates(&self, currency: Option<&str>) -> Result<Vec<exchange_rates::Model>, AppError> {
+        let mut query = exchange_rates::Entity::find();
+        if let Some(currency) = currency {
+            query = query.filter(exchange_rates::Column::Currency.eq(currency_code(Some(currency))?));
+        }
+
+        let rates = query
+            .order_by_desc(exchange_rates::Column::RateDate)
+            .order_by_asc(exchange_rates::Column::Currency)
+            .all(&self.db)
+            .await?;
+        Ok(rates)
+    }
+
+    /// Create or replace the rate of a currency on a date
+    pub async fn save_rate(&self, req: SaveRateRequest) -> Result<exchange_rates::Model, AppError> {
+        let currency = currency_code(Some(&req.currency))?;
+        Self::validate_rate(&currency, req.rate)?;
+        Self::upsert_on(&self.db, currency, req.rate_date, req.rate).await
+    }
+
+    /// Delete rate
+    pub async fn delete_rate(&self, rate_id: i32) -> Result<(), AppError> {
+        let result = exchange_rates::Entity::delete_by_id(rate_id).exec(&self.db).await?;
+        if result.rows_affected == 0 {
+            return Err(AppError::NotFound("Exchange rate not found".into()));
+        }
+        Ok(())
+    }
+
+    /// Validate one line of an imported file
+    fn parse_row(row: RateRow) -> Result<(String, NaiveDate, Decimal), AppError> {
+        let currency = currency_code(Some(&row.currency))?;
+        let rate_date = NaiveDate::parse_from_str(&row.rate_date, "%Y-%m-%d")
+            .map_err(|_| AppError::BadRequest("Invalid date format, expected YYYY-MM-DD".into()))?;
+        let rate = Decimal::from_str(&row.rate)
+            .map_err(|_| AppError::BadRequest(format!("'{}' is not a valid rate", row.rate)))?;
+        Self::validate_rate(&currency, rate)?;
+        Ok((currency, rate_date, rate))
+    }
+
+    /// Import a CSV file with a `currency,rate_date,rate` header.
+    /// Nothing is saved unless every line is valid.
+    pub async fn import_csv(&self, content: &str) -> Result<ImportSummary, AppError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(content.as_bytes());
+
+        let mut rows = Vec::new();
+        for (index, row) in reader.deserialize::<RateRow>().enumerate() {
+            // Line 1 is the header
+            let line = index + 2;
+            let parsed = row
+                .map_err(|e| AppError::BadRequest(e.to_string()))
+                .and_then(Self::parse_row)
+                .map_err(|e| match e {
+                    AppError::BadRequest(reason) => AppError::BadRequest(format!("Line {}: {}", line, reason)),
+                    other => other,
+                })?;
+            rows.push(parsed);
+        }
+
+        if rows.is_empty() {
+            return Err(AppError::BadRequest("The file contains no exchange rates".into()));
+        }
+
+        let currencies: BTreeSet<String> = rows.iter().map(|(currency, ..)| currency.clone()).collect();
+
+        let txn = self.db.begin().await?;
+        for (currency, rate_date, rate) in rows.iter().cloned() {
+            Self::upsert_on(&txn, currency, rate_date, rate).await?;
+        }
+        txn.commit().await?;
+
+        Ok(ImportSummary {
+            imported: rows.len(),
+            currencies: currencies.into_iter().collect(),
+        })
+    }
+
+    /// Rate in effect on a date: the latest one recorded on or before it
+    pub async fn rate_on(&self, currency: &str, date: NaiveDate) -> Result<Decimal, AppError> {
+        if currency == money::base_currency() {
+            return Ok(Decimal::ONE);
+        }
+
+        let rate = exchange_rates::Entity::find()
+            .filter(exchange_rates::Column::Currency.eq(currency))
+            .filter(exchange_rates::Column::RateDate.lte(date))
+            .order_by_desc(exchange_rates::Column::RateDate)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| {
+                AppError::BadRequest(format!("No {} exchange rate on or before {}", currency, date))
+            })?;
+        Ok(rate.rate)
+    }
+
+    /// Value an amount entered in `currency` (base currency when `None`) at the rate of `date`
+    pub async fn convert(
+        &self,
+        currency: Option<&str>,
+        original_amount: Money,
+        date: NaiveDate,
+    ) -> Result<Conversion, AppError> {
+        let currency = currency_code(currency)?;
+        let rate = self.rate_on(&currency, date).await?;
+
+        Ok(Conversion {
+            amount: original_amount.at_rate(rate),
+            currency,
+            original_amount,
+            rate,
+        })
+    }
+}
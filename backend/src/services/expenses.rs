@@ -1,10 +1,16 @@
-use sea_orm::{DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter};
+use sea_orm::{DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, TransactionTrait};
+use sea_orm::prelude::Decimal;
 use serde::{Deserialize, Serialize};
-use chrono::{Datelike, NaiveDate};
+use chrono::NaiveDate;
 use crate::{
-    entities::{expenses, reports},
-    services::reports::ReportsService,
+    entities::expenses,
+    services::report_queue::ReportQueue,
+    services::ledger::{LedgerService, SOURCE_EXPENSE},
+    services::exchange_rates::ExchangeRatesService,
+    services::staff_activity::{StaffActivityService, ACTION_DELETE, ACTION_UPDATE, RECORD_EXPENSE},
+    services::export::{self, ExportFile, ExportFormat},
     errors::AppError,
+    money::{self, Money},
 };
 
 #[derive(Clone)]
@@ -16,16 +22,19 @@ pub struct ExpensesService {
 pub struct CreateExpenseRequest {
     pub description: String,
     pub label: String,
-    pub amount: f64,
+    pub amount: Money,            // in `currency`
+    pub currency: Option<String>, // defaults to the base currency
     pub expense_date: NaiveDate,
     pub created_by: i32,
+    pub branch_id: i32,
 }
 
 #[derive(Deserialize)]
 pub struct UpdateExpenseRequest {
     pub description: Option<String>,
     pub label: Option<String>,
-    pub amount: Option<f64>,
+    pub amount: Option<Money>,
+    pub currency: Option<String>,
     pub expense_date: Option<NaiveDate>,
 }
 
@@ -34,10 +43,15 @@ pub struct CreateExpenseResponse {
     pub expense_id: i32,
     pub description: String,
     pub label: String,
-    pub amount: f64,
+    pub amount: Money,
+    pub original_currency: String,
+    pub original_amount: Money,
+    pub exchange_rate: Decimal,
     pub expense_date: NaiveDate,
     pub created_by: Option<i32>,
     pub modified_by: Option<i32>,
+    pub branch_id: i32,
+    pub currency: &'static str,
 }
 
 #[derive(Serialize)]
@@ -45,10 +59,15 @@ pub struct AllExpensesResponse {
     pub expense_id: i32,
     pub description: String,
     pub label: String,
-    pub amount: f64,
+    pub amount: Money,
+    pub original_currency: String,
+    pub original_amount: Money,
+    pub exchange_rate: Decimal,
     pub expense_date: NaiveDate,
     pub created_by: Option<i32>,
     pub modified_by: Option<i32>,
+    pub branch_id: i32,
+    pub currency: &'static str,
 }
 
 #[derive(Serialize)]
@@ -56,20 +75,31 @@ pub struct GetExpenseResponse {
     pub expense_id: i32,
     pub description: String,
     pub label: String,
-    pub amount: f64,
+    pub amount: Money,
+    pub original_currency: String,
+    pub original_amount: Money,
+    pub exchange_rate: Decimal,
     pub expense_date: NaiveDate,
     pub created_by: Option<i32>,
     pub modified_by: Option<i32>,
+    pub branch_id: i32,
+    pub currency: &'static str,
 }
 
 #[derive(Serialize)]
 pub struct UpdateExpenseResponse {
     pub expense_id: i32,
+    pub description: String,
     pub label: String,
-    pub order_date: NaiveDate,
-    pub total_amount: f64,
+    pub amount: Money,
+    pub original_currency: String,
+    pub original_amount: Money,
+    pub exchange_rate: Decimal,
+    pub expense_date: NaiveDate,
     pub created_by: Option<i32>,
     pub modified_by: Option<i32>,
+    pub branch_id: i32,
+    pub currency: &'static str,
 }
 
 impl ExpensesService {
@@ -79,42 +109,57 @@ impl ExpensesService {
 
     /// Create a new expense
     pub async fn create_expense(&self, req: CreateExpenseRequest) -> Result<CreateExpenseResponse, AppError> {
+        // Amounts are kept in the base currency at the rate of the expense date
+        let conversion = ExchangeRatesService::new(self.db.clone())
+            .convert(req.currency.as_deref(), req.amount, req.expense_date)
+            .await?;
+
         let new_expense = expenses::ActiveModel {
             description: Set(req.description.clone()),
             label: Set(req.label.clone()),
-            amount: Set(req.amount),
+            amount: Set(conversion.amount),
+            original_currency: Set(conversion.currency),
+            original_amount: Set(conversion.original_amount),
+            exchange_rate: Set(conversion.rate),
             expense_date: Set(req.expense_date),
             created_by: Set(Some(req.created_by)),
             modified_by: Set(Some(req.created_by)),
+            branch_id: Set(req.branch_id),
             ..Default::default()
-        }
-        .insert(&self.db)
-        .await?;
+        };
 
-        // After saving the expense
-        let expense_date = new_expense.expense_date;
-        let first_day_of_month = NaiveDate::from_ymd_opt(expense_date.year(), expense_date.month(), 1)
-            .expect("Invalid expense date");
+        // The expense, its ledger entry and the report mark are written together
+        let txn = self.db.begin().await?;
+        let new_expense = new_expense.insert(&txn).await?;
 
-        let reports_service = ReportsService::new(self.db.clone());
-        if let Err(e) = reports_service.generate_monthly_report(first_day_of_month).await {
-            tracing::error!("Failed to auto-update monthly report after expense creation: {}", e);
-        }
+        // Post the expense to the ledger and queue the month for a debounced report recomputation
+        LedgerService::post_expense(&txn, &new_expense).await?;
+        ReportQueue::mark_dirty(&txn, new_expense.expense_date).await?;
+        txn.commit().await?;
 
         Ok(CreateExpenseResponse {
             expense_id: new_expense.expense_id,
             description: new_expense.description,
             label: new_expense.label,
             amount: new_expense.amount,
+            original_currency: new_expense.original_currency,
+            original_amount: new_expense.original_amount,
+            exchange_rate: new_expense.exchange_rate,
             expense_date: new_expense.expense_date,
             created_by: new_expense.created_by,
             modified_by: new_expense.modified_by,
+            branch_id: new_expense.branch_id,
+            currency: money::base_currency(),
         })
     }
 
-    /// Fetch all expenses
-    pub async fn get_expenses(&self) -> Result<Vec<AllExpensesResponse>, AppError> {
-        let all_expenses = expenses::Entity::find()
+    /// Fetch all expenses, optionally of one branch
+    pub async fn get_expenses(&self, branch_id: Option<i32>) -> Result<Vec<AllExpensesResponse>, AppError> {
+        let mut query = expenses::Entity::find();
+        if let Some(branch_id) = branch_id {
+            query = query.filter(expenses::Column::BranchId.eq(branch_id));
+        }
+        let all_expenses = query
             .all(&self.db)
             .await
             .map_err(AppError::from)?;
@@ -126,42 +171,90 @@ impl ExpensesService {
                 description: all_expenses.description,
                 label: all_expenses.label,
                 amount: all_expenses.amount,
+                original_currency: all_expenses.original_currency,
+                original_amount: all_expenses.original_amount,
+                exchange_rate: all_expenses.exchange_rate,
                 expense_date: all_expenses.expense_date,
                 created_by: all_expenses.created_by,
                 modified_by: all_expenses.modified_by,
+                branch_id: all_expenses.branch_id,
+                currency: money::base_currency(),
             }).collect();
 
         Ok(response)
     }
 
-    /// Fetch single expense by ID
-    pub async fn get_expense_by_id(&self, expense_id: i32) -> Result<GetExpenseResponse, AppError> {
-        let expense = expenses::Entity::find_by_id(expense_id)
+    /// Export all expenses (optionally of one branch) as CSV or XLSX
+    pub async fn export_expenses(&self, format: ExportFormat, branch_id: Option<i32>) -> Result<ExportFile, AppError> {
+        let mut query = expenses::Entity::find();
+        if let Some(branch_id) = branch_id {
+            query = query.filter(expenses::Column::BranchId.eq(branch_id));
+        }
+        let expenses = query.all(&self.db).await?;
+        export::rows_file("expenses", "Expenses", format, &expenses)
+    }
+
+    /// Fetch single expense by ID, within one branch unless `branch_id` is `None`
+    pub async fn get_expense_by_id(&self, expense_id: i32, branch_id: Option<i32>) -> Result<GetExpenseResponse, AppError> {
+        let mut query = expenses::Entity::find_by_id(expense_id);
+        if let Some(branch_id) = branch_id {
+            query = query.filter(expenses::Column::BranchId.eq(branch_id));
+        }
+        let expense = query
             .one(&self.db)
             .await?
-            .ok_or(AppError::NotFound("Order not found".into()))?;
+            .ok_or(AppError::NotFound("Expense not found".into()))?;
 
         Ok(GetExpenseResponse {
             expense_id: expense.expense_id,
             description: expense.description,
             label: expense.label,
             amount: expense.amount,
+            original_currency: expense.original_currency,
+            original_amount: expense.original_amount,
+            exchange_rate: expense.exchange_rate,
             expense_date: expense.expense_date,
             created_by: expense.created_by,
             modified_by: expense.modified_by,
+            branch_id: expense.branch_id,
+            currency: money::base_currency(),
         })
     }
 
-    /// Update expense
-    pub async fn update_expense(&self, expense_id: i32, req: UpdateExpenseRequest, modified_by: i32) -> Result<UpdateExpenseResponse, AppError> {
+    /// Update an expense of a branch
+    pub async fn update_expense(
+        &self,
+        expense_id: i32,
+        branch_id: i32,
+        req: UpdateExpenseRequest,
+        modified_by: i32,
+    ) -> Result<UpdateExpenseResponse, AppError> {
         use expenses::Entity as Expenses;
 
         // Find existing expense
         let existing = Expenses::find_by_id(expense_id)
+            .filter(expenses::Column::BranchId.eq(branch_id))
             .one(&self.db)
             .await?
             .ok_or_else(|| AppError::NotFound("Expense not found".into()))?;
 
+        let previous_date = existing.expense_date;
+
+        // A new amount, currency or date is valued again at the rate of the (new) expense date
+        let conversion = if req.amount.is_some() || req.currency.is_some() || req.expense_date.is_some() {
+            let currency = req.currency.clone().unwrap_or_else(|| existing.original_currency.clone());
+            let conversion = ExchangeRatesService::new(self.db.clone())
+                .convert(
+                    Some(&currency),
+                    req.amount.unwrap_or(existing.original_amount),
+                    req.expense_date.unwrap_or(existing.expense_date),
+                )
+                .await?;
+            Some(conversion)
+        } else {
+            None
+        };
+
         // Convert to active model
         let mut active: expenses::ActiveModel = existing.into();
 
@@ -173,8 +266,11 @@ impl ExpensesService {
             active.label = Set(label);
         }
 
-        if let Some(amount) = req.amount {
-            active.amount = Set(amount);
+        if let Some(conversion) = conversion {
+            active.amount = Set(conversion.amount);
+            active.original_currency = Set(conversion.currency);
+            active.original_amount = Set(conversion.original_amount);
+            active.exchange_rate = Set(conversion.rate);
         }
 
         if let Some(date) = req.expense_date {
@@ -184,40 +280,53 @@ impl ExpensesService {
         active.modified_by = Set(Some(modified_by));
 
         // Update in DB
-        let updated = active.update(&self.db).await?;
-
-        // After updating expense
-        if let Some(updated) = updated {
-            let expense_date = updated.expense_date;
-            let first_day_of_month = NaiveDate::from_ymd_opt(expense_date.year(), expense_date.month(), 1)
-                .expect("Invalid expense date");
-
-            let reports_service = ReportsService::new(self.db.clone());
-            if let Err(e) = reports_service.generate_monthly_report(first_day_of_month).await {
-                tracing::error!("Failed to auto-update monthly report after expense update: {}", e);
-            }
-        }
+        let txn = self.db.begin().await?;
+        let updated = active.update(&txn).await?;
+        StaffActivityService::record_edit(&txn, RECORD_EXPENSE, expense_id, ACTION_UPDATE, modified_by, updated.branch_id)
+            .await?;
+
+        LedgerService::post_expense(&txn, &updated).await?;
+
+        // Both the old and the new month change when the expense date moves
+        ReportQueue::mark_dirty(&txn, previous_date).await?;
+        ReportQueue::mark_dirty(&txn, updated.expense_date).await?;
+        txn.commit().await?;
 
         Ok(UpdateExpenseResponse {
             expense_id: updated.expense_id,
             description: updated.description,
             label: updated.label,
             amount: updated.amount,
+            original_currency: updated.original_currency,
+            original_amount: updated.original_amount,
+            exchange_rate: updated.exchange_rate,
             expense_date: updated.expense_date,
             created_by: updated.created_by,
             modified_by: updated.modified_by,
+            branch_id: updated.branch_id,
+            currency: money::base_currency(),
         })
     }
 
-    /// Delete expense
-    pub async fn delete_expense(&self, expense_id: i32) -> Result<(), AppError> {
-        let expense: expenses::ActiveModel = expenses::Entity::find_by_id(expense_id)
+    /// Delete an expense of a branch
+    pub async fn delete_expense(&self, expense_id: i32, branch_id: i32, user_id: i32) -> Result<(), AppError> {
+        let expense = expenses::Entity::find_by_id(expense_id)
+            .filter(expenses::Column::BranchId.eq(branch_id))
             .one(&self.db)
             .await?
-            .ok_or(AppError::NotFound)?
-            .into();
+            .ok_or(AppError::NotFound("Expense not found".into()))?;
+        let expense_date = expense.expense_date;
+        let branch_id = expense.branch_id;
+
+        let txn = self.db.begin().await?;
+        LedgerService::remove_source(&txn, SOURCE_EXPENSE, expense_id).await?;
+
+        let expense: expenses::ActiveModel = expense.into();
+        expense.delete(&txn).await?;
 
-        expense.delete(&self.db).await?;
+        StaffActivityService::record_edit(&txn, RECORD_EXPENSE, expense_id, ACTION_DELETE, user_id, branch_id).await?;
+        ReportQueue::mark_dirty(&txn, expense_date).await?;
+        txn.commit().await?;
         Ok(())
     }
 }
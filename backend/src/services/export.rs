@@ -0,0 +1,297 @@
+use actix_web::{http::header, HttpResponse};
+use chrono::NaiveDate;
+use rust_xlsxwriter::{Format, Workbook, Worksheet};
+use sea_orm::prelude::Decimal;
+use serde::Deserialize;
+use crate::{
+    entities::{expenses, invoices, orders},
+    errors::AppError,
+    money::Money,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<String>, // json (default), csv or xlsx
+}
+
+/// Output format requested through `?format=`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn parse(format: Option<&str>) -> Result<Self, AppError> {
+        match format.map(|f| f.to_ascii_lowercase()).as_deref() {
+            None | Some("json") => Ok(ExportFormat::Json),
+            Some("csv") => Ok(ExportFormat::Csv),
+            Some("xlsx") => Ok(ExportFormat::Xlsx),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "Unsupported format '{}', expected json, csv or xlsx",
+                other
+            ))),
+        }
+    }
+}
+
+/// A single typed value, written as a native cell in XLSX
+pub enum Cell {
+    Text(String),
+    Int(i64),
+    Amount(Money),
+    Percent(Decimal),
+    /// Exchange rate, kept at full precision
+    Rate(Decimal),
+    Date(NaiveDate),
+}
+
+impl Cell {
+    fn to_csv_field(&self) -> String {
+        match self {
+            // Spreadsheets run text starting with these as a formula; the quote keeps it text
+            Cell::Text(text) if text.starts_with(['=', '+', '-', '@', '\t', '\r']) => format!("'{}", text),
+            Cell::Text(text) => text.clone(),
+            Cell::Int(value) => value.to_string(),
+            Cell::Amount(amount) => amount.to_string(),
+            Cell::Percent(value) => value.round_dp(2).to_string(),
+            Cell::Rate(value) => value.normalize().to_string(),
+            Cell::Date(date) => date.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+/// Anything that can be exported as one row of a table
+pub trait ExportRow {
+    fn headers() -> &'static [&'static str];
+    fn cells(&self) -> Vec<Cell>;
+}
+
+/// A named table inside a workbook
+pub struct Sheet {
+    pub name: String,
+    pub headers: Vec<&'static str>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Sheet {
+    pub fn from_rows<T: ExportRow>(name: &str, rows: &[T]) -> Self {
+        Sheet {
+            name: name.to_string(),
+            headers: T::headers().to_vec(),
+            rows: rows.iter().map(|row| row.cells()).collect(),
+        }
+    }
+}
+
+/// Rendered export ready to be sent as a download
+pub struct ExportFile {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl ExportFile {
+    pub fn into_response(self) -> HttpResponse {
+        HttpResponse::Ok()
+            .content_type(self.content_type)
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", self.filename),
+            ))
+            .body(self.body)
+    }
+}
+
+const XLSX_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Render a single sheet as CSV
+pub fn csv_file(basename: &str, sheet: &Sheet) -> Result<ExportFile, AppError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer
+        .write_record(&sheet.headers)
+        .map_err(|_| AppError::InternalError)?;
+    for row in &sheet.rows {
+        writer
+            .write_record(row.iter().map(Cell::to_csv_field))
+            .map_err(|_| AppError::InternalError)?;
+    }
+
+    let body = writer.into_inner().map_err(|_| AppError::InternalError)?;
+
+    Ok(ExportFile {
+        filename: format!("{}.csv", basename),
+        content_type: "text/csv; charset=utf-8",
+        body,
+    })
+}
+
+fn write_sheet(worksheet: &mut Worksheet, sheet: &Sheet) -> Result<(), rust_xlsxwriter::XlsxError> {
+    let header_format = Format::new().set_bold();
+    let amount_format = Format::new().set_num_format("#,##0.00");
+    let rate_format = Format::new().set_num_format("0.000000");
+    let date_format = Format::new().set_num_format("yyyy-mm-dd");
+
+    worksheet.set_name(&sheet.name)?;
+
+    for (col, title) in sheet.headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *title, &header_format)?;
+        worksheet.set_column_width(col as u16, 16)?;
+    }
+    worksheet.set_freeze_panes(1, 0)?;
+
+    for (index, row) in sheet.rows.iter().enumerate() {
+        let row_num = index as u32 + 1;
+        for (col, cell) in row.iter().enumerate() {
+            let col = col as u16;
+            match cell {
+                Cell::Text(text) => {
+                    worksheet.write_string(row_num, col, text)?;
+                }
+                Cell::Int(value) => {
+                    worksheet.write_number(row_num, col, *value as f64)?;
+                }
+                Cell::Amount(amount) => {
+                    let value = f64::try_from(amount.amount()).unwrap_or_default();
+                    worksheet.write_number_with_format(row_num, col, value, &amount_format)?;
+                }
+                Cell::Percent(value) => {
+                    let value = f64::try_from(value.round_dp(2)).unwrap_or_default();
+                    worksheet.write_number_with_format(row_num, col, value, &amount_format)?;
+                }
+                Cell::Rate(value) => {
+                    let value = f64::try_from(*value).unwrap_or_default();
+                    worksheet.write_number_with_format(row_num, col, value, &rate_format)?;
+                }
+                Cell::Date(date) => {
+                    worksheet.write_datetime_with_format(row_num, col, date, &date_format)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Render one or more sheets as an XLSX workbook
+pub fn xlsx_file(basename: &str, sheets: &[Sheet]) -> Result<ExportFile, AppError> {
+    let mut workbook = Workbook::new();
+
+    for sheet in sheets {
+        let worksheet = workbook.add_worksheet();
+        write_sheet(worksheet, sheet).map_err(|e| {
+            tracing::error!("Failed to write worksheet '{}': {}", sheet.name, e);
+            AppError::InternalError
+        })?;
+    }
+
+    let body = workbook.save_to_buffer().map_err(|e| {
+        tracing::error!("Failed to build workbook: {}", e);
+        AppError::InternalError
+    })?;
+
+    Ok(ExportFile {
+        filename: format!("{}.xlsx", basename),
+        content_type: XLSX_CONTENT_TYPE,
+        body,
+    })
+}
+
+/// Export a flat list as CSV or a single-sheet workbook
+pub fn rows_file<T: ExportRow>(
+    basename: &str,
+    sheet_name: &str,
+    format: ExportFormat,
+    rows: &[T],
+) -> Result<ExportFile, AppError> {
+    let sheet = Sheet::from_rows(sheet_name, rows);
+    match format {
+        ExportFormat::Csv => csv_file(basename, &sheet),
+        ExportFormat::Xlsx => xlsx_file(basename, &[sheet]),
+        ExportFormat::Json => Err(AppError::BadRequest("JSON is not a file export format".into())),
+    }
+}
+
+impl ExportRow for orders::Model {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "Order ID", "Branch", "Counterparty branch", "Patient", "Order date", "Amount", "Currency",
+            "Original amount", "Rate", "Description", "Created by", "Modified by",
+        ]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::Int(self.order_id.into()),
+            Cell::Int(self.branch_id.into()),
+            Cell::Text(self.counterparty_branch_id.map(|id| id.to_string()).unwrap_or_default()),
+            Cell::Text(self.patient_name.clone()),
+            Cell::Date(self.order_date),
+            Cell::Amount(self.total_amount),
+            Cell::Text(self.original_currency.clone()),
+            Cell::Amount(self.original_amount),
+            Cell::Rate(self.exchange_rate),
+            Cell::Text(self.description.clone()),
+            Cell::Text(self.created_by.map(|id| id.to_string()).unwrap_or_default()),
+            Cell::Text(self.modified_by.map(|id| id.to_string()).unwrap_or_default()),
+        ]
+    }
+}
+
+impl ExportRow for expenses::Model {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "Expense ID", "Branch", "Description", "Label", "Amount", "Currency", "Original amount", "Rate",
+            "Expense date", "Created by", "Modified by",
+        ]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::Int(self.expense_id.into()),
+            Cell::Int(self.branch_id.into()),
+            Cell::Text(self.description.clone()),
+            Cell::Text(self.label.clone()),
+            Cell::Amount(self.amount),
+            Cell::Text(self.original_currency.clone()),
+            Cell::Amount(self.original_amount),
+            Cell::Rate(self.exchange_rate),
+            Cell::Date(self.expense_date),
+            Cell::Text(self.created_by.map(|id| id.to_string()).unwrap_or_default()),
+            Cell::Text(self.modified_by.map(|id| id.to_string()).unwrap_or_default()),
+        ]
+    }
+}
+
+impl ExportRow for invoices::Model {
+    fn headers() -> &'static [&'static str] {
+        &["Invoice ID", "Branch", "Order ID", "Transaction ID", "Invoice date", "Amount", "Description"]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            Cell::Int(self.invoice_id.into()),
+            Cell::Int(self.branch_id.into()),
+            Cell::Int(self.order_id.into()),
+            Cell::Text(self.transaction_id.clone()),
+            Cell::Date(self.invoice_date),
+            Cell::Amount(self.total_amount),
+            Cell::Text(self.description.clone()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_text_cannot_start_a_formula() {
+        for text in ["=HYPERLINK(\"http://evil\",\"x\")", "+1", "-1", "@SUM(A1)", "\tx", "\rx"] {
+            assert_eq!(Cell::Text(text.into()).to_csv_field(), format!("'{}", text));
+        }
+        assert_eq!(Cell::Text("Jane Doe".into()).to_csv_field(), "Jane Doe");
+    }
+}
@@ -0,0 +1,244 @@
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder, ConnectionTrait,
+};
+use sea_orm::sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use chrono::{Duration, NaiveDateTime, Utc};
+
+use crate::{
+    entities::{invitations, users},
+    errors::AppError,
+    config::Config,
+    permissions::Role,
+    mail::{Mailer, Template},
+    tokens,
+};
+
+/// An invitation lets one person register once, with the email and role an admin chose
+#[derive(Clone)]
+pub struct InvitationsService {
+    pub db: DatabaseConnection,
+    mailer: Arc<dyn Mailer>,
+    ttl: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateInvitationRequest {
+    pub email: String,
+    pub role: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvitationQuery {
+    /// Include accepted, revoked and expired invitations
+    pub all: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct InvitationResponse {
+    pub invitation_id: i32,
+    pub email: String,
+    pub role: String,
+    /// pending, accepted, revoked or expired
+    pub status: &'static str,
+    pub invited_by: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub accepted_at: Option<NaiveDateTime>,
+    pub accepted_by: Option<i32>,
+}
+
+/// A new invitation with its code, which is shown only this once
+#[derive(Serialize)]
+pub struct CreatedInvitation {
+    #[serde(flatten)]
+    pub invitation: InvitationResponse,
+    pub code: String,
+    /// Whether the code reached the invitee by email; otherwise pass it on yourself
+    pub emailed: bool,
+}
+
+fn status(invitation: &invitations::Model, now: NaiveDateTime) -> &'static str {
+    if invitation.accepted_at.is_some() {
+        "accepted"
+    } else if invitation.revoked_at.is_some() {
+        "revoked"
+    } else if invitation.expires_at <= now {
+        "expired"
+    } else {
+        "pending"
+    }
+}
+
+impl From<invitations::Model> for InvitationResponse {
+    fn from(invitation: invitations::Model) -> Self {
+        InvitationResponse {
+            status: status(&invitation, Utc::now().naive_utc()),
+            invitation_id: invitation.invitation_id,
+            email: invitation.email,
+            role: invitation.role,
+            invited_by: invitation.invited_by,
+            created_at: invitation.created_at,
+            expires_at: invitation.expires_at,
+            accepted_at: invitation.accepted_at,
+            accepted_by: invitation.accepted_by,
+        }
+    }
+}
+
+/// Emails are compared case-insensitively
+pub fn normalize_email(email: &str) -> Result<String, AppError> {
+    let email = email.trim().to_lowercase();
+    match email.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') => Ok(email),
+        _ => Err(AppError::BadRequest(format!("Invalid email address '{}'", email))),
+    }
+}
+
+impl InvitationsService {
+    pub fn new(db: DatabaseConnection, config: &Config, mailer: Arc<dyn Mailer>) -> Self {
+        Self {
+            db,
+            mailer,
+            ttl: Duration::days(config.invitation_days),
+        }
+    }
+
+    pub async fn create_invitation(&self, req: CreateInvitationRequest, invited_by: i32) -> Result<CreatedInvitation, AppError> {
+        let email = normalize_email(&req.email)?;
+        let role = Role::parse(&req.role)?;
+        let now = Utc::now().naive_utc();
+
+        let registered = users::Entity::find()
+            .filter(users::Column::Email.eq(email.clone()))
+            .one(&self.db)
+            .await?;
+        if registered.is_some() {
+            return Err(AppError::BadRequest(format!("{} already has an account", email)));
+        }
+
+        let pending = invitations::Entity::find()
+            .filter(invitations::Column::Email.eq(email.clone()))
+            .filter(invitations::Column::AcceptedAt.is_null())
+            .filter(invitations::Column::RevokedAt.is_null())
+            .filter(invitations::Column::ExpiresAt.gt(now))
+            .one(&self.db)
+            .await?;
+        if pending.is_some() {
+            return Err(AppError::BadRequest(format!(
+                "{} already has a pending invitation; revoke it to send a new one",
+                email
+            )));
+        }
+
+        let code = tokens::generate();
+        let invitation = invitations::ActiveModel {
+            email: Set(email),
+            role: Set(role.as_str().to_string()),
+            code_hash: Set(tokens::hash(&code)),
+            invited_by: Set(Some(invited_by)),
+            created_at: Set(now),
+            expires_at: Set(now + self.ttl),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+
+        // The admin still gets the code when the email can't go out
+        let expires_at = invitation.expires_at.format("%Y-%m-%d %H:%M").to_string();
+        let emailed = self
+            .mailer
+            .send_template(
+                Template::Invitation,
+                &invitation.email,
+                &[("role", &invitation.role), ("code", &code), ("expires_at", &expires_at)],
+            )
+            .await
+            .inspect_err(|e| tracing::error!("Failed to email the invitation to {}: {}", invitation.email, e))
+            .is_ok();
+
+        Ok(CreatedInvitation {
+            invitation: invitation.into(),
+            code,
+            emailed,
+        })
+    }
+
+    /// Pending invitations, newest first; every invitation with `include_all`
+    pub async fn get_invitations(&self, include_all: bool) -> Result<Vec<InvitationResponse>, AppError> {
+        let mut query = invitations::Entity::find();
+        if !include_all {
+            query = query
+                .filter(invitations::Column::AcceptedAt.is_null())
+                .filter(invitations::Column::RevokedAt.is_null())
+                .filter(invitations::Column::ExpiresAt.gt(Utc::now().naive_utc()));
+        }
+
+        let invitations = query
+            .order_by_desc(invitations::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(invitations.into_iter().map(InvitationResponse::from).collect())
+    }
+
+    /// Withdraw an invitation that hasn't been used yet
+    pub async fn revoke_invitation(&self, invitation_id: i32) -> Result<(), AppError> {
+        let invitation = invitations::Entity::find_by_id(invitation_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Invitation not found".into()))?;
+
+        if invitation.accepted_at.is_some() {
+            return Err(AppError::BadRequest("The invitation has already been accepted".into()));
+        }
+        if invitation.revoked_at.is_some() {
+            return Ok(());
+        }
+
+        let mut active: invitations::ActiveModel = invitation.into();
+        active.revoked_at = Set(Some(Utc::now().naive_utc()));
+        active.update(&self.db).await?;
+        Ok(())
+    }
+
+    /// The pending invitation a code belongs to
+    pub async fn find_pending(&self, code: &str) -> Result<invitations::Model, AppError> {
+        let invitation = invitations::Entity::find()
+            .filter(invitations::Column::CodeHash.eq(tokens::hash(code)))
+            .one(&self.db)
+            .await?;
+
+        match invitation {
+            Some(invitation) if status(&invitation, Utc::now().naive_utc()) == "pending" => Ok(invitation),
+            _ => Err(AppError::BadRequest("Invalid or expired invitation code".into())),
+        }
+    }
+
+    /// Use up an invitation; fails when a concurrent registration got there first.
+    /// Runs on the caller's transaction so a failed registration gives the invitation back.
+    pub async fn claim<C: ConnectionTrait>(conn: &C, invitation_id: i32) -> Result<(), AppError> {
+        let result = invitations::Entity::update_many()
+            .col_expr(invitations::Column::AcceptedAt, Expr::value(Utc::now().naive_utc()))
+            .filter(invitations::Column::InvitationId.eq(invitation_id))
+            .filter(invitations::Column::AcceptedAt.is_null())
+            .filter(invitations::Column::RevokedAt.is_null())
+            .exec(conn)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(AppError::BadRequest("Invalid or expired invitation code".into()));
+        }
+        Ok(())
+    }
+
+    /// Record the account a claimed invitation was registered as
+    pub async fn set_accepted_by<C: ConnectionTrait>(conn: &C, invitation_id: i32, user_id: i32) -> Result<(), AppError> {
+        invitations::Entity::update_many()
+            .col_expr(invitations::Column::AcceptedBy, Expr::value(user_id))
+            .filter(invitations::Column::InvitationId.eq(invitation_id))
+            .exec(conn)
+            .await?;
+        Ok(())
+    }
+}
@@ -1,9 +1,14 @@
-use sea_orm::{DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter};
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, ConnectionTrait, TransactionTrait,
+};
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDate;
 use crate::{
     entities::{invoices, orders},
     errors::AppError,
+    money::{self, Money},
+    services::export::{self, ExportFile, ExportFormat},
+    services::ledger::LedgerService,
 };
 
 #[derive(Clone)]
@@ -16,8 +21,9 @@ pub struct CreateInvoiceRequest {
     pub order_id: i32,
     pub transaction_id: String,
     pub invoice_date: NaiveDate,
-    pub total_amount: f64,
+    pub total_amount: Money,
     pub description: String,
+    pub branch_id: i32,
 }
 
 #[derive(Serialize)]
@@ -26,8 +32,10 @@ pub struct InvoiceResponse {
     pub order_id: i32,
     pub transaction_id: String,
     pub invoice_date: NaiveDate,
-    pub total_amount: f64,
+    pub total_amount: Money,
     pub description: String,
+    pub branch_id: i32,
+    pub currency: &'static str,
 }
 
 impl InvoicesService {
@@ -37,17 +45,26 @@ impl InvoicesService {
 
     /// Generate an invoice for an order
     pub async fn create_invoice(&self, req: CreateInvoiceRequest) -> Result<InvoiceResponse, AppError> {
+        Self::create_invoice_on(&self.db, req).await
+    }
+
+    /// Generate an invoice on the caller's connection, e.g. within the transaction creating its order
+    pub(crate) async fn create_invoice_on<C: ConnectionTrait>(
+        conn: &C,
+        req: CreateInvoiceRequest,
+    ) -> Result<InvoiceResponse, AppError> {
         // Verify the order exists
         let order = orders::Entity::find_by_id(req.order_id)
-            .one(&self.db)
+            .one(conn)
             .await?
             .ok_or(AppError::BadRequest("Order not found".into()))?;
 
         // Check if an invoice already exists for this order
-        if let Some(_) = invoices::Entity::find()
+        if invoices::Entity::find()
             .filter(invoices::Column::OrderId.eq(req.order_id))
-            .one(&self.db)
+            .one(conn)
             .await?
+            .is_some()
         {
             return Err(AppError::BadRequest("Invoice already exists for this order".into()));
         }
@@ -59,10 +76,11 @@ impl InvoicesService {
             invoice_date: Set(req.invoice_date),
             total_amount: Set(req.total_amount),
             description: Set(req.description.clone()),
+            branch_id: Set(req.branch_id),
             ..Default::default()
         };
 
-        let invoice = new_invoice.insert(&self.db).await?;
+        let invoice = new_invoice.insert(conn).await?;
 
         Ok(InvoiceResponse {
             invoice_id: invoice.invoice_id,
@@ -71,12 +89,18 @@ impl InvoicesService {
             invoice_date: invoice.invoice_date,
             total_amount: invoice.total_amount,
             description: invoice.description,
+            branch_id: invoice.branch_id,
+            currency: money::base_currency(),
         })
     }
 
-    /// Fetch all invoices
-    pub async fn get_all_invoices(&self) -> Result<Vec<InvoiceResponse>, AppError> {
-        let invoices_list = invoices::Entity::find().all(&self.db).await?;
+    /// Fetch all invoices, optionally of one branch
+    pub async fn get_all_invoices(&self, branch_id: Option<i32>) -> Result<Vec<InvoiceResponse>, AppError> {
+        let mut query = invoices::Entity::find();
+        if let Some(branch_id) = branch_id {
+            query = query.filter(invoices::Column::BranchId.eq(branch_id));
+        }
+        let invoices_list = query.all(&self.db).await?;
 
         Ok(invoices_list
             .into_iter()
@@ -87,16 +111,52 @@ impl InvoicesService {
                 invoice_date: inv.invoice_date,
                 total_amount: inv.total_amount,
                 description: inv.description,
+                branch_id: inv.branch_id,
+                currency: money::base_currency(),
             })
             .collect())
     }
 
-    /// Fetch invoices by order ID
-    pub async fn get_invoices_by_order(&self, order_id: i32) -> Result<Vec<InvoiceResponse>, AppError> {
-        let invoices_list = invoices::Entity::find()
-            .filter(invoices::Column::OrderId.eq(order_id))
-            .all(&self.db)
-            .await?;
+    /// Export all invoices (optionally of one branch) as CSV or XLSX
+    pub async fn export_invoices(&self, format: ExportFormat, branch_id: Option<i32>) -> Result<ExportFile, AppError> {
+        let mut query = invoices::Entity::find();
+        if let Some(branch_id) = branch_id {
+            query = query.filter(invoices::Column::BranchId.eq(branch_id));
+        }
+        let invoices_list = query.all(&self.db).await?;
+        export::rows_file("invoices", "Invoices", format, &invoices_list)
+    }
+
+    /// Fetch single invoice by ID, within one branch unless `branch_id` is `None`
+    pub async fn get_invoice(&self, invoice_id: i32, branch_id: Option<i32>) -> Result<InvoiceResponse, AppError> {
+        let mut query = invoices::Entity::find_by_id(invoice_id);
+        if let Some(branch_id) = branch_id {
+            query = query.filter(invoices::Column::BranchId.eq(branch_id));
+        }
+        let inv = query
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound("Invoice not found".into()))?;
+
+        Ok(InvoiceResponse {
+            invoice_id: inv.invoice_id,
+            order_id: inv.order_id,
+            transaction_id: inv.transaction_id,
+            invoice_date: inv.invoice_date,
+            total_amount: inv.total_amount,
+            description: inv.description,
+            branch_id: inv.branch_id,
+            currency: money::base_currency(),
+        })
+    }
+
+    /// Fetch invoices by order ID, within one branch unless `branch_id` is `None`
+    pub async fn get_invoices_by_order(&self, order_id: i32, branch_id: Option<i32>) -> Result<Vec<InvoiceResponse>, AppError> {
+        let mut query = invoices::Entity::find().filter(invoices::Column::OrderId.eq(order_id));
+        if let Some(branch_id) = branch_id {
+            query = query.filter(invoices::Column::BranchId.eq(branch_id));
+        }
+        let invoices_list = query.all(&self.db).await?;
 
         Ok(invoices_list
             .into_iter()
@@ -107,19 +167,25 @@ impl InvoicesService {
                 invoice_date: inv.invoice_date,
                 total_amount: inv.total_amount,
                 description: inv.description,
+                branch_id: inv.branch_id,
+                currency: money::base_currency(),
             })
             .collect())
     }
 
-    /// Delete invoice
-    pub async fn delete_invoice(&self, invoice_id: i32) -> Result<(), AppError> {
+    /// Delete an invoice of a branch (cascade deletes its payments)
+    pub async fn delete_invoice(&self, invoice_id: i32, branch_id: i32) -> Result<(), AppError> {
         let invoice: invoices::ActiveModel = invoices::Entity::find_by_id(invoice_id)
+            .filter(invoices::Column::BranchId.eq(branch_id))
             .one(&self.db)
             .await?
-            .ok_or(AppError::NotFound)?
+            .ok_or(AppError::NotFound("Invoice not found".into()))?
             .into();
 
-        invoice.delete(&self.db).await?;
+        let txn = self.db.begin().await?;
+        LedgerService::remove_invoice_payments(&txn, invoice_id).await?;
+        invoice.delete(&txn).await?;
+        txn.commit().await?;
         Ok(())
     }
 }
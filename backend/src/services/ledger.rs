@@ -0,0 +1,954 @@
+use std::collections::{BTreeMap, HashMap};
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder, QuerySelect,
+    ConnectionTrait, TransactionTrait, FromQueryResult, JoinType, RelationTrait, PaginatorTrait,
+};
+use sea_orm::sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use chrono::{Months, NaiveDate, Utc};
+use crate::{
+    entities::{accounts, expenses, invoices, journal_entries, journal_lines, orders, payments},
+    errors::AppError,
+    money::{self, Money},
+    services::reports::{PeriodSummary, ReportsService},
+};
+
+/// Codes of the system accounts seeded by the migration
+pub const CASH: &str = "1000";
+pub const ACCOUNTS_RECEIVABLE: &str = "1100";
+pub const BANK: &str = "1010";
+pub const FIXED_ASSETS: &str = "1500";
+pub const ACCOUNTS_PAYABLE: &str = "2000";
+pub const OWNERS_EQUITY: &str = "3000";
+pub const OPENING_BALANCE_EQUITY: &str = "3900";
+pub const SERVICE_REVENUE: &str = "4000";
+pub const FX_GAIN_LOSS: &str = "4900";
+pub const OPERATING_EXPENSES: &str = "5000";
+
+/// `source_type` of entries posted automatically; manual entries have none
+pub const SOURCE_ORDER: &str = "order";
+pub const SOURCE_EXPENSE: &str = "expense";
+pub const SOURCE_PAYMENT: &str = "payment";
+pub const SOURCE_OPENING: &str = "opening";
+
+#[derive(Clone)]
+pub struct LedgerService {
+    pub db: DatabaseConnection,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountType {
+    Asset,
+    Liability,
+    Equity,
+    Revenue,
+    Expense,
+}
+
+impl AccountType {
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value.to_ascii_lowercase().as_str() {
+            "asset" => Ok(AccountType::Asset),
+            "liability" => Ok(AccountType::Liability),
+            "equity" => Ok(AccountType::Equity),
+            "revenue" => Ok(AccountType::Revenue),
+            "expense" => Ok(AccountType::Expense),
+            other => Err(AppError::BadRequest(format!(
+                "Unknown account type '{}', expected asset, liability, equity, revenue or expense",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountType::Asset => "asset",
+            AccountType::Liability => "liability",
+            AccountType::Equity => "equity",
+            AccountType::Revenue => "revenue",
+            AccountType::Expense => "expense",
+        }
+    }
+
+    /// Assets and expenses grow with debits, everything else with credits
+    pub fn is_debit_normal(&self) -> bool {
+        matches!(self, AccountType::Asset | AccountType::Expense)
+    }
+}
+
+/// Balance-sheet grouping of an account
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountCategory {
+    Cash,
+    Bank,
+    Receivable,
+    FixedAsset,
+    Payable,
+}
+
+impl AccountCategory {
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value.to_ascii_lowercase().as_str() {
+            "cash" => Ok(AccountCategory::Cash),
+            "bank" => Ok(AccountCategory::Bank),
+            "receivable" => Ok(AccountCategory::Receivable),
+            "fixed_asset" => Ok(AccountCategory::FixedAsset),
+            "payable" => Ok(AccountCategory::Payable),
+            other => Err(AppError::BadRequest(format!(
+                "Unknown account category '{}', expected cash, bank, receivable, fixed_asset or payable",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountCategory::Cash => "cash",
+            AccountCategory::Bank => "bank",
+            AccountCategory::Receivable => "receivable",
+            AccountCategory::FixedAsset => "fixed_asset",
+            AccountCategory::Payable => "payable",
+        }
+    }
+
+    /// Categories that only make sense on one side of the balance sheet
+    fn allowed_for(&self, account_type: AccountType) -> bool {
+        match self {
+            AccountCategory::Payable => account_type == AccountType::Liability,
+            _ => account_type == AccountType::Asset,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateAccountRequest {
+    pub code: String,
+    pub name: String,
+    pub account_type: String,
+    pub category: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct OpeningBalance {
+    pub account_id: i32,
+    /// Balance on the account's normal side; negative for a contra balance
+    pub amount: Money,
+}
+
+#[derive(Deserialize)]
+pub struct OpeningBalancesRequest {
+    pub as_of: NaiveDate,
+    pub balances: Vec<OpeningBalance>,
+    pub created_by: i32,
+}
+
+#[derive(Deserialize)]
+pub struct JournalLineRequest {
+    pub account_id: i32,
+    pub debit: Option<Money>,
+    pub credit: Option<Money>,
+    pub memo: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateJournalEntryRequest {
+    pub entry_date: NaiveDate,
+    pub description: String,
+    pub lines: Vec<JournalLineRequest>,
+    pub created_by: i32,
+}
+
+#[derive(Serialize)]
+pub struct JournalLineResponse {
+    pub line_id: i32,
+    pub account_id: i32,
+    pub account_code: String,
+    pub account_name: String,
+    pub debit: Money,
+    pub credit: Money,
+    pub memo: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct JournalEntryResponse {
+    pub entry_id: i32,
+    pub entry_date: NaiveDate,
+    pub description: String,
+    pub source_type: Option<String>,
+    pub source_id: Option<i32>,
+    pub created_by: Option<i32>,
+    pub lines: Vec<JournalLineResponse>,
+    pub currency: &'static str,
+}
+
+/// Net balance of one account; `debit`/`credit` hold whichever side the balance falls on
+#[derive(Serialize)]
+pub struct TrialBalanceLine {
+    pub account_id: i32,
+    pub code: String,
+    pub name: String,
+    pub account_type: String,
+    pub debit: Money,
+    pub credit: Money,
+}
+
+#[derive(Serialize)]
+pub struct TrialBalance {
+    pub as_of: NaiveDate,
+    pub lines: Vec<TrialBalanceLine>,
+    pub total_debit: Money,
+    pub total_credit: Money,
+    pub balanced: bool,
+    pub currency: &'static str,
+}
+
+/// Monthly figures computed from the operational tables and from the ledger
+#[derive(Serialize)]
+pub struct Reconciliation {
+    pub reports: PeriodSummary,
+    pub ledger: PeriodSummary,
+    pub matches: bool,
+}
+
+#[derive(Serialize)]
+pub struct RebuildSummary {
+    pub orders: usize,
+    pub expenses: usize,
+    pub payments: usize,
+}
+
+/// One side of a posting before it is written
+struct Posting {
+    account_id: i32,
+    debit: Money,
+    credit: Money,
+    memo: Option<String>,
+}
+
+/// Two-line entry posted automatically for an order, expense or payment
+struct AutoPosting<'a> {
+    source: (&'a str, i32),
+    entry_date: NaiveDate,
+    description: String,
+    created_by: Option<i32>,
+    debit: &'a str,
+    credit: &'a str,
+    amount: Money,
+    memo: &'a str,
+}
+
+/// Debit and credit sums of a `GROUP BY` over journal lines
+#[derive(FromQueryResult)]
+pub(crate) struct LineTotal {
+    pub account_id: i32,
+    pub memo: Option<String>,
+    pub debit: Option<Money>,
+    pub credit: Option<Money>,
+}
+
+impl LedgerService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Look up a system account by code
+    async fn account_id<C: ConnectionTrait>(conn: &C, code: &str) -> Result<i32, AppError> {
+        let account = accounts::Entity::find()
+            .filter(accounts::Column::Code.eq(code))
+            .one(conn)
+            .await?
+            .ok_or_else(|| {
+                tracing::error!("Ledger account {} is missing, run the migrations", code);
+                AppError::InternalError
+            })?;
+        Ok(account.account_id)
+    }
+
+    /// Reject postings that don't balance or mix both sides on one line
+    fn validate_postings(postings: &[Posting]) -> Result<(), AppError> {
+        if postings.len() < 2 {
+            return Err(AppError::BadRequest("A journal entry needs at least two lines".into()));
+        }
+
+        for posting in postings {
+            if posting.debit.is_negative() || posting.credit.is_negative() {
+                return Err(AppError::BadRequest("Debit and credit amounts cannot be negative".into()));
+            }
+            if posting.debit.is_zero() == posting.credit.is_zero() {
+                return Err(AppError::BadRequest(
+                    "Each line needs either a debit or a credit amount".into(),
+                ));
+            }
+        }
+
+        let debits: Money = postings.iter().map(|p| p.debit).sum();
+        let credits: Money = postings.iter().map(|p| p.credit).sum();
+        if debits != credits {
+            return Err(AppError::BadRequest(format!(
+                "Journal entry is not balanced: debits {} != credits {}",
+                debits, credits
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Remove the entries of a source record
+    pub async fn remove_source<C: ConnectionTrait>(conn: &C, source_type: &str, source_id: i32) -> Result<(), AppError> {
+        let entry_ids: Vec<i32> = journal_entries::Entity::find()
+            .select_only()
+            .column(journal_entries::Column::EntryId)
+            .filter(journal_entries::Column::SourceType.eq(source_type))
+            .filter(journal_entries::Column::SourceId.eq(source_id))
+            .into_tuple()
+            .all(conn)
+            .await?;
+
+        if entry_ids.is_empty() {
+            return Ok(());
+        }
+
+        journal_lines::Entity::delete_many()
+            .filter(journal_lines::Column::EntryId.is_in(entry_ids.clone()))
+            .exec(conn)
+            .await?;
+        journal_entries::Entity::delete_many()
+            .filter(journal_entries::Column::EntryId.is_in(entry_ids))
+            .exec(conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Write a balanced entry and its lines on the caller's connection, which has
+    /// to be a transaction so the entry lands together with the record it books.
+    /// Entries of a source replace whatever was posted for it before.
+    async fn post<C: ConnectionTrait>(
+        conn: &C,
+        entry_date: NaiveDate,
+        description: String,
+        source: Option<(&str, i32)>,
+        created_by: Option<i32>,
+        postings: Vec<Posting>,
+    ) -> Result<journal_entries::Model, AppError> {
+        Self::validate_postings(&postings)?;
+
+        if let Some((source_type, source_id)) = source {
+            Self::remove_source(conn, source_type, source_id).await?;
+        }
+
+        let entry = journal_entries::ActiveModel {
+            entry_date: Set(entry_date),
+            description: Set(description),
+            source_type: Set(source.map(|(source_type, _)| source_type.to_string())),
+            source_id: Set(source.map(|(_, source_id)| source_id)),
+            created_by: Set(created_by),
+            created_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+
+        let lines = postings.into_iter().map(|posting| journal_lines::ActiveModel {
+            entry_id: Set(entry.entry_id),
+            account_id: Set(posting.account_id),
+            debit: Set(posting.debit),
+            credit: Set(posting.credit),
+            memo: Set(posting.memo),
+            ..Default::default()
+        });
+        journal_lines::Entity::insert_many(lines).exec(conn).await?;
+
+        Ok(entry)
+    }
+
+    /// Post (or re-post) the entry of a source record.
+    /// Zero amounts leave nothing in the ledger.
+    async fn post_source<C: ConnectionTrait>(conn: &C, auto: AutoPosting<'_>) -> Result<(), AppError> {
+        let (source_type, source_id) = auto.source;
+        if auto.amount.is_zero() {
+            return Self::remove_source(conn, source_type, source_id).await;
+        }
+
+        // A negative amount (e.g. a refund) swaps the sides
+        let (debit_code, credit_code, amount) = if auto.amount.is_negative() {
+            (auto.credit, auto.debit, -auto.amount)
+        } else {
+            (auto.debit, auto.credit, auto.amount)
+        };
+
+        let postings = vec![
+            Posting {
+                account_id: Self::account_id(conn, debit_code).await?,
+                debit: amount,
+                credit: Money::ZERO,
+                memo: Some(auto.memo.to_string()),
+            },
+            Posting {
+                account_id: Self::account_id(conn, credit_code).await?,
+                debit: Money::ZERO,
+                credit: amount,
+                memo: Some(auto.memo.to_string()),
+            },
+        ];
+
+        Self::post(conn, auto.entry_date, auto.description, Some(auto.source), auto.created_by, postings).await?;
+        Ok(())
+    }
+
+    /// Order billed: Dr Accounts Receivable / Cr Service Revenue.
+    /// Inter-branch transfers cancel out in the consolidated books and are not posted.
+    pub async fn post_order<C: ConnectionTrait>(conn: &C, order: &orders::Model) -> Result<(), AppError> {
+        if order.counterparty_branch_id.is_some() {
+            return Self::remove_source(conn, SOURCE_ORDER, order.order_id).await;
+        }
+
+        Self::post_source(conn, AutoPosting {
+            source: (SOURCE_ORDER, order.order_id),
+            entry_date: order.order_date,
+            description: format!("Order #{} - {}", order.order_id, order.patient_name),
+            created_by: order.modified_by.or(order.created_by),
+            debit: ACCOUNTS_RECEIVABLE,
+            credit: SERVICE_REVENUE,
+            amount: order.total_amount,
+            memo: &order.description,
+        })
+        .await
+    }
+
+    /// Expense paid: Dr Operating Expenses / Cr Cash, memo carries the label
+    pub async fn post_expense<C: ConnectionTrait>(conn: &C, expense: &expenses::Model) -> Result<(), AppError> {
+        Self::post_source(conn, AutoPosting {
+            source: (SOURCE_EXPENSE, expense.expense_id),
+            entry_date: expense.expense_date,
+            description: format!("Expense #{} - {}", expense.expense_id, expense.description),
+            created_by: expense.modified_by.or(expense.created_by),
+            debit: OPERATING_EXPENSES,
+            credit: CASH,
+            amount: expense.amount,
+            memo: &expense.label,
+        })
+        .await
+    }
+
+    /// Invoice payment received: Dr Cash / Cr Accounts Receivable.
+    /// A foreign-currency payment settles the receivable at the order's rate; the
+    /// difference to the cash received goes to the exchange gain/loss account.
+    pub async fn post_payment<C: ConnectionTrait>(conn: &C, payment: &payments::Model) -> Result<(), AppError> {
+        let source = (SOURCE_PAYMENT, payment.payment_id);
+        let description = format!("Payment #{} for invoice #{}", payment.payment_id, payment.invoice_id);
+
+        // Settling an inter-branch transfer only moves cash between branches
+        let order = invoices::Entity::find_by_id(payment.invoice_id)
+            .find_also_related(orders::Entity)
+            .one(conn)
+            .await?
+            .and_then(|(_, order)| order);
+        if order.is_some_and(|order| order.counterparty_branch_id.is_some()) {
+            return Self::remove_source(conn, SOURCE_PAYMENT, payment.payment_id).await;
+        }
+
+        if payment.fx_gain_loss.is_zero() {
+            return Self::post_source(conn, AutoPosting {
+                source,
+                entry_date: payment.payment_date,
+                description,
+                created_by: payment.created_by,
+                debit: CASH,
+                credit: ACCOUNTS_RECEIVABLE,
+                amount: payment.amount,
+                memo: &payment.method,
+            })
+            .await;
+        }
+
+        let memo = Some(payment.method.clone());
+        let fx = payment.fx_gain_loss;
+        let postings = vec![
+            Posting {
+                account_id: Self::account_id(conn, CASH).await?,
+                debit: payment.amount,
+                credit: Money::ZERO,
+                memo: memo.clone(),
+            },
+            Posting {
+                account_id: Self::account_id(conn, ACCOUNTS_RECEIVABLE).await?,
+                debit: Money::ZERO,
+                credit: payment.amount - fx,
+                memo: memo.clone(),
+            },
+            // A gain is credited, a loss debited
+            Posting {
+                account_id: Self::account_id(conn, FX_GAIN_LOSS).await?,
+                debit: (-fx).max(Money::ZERO),
+                credit: fx.max(Money::ZERO),
+                memo,
+            },
+        ];
+
+        Self::post(conn, payment.payment_date, description, Some(source), payment.created_by, postings).await?;
+        Ok(())
+    }
+
+    /// Remove the entries of an order and of the payments made against its invoices
+    pub async fn remove_order<C: ConnectionTrait>(conn: &C, order_id: i32) -> Result<(), AppError> {
+        let invoice_ids: Vec<i32> = invoices::Entity::find()
+            .select_only()
+            .column(invoices::Column::InvoiceId)
+            .filter(invoices::Column::OrderId.eq(order_id))
+            .into_tuple()
+            .all(conn)
+            .await?;
+
+        for invoice_id in invoice_ids {
+            Self::remove_invoice_payments(conn, invoice_id).await?;
+        }
+
+        Self::remove_source(conn, SOURCE_ORDER, order_id).await
+    }
+
+    /// Remove the entries of every payment made against an invoice
+    pub async fn remove_invoice_payments<C: ConnectionTrait>(conn: &C, invoice_id: i32) -> Result<(), AppError> {
+        let payment_ids: Vec<i32> = payments::Entity::find()
+            .select_only()
+            .column(payments::Column::PaymentId)
+            .filter(payments::Column::InvoiceId.eq(invoice_id))
+            .into_tuple()
+            .all(conn)
+            .await?;
+
+        for payment_id in payment_ids {
+            Self::remove_source(conn, SOURCE_PAYMENT, payment_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Fetch the chart of accounts
+    pub async fn get_accounts(&self) -> Result<Vec<accounts::Model>, AppError> {
+        let accounts_list = accounts::Entity::find()
+            .order_by_asc(accounts::Column::Code)
+            .all(&self.db)
+            .await?;
+        Ok(accounts_list)
+    }
+
+    /// Add an account to the chart of accounts
+    pub async fn create_account(&self, req: CreateAccountRequest) -> Result<accounts::Model, AppError> {
+        let account_type = AccountType::parse(&req.account_type)?;
+        let category = req.category.as_deref().map(AccountCategory::parse).transpose()?;
+        let code = req.code.trim().to_string();
+
+        if let Some(category) = category
+            && !category.allowed_for(account_type)
+        {
+            return Err(AppError::BadRequest(format!(
+                "Category {} cannot be used for {} accounts",
+                category.as_str(),
+                account_type.as_str()
+            )));
+        }
+
+        if code.is_empty() || req.name.trim().is_empty() {
+            return Err(AppError::BadRequest("Account code and name are required".into()));
+        }
+
+        if accounts::Entity::find()
+            .filter(accounts::Column::Code.eq(code.as_str()))
+            .one(&self.db)
+            .await?
+            .is_some()
+        {
+            return Err(AppError::BadRequest(format!("Account code {} already exists", code)));
+        }
+
+        let account = accounts::ActiveModel {
+            code: Set(code),
+            name: Set(req.name.trim().to_string()),
+            account_type: Set(account_type.as_str().to_string()),
+            category: Set(category.map(|c| c.as_str().to_string())),
+            is_system: Set(false),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+
+        Ok(account)
+    }
+
+    /// Record a manual journal entry
+    pub async fn create_entry(&self, req: CreateJournalEntryRequest) -> Result<JournalEntryResponse, AppError> {
+        if req.description.trim().is_empty() {
+            return Err(AppError::BadRequest("Description is required".into()));
+        }
+
+        let account_ids: Vec<i32> = req.lines.iter().map(|line| line.account_id).collect();
+        let known = accounts::Entity::find()
+            .filter(accounts::Column::AccountId.is_in(account_ids.clone()))
+            .all(&self.db)
+            .await?;
+        if let Some(unknown) = account_ids
+            .iter()
+            .find(|id| !known.iter().any(|account| account.account_id == **id))
+        {
+            return Err(AppError::BadRequest(format!("Account {} does not exist", unknown)));
+        }
+
+        let postings = req
+            .lines
+            .into_iter()
+            .map(|line| Posting {
+                account_id: line.account_id,
+                debit: line.debit.unwrap_or(Money::ZERO),
+                credit: line.credit.unwrap_or(Money::ZERO),
+                memo: line.memo,
+            })
+            .collect();
+
+        let txn = self.db.begin().await?;
+        let entry = Self::post(&txn, req.entry_date, req.description, None, Some(req.created_by), postings).await?;
+        txn.commit().await?;
+
+        let mut entries = self.entries_with_lines(vec![entry]).await?;
+        entries.pop().ok_or(AppError::InternalError)
+    }
+
+    /// Record the balances the books start from, offset against Opening Balance Equity.
+    /// Entering them again replaces the previous opening entry.
+    pub async fn set_opening_balances(&self, req: OpeningBalancesRequest) -> Result<JournalEntryResponse, AppError> {
+        let opening_equity_id = Self::account_id(&self.db, OPENING_BALANCE_EQUITY).await?;
+
+        let accounts_by_id: HashMap<i32, accounts::Model> = accounts::Entity::find()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|account| (account.account_id, account))
+            .collect();
+
+        let mut postings = Vec::new();
+        for balance in req.balances {
+            let amount = balance.amount;
+            if amount.is_zero() {
+                continue;
+            }
+            if balance.account_id == opening_equity_id {
+                return Err(AppError::BadRequest(
+                    "Opening Balance Equity is the offset account and cannot be set directly".into(),
+                ));
+            }
+            let account = accounts_by_id.get(&balance.account_id).ok_or_else(|| {
+                AppError::BadRequest(format!("Account {} does not exist", balance.account_id))
+            })?;
+
+            // Positive amounts sit on the account's normal side
+            let debit_side = AccountType::parse(&account.account_type)?.is_debit_normal() != amount.is_negative();
+            postings.push(Posting {
+                account_id: account.account_id,
+                debit: if debit_side { amount.abs() } else { Money::ZERO },
+                credit: if debit_side { Money::ZERO } else { amount.abs() },
+                memo: Some("Opening balance".into()),
+            });
+        }
+
+        if postings.is_empty() {
+            return Err(AppError::BadRequest("No opening balances given".into()));
+        }
+
+        let offset: Money = postings.iter().map(|p| p.debit - p.credit).sum();
+        if !offset.is_zero() {
+            postings.push(Posting {
+                account_id: opening_equity_id,
+                debit: (-offset).max(Money::ZERO),
+                credit: offset.max(Money::ZERO),
+                memo: Some("Opening balance offset".into()),
+            });
+        }
+
+        let txn = self.db.begin().await?;
+        let entry = Self::post(
+            &txn,
+            req.as_of,
+            "Opening balances".into(),
+            Some((SOURCE_OPENING, 0)),
+            Some(req.created_by),
+            postings,
+        )
+        .await?;
+        txn.commit().await?;
+
+        let mut entries = self.entries_with_lines(vec![entry]).await?;
+        entries.pop().ok_or(AppError::InternalError)
+    }
+
+    /// Delete a manual journal entry; automatic entries follow their source record
+    pub async fn delete_entry(&self, entry_id: i32) -> Result<(), AppError> {
+        let entry = journal_entries::Entity::find_by_id(entry_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound("Journal entry not found".into()))?;
+
+        if entry.source_type.as_deref() == Some(SOURCE_OPENING) {
+            return Err(AppError::BadRequest(
+                "Opening balances are replaced by entering them again".into(),
+            ));
+        }
+        if let Some(source_type) = entry.source_type {
+            return Err(AppError::BadRequest(format!(
+                "Entry was posted automatically for {} #{}; change the {} instead",
+                source_type,
+                entry.source_id.unwrap_or_default(),
+                source_type
+            )));
+        }
+
+        let txn = self.db.begin().await?;
+        journal_lines::Entity::delete_many()
+            .filter(journal_lines::Column::EntryId.eq(entry_id))
+            .exec(&txn)
+            .await?;
+        journal_entries::Entity::delete_by_id(entry_id).exec(&txn).await?;
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Fetch journal entries dated within an inclusive range
+    pub async fn get_entries(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<JournalEntryResponse>, AppError> {
+        let entries = journal_entries::Entity::find()
+            .filter(journal_entries::Column::EntryDate.between(from, to))
+            .order_by_asc(journal_entries::Column::EntryDate)
+            .order_by_asc(journal_entries::Column::EntryId)
+            .all(&self.db)
+            .await?;
+
+        self.entries_with_lines(entries).await
+    }
+
+    async fn entries_with_lines(
+        &self,
+        entries: Vec<journal_entries::Model>,
+    ) -> Result<Vec<JournalEntryResponse>, AppError> {
+        let entry_ids: Vec<i32> = entries.iter().map(|e| e.entry_id).collect();
+
+        let lines = journal_lines::Entity::find()
+            .filter(journal_lines::Column::EntryId.is_in(entry_ids))
+            .order_by_asc(journal_lines::Column::LineId)
+            .all(&self.db)
+            .await?;
+
+        let accounts_by_id: HashMap<i32, accounts::Model> = accounts::Entity::find()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|account| (account.account_id, account))
+            .collect();
+
+        let mut lines_by_entry: HashMap<i32, Vec<JournalLineResponse>> = HashMap::new();
+        for line in lines {
+            let account = accounts_by_id.get(&line.account_id);
+            lines_by_entry.entry(line.entry_id).or_default().push(JournalLineResponse {
+                line_id: line.line_id,
+                account_id: line.account_id,
+                account_code: account.map(|a| a.code.clone()).unwrap_or_default(),
+                account_name: account.map(|a| a.name.clone()).unwrap_or_default(),
+                debit: line.debit,
+                credit: line.credit,
+                memo: line.memo,
+            });
+        }
+
+        Ok(entries
+            .into_iter()
+            .map(|entry| JournalEntryResponse {
+                lines: lines_by_entry.remove(&entry.entry_id).unwrap_or_default(),
+                entry_id: entry.entry_id,
+                entry_date: entry.entry_date,
+                description: entry.description,
+                source_type: entry.source_type,
+                source_id: entry.source_id,
+                created_by: entry.created_by,
+                currency: money::base_currency(),
+            })
+            .collect())
+    }
+
+    /// Debit and credit sums per account (and memo, when `by_memo`) for entries
+    /// dated up to `to`, optionally from `from` and of one source type
+    pub(crate) async fn line_totals(
+        &self,
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+        source_type: Option<&str>,
+        by_memo: bool,
+    ) -> Result<Vec<LineTotal>, AppError> {
+        let mut query = journal_lines::Entity::find()
+            .select_only()
+            .column(journal_lines::Column::AccountId)
+            .column_as(Expr::col((journal_lines::Entity, journal_lines::Column::Debit)).sum(), "debit")
+            .column_as(Expr::col((journal_lines::Entity, journal_lines::Column::Credit)).sum(), "credit")
+            .join(JoinType::InnerJoin, journal_lines::Relation::JournalEntries.def())
+            .filter(journal_entries::Column::EntryDate.lte(to))
+            .group_by(journal_lines::Column::AccountId);
+
+        if let Some(from) = from {
+            query = query.filter(journal_entries::Column::EntryDate.gte(from));
+        }
+        if let Some(source_type) = source_type {
+            query = query.filter(journal_entries::Column::SourceType.eq(source_type));
+        }
+        query = if by_memo {
+            query
+                .column(journal_lines::Column::Memo)
+                .group_by(journal_lines::Column::Memo)
+        } else {
+            query.column_as(Expr::value(Option::<String>::None), "memo")
+        };
+
+        Ok(query.into_model::<LineTotal>().all(&self.db).await?)
+    }
+
+    /// Balance of every account as of a date
+    pub async fn trial_balance(&self, as_of: NaiveDate) -> Result<TrialBalance, AppError> {
+        let totals: HashMap<i32, Money> = self
+            .line_totals(None, as_of, None, false)
+            .await?
+            .into_iter()
+            .map(|total| {
+                let net = Money::from_sum(total.debit) - Money::from_sum(total.credit);
+                (total.account_id, net)
+            })
+            .collect();
+
+        let lines: Vec<TrialBalanceLine> = self
+            .get_accounts()
+            .await?
+            .into_iter()
+            .filter_map(|account| {
+                let net = *totals.get(&account.account_id)?;
+                Some(TrialBalanceLine {
+                    account_id: account.account_id,
+                    code: account.code,
+                    name: account.name,
+                    account_type: account.account_type,
+                    debit: net.max(Money::ZERO),
+                    credit: (-net).max(Money::ZERO),
+                })
+            })
+            .collect();
+
+        let total_debit: Money = lines.iter().map(|line| line.debit).sum();
+        let total_credit: Money = lines.iter().map(|line| line.credit).sum();
+
+        Ok(TrialBalance {
+            as_of,
+            lines,
+            total_debit,
+            total_credit,
+            balanced: total_debit == total_credit,
+            currency: money::base_currency(),
+        })
+    }
+
+    /// Rebuild a period's report figures from the automatic postings alone,
+    /// so manual adjustments don't distort the comparison
+    pub async fn summarize_period(&self, from: NaiveDate, to: NaiveDate) -> Result<PeriodSummary, AppError> {
+        let revenue_id = Self::account_id(&self.db, SERVICE_REVENUE).await?;
+        let expenses_id = Self::account_id(&self.db, OPERATING_EXPENSES).await?;
+        let fx_id = Self::account_id(&self.db, FX_GAIN_LOSS).await?;
+
+        // Counted from the orders themselves: zero-amount orders post no entry
+        let total_orders = orders::Entity::find()
+            .filter(orders::Column::OrderDate.between(from, to))
+            .filter(orders::Column::CounterpartyBranchId.is_null())
+            .count(&self.db)
+            .await? as i32;
+
+        let total_income: Money = self
+            .line_totals(Some(from), to, Some(SOURCE_ORDER), false)
+            .await?
+            .into_iter()
+            .filter(|total| total.account_id == revenue_id)
+            .map(|total| Money::from_sum(total.credit) - Money::from_sum(total.debit))
+            .sum();
+
+        let mut expenses_by_label: BTreeMap<String, Money> = BTreeMap::new();
+        for total in self.line_totals(Some(from), to, Some(SOURCE_EXPENSE), true).await? {
+            if total.account_id != expenses_id {
+                continue;
+            }
+            *expenses_by_label.entry(total.memo.unwrap_or_default()).or_default() +=
+                Money::from_sum(total.debit) - Money::from_sum(total.credit);
+        }
+        let total_expenses: Money = expenses_by_label.values().copied().sum();
+
+        let fx_gain_loss: Money = self
+            .line_totals(Some(from), to, Some(SOURCE_PAYMENT), false)
+            .await?
+            .into_iter()
+            .filter(|total| total.account_id == fx_id)
+            .map(|total| Money::from_sum(total.credit) - Money::from_sum(total.debit))
+            .sum();
+
+        Ok(PeriodSummary {
+            from,
+            to,
+            branch_id: None,
+            total_orders,
+            total_income,
+            total_expenses,
+            expenses_by_label,
+            fx_gain_loss,
+            net_profit: total_income - total_expenses + fx_gain_loss,
+            currency: money::base_currency(),
+        })
+    }
+
+    /// Compare a period's report figures with the ones rebuilt from the ledger
+    pub async fn reconcile(&self, from: NaiveDate, to: NaiveDate) -> Result<Reconciliation, AppError> {
+        let reports = ReportsService::new(self.db.clone()).summarize_period(from, to).await?;
+        let ledger = self.summarize_period(from, to).await?;
+
+        let matches = reports.total_orders == ledger.total_orders
+            && reports.total_income == ledger.total_income
+            && reports.total_expenses == ledger.total_expenses
+            && reports.expenses_by_label == ledger.expenses_by_label
+            && reports.fx_gain_loss == ledger.fx_gain_loss;
+
+        Ok(Reconciliation { reports, ledger, matches })
+    }
+
+    /// Reconcile a whole month (YYYY-MM-01)
+    pub async fn reconcile_month(&self, month: NaiveDate) -> Result<Reconciliation, AppError> {
+        let end_of_month = (month + Months::new(1)).pred_opt().ok_or(AppError::InternalError)?;
+        self.reconcile(month, end_of_month).await
+    }
+
+    /// Re-post every order, expense and payment, e.g. for data recorded before the ledger existed
+    pub async fn rebuild(&self) -> Result<RebuildSummary, AppError> {
+        let txn = self.db.begin().await?;
+
+        let orders_list = orders::Entity::find().all(&txn).await?;
+        for order in &orders_list {
+            Self::post_order(&txn, order).await?;
+        }
+
+        let expenses_list = expenses::Entity::find().all(&txn).await?;
+        for expense in &expenses_list {
+            Self::post_expense(&txn, expense).await?;
+        }
+
+        let payments_list = payments::Entity::find().all(&txn).await?;
+        for payment in &payments_list {
+            Self::post_payment(&txn, payment).await?;
+        }
+
+        txn.commit().await?;
+
+        Ok(RebuildSummary {
+            orders: orders_list.len(),
+            expenses: expenses_list.len(),
+            payments: payments_list.len(),
+        })
+    }
+}
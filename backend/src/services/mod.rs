@@ -3,4 +3,21 @@ pub mod orders;
 pub mod expenses;
 pub mod invoices;
 pub mod reports;
-pub mod registration;
\ No newline at end of file
+pub mod registration;
+pub mod export;
+pub mod report_queue;
+pub mod ledger;
+pub mod payments;
+pub mod statements;
+pub mod exchange_rates;
+pub mod branches;
+pub mod dashboard;
+pub mod analytics;
+pub mod staff_activity;
+pub mod roles;
+pub mod sessions;
+pub mod invitations;
+pub mod throttle;
+pub mod two_factor;
+pub mod user_admin;
+pub mod api_keys;
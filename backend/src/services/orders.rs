@@ -1,12 +1,22 @@
-use sea_orm::{DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, ActiveValue};
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter,
+    PaginatorTrait, TransactionTrait,
+};
+use sea_orm::prelude::Decimal;
 use serde::{Deserialize, Serialize};
 use rand::{distributions::Alphanumeric, Rng};
-use chrono::{Datelike, NaiveDate};
+use chrono::NaiveDate;
 use crate::{
-    entities::{orders, invoices, users, reports},
+    entities::{orders, invoices, payments},
     errors::AppError,
+    money::{self, Money},
     services::invoices::{InvoicesService, CreateInvoiceRequest},
-    services::reports::ReportsService,
+    services::report_queue::ReportQueue,
+    services::ledger::LedgerService,
+    services::exchange_rates::{currency_code, ExchangeRatesService},
+    services::branches::BranchesService,
+    services::staff_activity::{StaffActivityService, ACTION_DELETE, ACTION_UPDATE, RECORD_ORDER},
+    services::export::{self, ExportFile, ExportFormat},
 };
 
 #[derive(Clone)]
@@ -18,16 +28,21 @@ pub struct OrdersService {
 pub struct CreateOrderRequest {
     pub patient_name: String,
     pub order_date: NaiveDate,
-    pub total_amount: f64,
+    pub total_amount: Money,      // in `currency`
+    pub currency: Option<String>, // defaults to the base currency
     pub description: String,
     pub created_by: i32, // user_id
+    pub branch_id: i32,
+    /// Another branch billed by this order, making it an inter-branch transfer
+    pub counterparty_branch_id: Option<i32>,
 }
 
 #[derive(Deserialize)]
 pub struct UpdateOrderRequest {
     pub patient_name: Option<String>,
     pub order_date: Option<NaiveDate>,
-    pub total_amount: Option<f64>,
+    pub total_amount: Option<Money>,
+    pub currency: Option<String>,
     pub description: Option<String>,
 }
 
@@ -37,8 +52,10 @@ pub struct InvoiceResponse {
     pub order_id: i32,
     pub transaction_id: String,
     pub invoice_date: NaiveDate,
-    pub total_amount: f64,
+    pub total_amount: Money,
     pub description: String,
+    pub branch_id: i32,
+    pub currency: &'static str,
 }
 
 #[derive(Serialize)]
@@ -46,9 +63,15 @@ pub struct CreateOrderResponse {
     pub order_id: i32,
     pub patient_name: String,
     pub order_date: NaiveDate,
-    pub total_amount: f64,
+    pub total_amount: Money,
+    pub original_currency: String,
+    pub original_amount: Money,
+    pub exchange_rate: Decimal,
     pub description: String,
     pub created_by: Option<i32>,
+    pub branch_id: i32,
+    pub counterparty_branch_id: Option<i32>,
+    pub currency: &'static str,
 }
 
 #[derive(Serialize)]
@@ -56,10 +79,16 @@ pub struct AllOrderResponse {
     pub order_id: i32,
     pub patient_name: String,
     pub order_date: NaiveDate,
-    pub total_amount: f64,
+    pub total_amount: Money,
+    pub original_currency: String,
+    pub original_amount: Money,
+    pub exchange_rate: Decimal,
     pub description: String,
     pub created_by: Option<i32>,
     pub modified_by: Option<i32>,
+    pub branch_id: i32,
+    pub counterparty_branch_id: Option<i32>,
+    pub currency: &'static str,
 }
 
 #[derive(Serialize)]
@@ -67,10 +96,16 @@ pub struct GetOrderResponse {
     pub order_id: i32,
     pub patient_name: String,
     pub order_date: NaiveDate,
-    pub total_amount: f64,
+    pub total_amount: Money,
+    pub original_currency: String,
+    pub original_amount: Money,
+    pub exchange_rate: Decimal,
     pub description: String,
     pub created_by: Option<i32>,
     pub modified_by: Option<i32>,
+    pub branch_id: i32,
+    pub counterparty_branch_id: Option<i32>,
+    pub currency: &'static str,
 }
 
 #[derive(Serialize)]
@@ -78,10 +113,16 @@ pub struct UpdateOrderResponse {
     pub order_id: i32,
     pub patient_name: String,
     pub order_date: NaiveDate,
-    pub total_amount: f64,
+    pub total_amount: Money,
+    pub original_currency: String,
+    pub original_amount: Money,
+    pub exchange_rate: Decimal,
     pub description: String,
     pub created_by: Option<i32>,
     pub modified_by: Option<i32>,
+    pub branch_id: i32,
+    pub counterparty_branch_id: Option<i32>,
+    pub currency: &'static str,
 }
 
 impl OrdersService {
@@ -90,16 +131,36 @@ impl OrdersService {
     }
 
     pub async fn create_order(&self, req: CreateOrderRequest) -> Result<(CreateOrderResponse, InvoiceResponse), AppError> {
+        if let Some(counterparty) = req.counterparty_branch_id {
+            if counterparty == req.branch_id {
+                return Err(AppError::BadRequest("An order cannot bill its own branch".into()));
+            }
+            BranchesService::new(self.db.clone()).find_branch(counterparty).await?;
+        }
+
+        // Amounts are kept in the base currency at the rate of the order date
+        let conversion = ExchangeRatesService::new(self.db.clone())
+            .convert(req.currency.as_deref(), req.total_amount, req.order_date)
+            .await?;
+
+        // The order, its invoice, its ledger entry and the report mark are written together
+        let txn = self.db.begin().await?;
+
         // Insert order
         let new_order = orders::ActiveModel {
             patient_name: Set(req.patient_name.clone()),
             order_date: Set(req.order_date),
-            total_amount: Set(req.total_amount),
+            total_amount: Set(conversion.amount),
+            original_currency: Set(conversion.currency),
+            original_amount: Set(conversion.original_amount),
+            exchange_rate: Set(conversion.rate),
             description: Set(req.description.clone()),
             created_by: Set(Some(req.created_by)),
+            branch_id: Set(req.branch_id),
+            counterparty_branch_id: Set(req.counterparty_branch_id),
             ..Default::default()
         }
-        .insert(&self.db)
+        .insert(&txn)
         .await?;
 
         // Generate a simple transaction ID
@@ -109,9 +170,6 @@ impl OrdersService {
             .map(char::from)
             .collect();
 
-        // Create invoice service
-        let invoice_service = InvoicesService::new(self.db.clone());
-
         // Create invoice for the order
         let invoice_req = CreateInvoiceRequest {
             order_id: new_order.order_id,
@@ -119,9 +177,10 @@ impl OrdersService {
             invoice_date: new_order.order_date,
             total_amount: new_order.total_amount,
             description: new_order.description.clone(),
+            branch_id: new_order.branch_id,
         };
 
-        let invoice_model = invoice_service.create_invoice(invoice_req).await?;
+        let invoice_model = InvoicesService::create_invoice_on(&txn, invoice_req).await?;
 
         let invoice_response = InvoiceResponse {
             invoice_id: invoice_model.invoice_id,
@@ -130,16 +189,14 @@ impl OrdersService {
             invoice_date: invoice_model.invoice_date,
             total_amount: invoice_model.total_amount,
             description: invoice_model.description,
+            branch_id: invoice_model.branch_id,
+            currency: money::base_currency(),
         };
 
-        let order_date = new_order.order_date;
-        let first_day_of_month = NaiveDate::from_ymd_opt(order_date.year(), order_date.month(), 1)
-            .expect("invalid date");
-
-        let reports_service = ReportsService::new(self.db.clone());
-        if let Err(e) = reports_service.generate_monthly_report(first_day_of_month).await {
-            tracing::error!("Failed to auto-update monthly report: {}", e);
-        }
+        // Bill the order in the ledger and queue the month for a debounced report recomputation
+        LedgerService::post_order(&txn, &new_order).await?;
+        ReportQueue::mark_dirty(&txn, new_order.order_date).await?;
+        txn.commit().await?;
 
         // Return both order and invoice
         Ok((
@@ -148,16 +205,27 @@ impl OrdersService {
                 patient_name: new_order.patient_name,
                 order_date: new_order.order_date,
                 total_amount: new_order.total_amount,
+                original_currency: new_order.original_currency,
+                original_amount: new_order.original_amount,
+                exchange_rate: new_order.exchange_rate,
                 description: new_order.description,
                 created_by: new_order.created_by,
+                branch_id: new_order.branch_id,
+                counterparty_branch_id: new_order.counterparty_branch_id,
+                currency: money::base_currency(),
             },
             invoice_response,
         ))
     }
 
-    /// Fetch all orders
-    pub async fn get_orders(&self) -> Result<Vec<AllOrderResponse>, AppError> {
-        let orders = orders::Entity::find()
+    /// Fetch all orders, optionally of one branch
+    pub async fn get_orders(&self, branch_id: Option<i32>) -> Result<Vec<AllOrderResponse>, AppError> {
+        let mut query = orders::Entity::find();
+        if let Some(branch_id) = branch_id {
+            query = query.filter(orders::Column::BranchId.eq(branch_id));
+        }
+
+        let orders = query
             .all(&self.db)
             .await
             .map_err(AppError::from)?; // convert DbErr to AppError
@@ -169,19 +237,38 @@ impl OrdersService {
                 patient_name: order.patient_name,
                 order_date: order.order_date,
                 total_amount: order.total_amount,
+                original_currency: order.original_currency,
+                original_amount: order.original_amount,
+                exchange_rate: order.exchange_rate,
                 description: order.description,
                 created_by: order.created_by,
                 modified_by: order.modified_by,
+                branch_id: order.branch_id,
+                counterparty_branch_id: order.counterparty_branch_id,
+                currency: money::base_currency(),
             })
             .collect();
 
         Ok(response)
     }
 
-    /// Fetch single order by ID
-    pub async fn get_order_by_id(&self, order_id: i32) -> Result<GetOrderResponse, AppError> {
-        let order = orders::Entity::find()
-            .filter(orders::Column::OrderId.eq(order_id))
+    /// Export all orders (optionally of one branch) as CSV or XLSX
+    pub async fn export_orders(&self, format: ExportFormat, branch_id: Option<i32>) -> Result<ExportFile, AppError> {
+        let mut query = orders::Entity::find();
+        if let Some(branch_id) = branch_id {
+            query = query.filter(orders::Column::BranchId.eq(branch_id));
+        }
+        let orders = query.all(&self.db).await?;
+        export::rows_file("orders", "Orders", format, &orders)
+    }
+
+    /// Fetch single order by ID, within one branch unless `branch_id` is `None`
+    pub async fn get_order_by_id(&self, order_id: i32, branch_id: Option<i32>) -> Result<GetOrderResponse, AppError> {
+        let mut query = orders::Entity::find().filter(orders::Column::OrderId.eq(order_id));
+        if let Some(branch_id) = branch_id {
+            query = query.filter(orders::Column::BranchId.eq(branch_id));
+        }
+        let order = query
             .one(&self.db)
             .await?
             .ok_or(AppError::NotFound("Order not found".into()))?;
@@ -191,16 +278,33 @@ impl OrdersService {
             patient_name: order.patient_name,
             order_date: order.order_date,
             total_amount: order.total_amount,
+            original_currency: order.original_currency,
+            original_amount: order.original_amount,
+            exchange_rate: order.exchange_rate,
             description: order.description,
             created_by: order.created_by,
             modified_by: order.modified_by,
+            branch_id: order.branch_id,
+            counterparty_branch_id: order.counterparty_branch_id,
+            currency: money::base_currency(),
         })
     }
 
-    /// Update order
+    /// True when any invoice of the order has been paid, even partially
+    async fn has_payments(&self, order_id: i32) -> Result<bool, AppError> {
+        let count = payments::Entity::find()
+            .inner_join(invoices::Entity)
+            .filter(invoices::Column::OrderId.eq(order_id))
+            .count(&self.db)
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// Update an order of a branch
     pub async fn update_order(
         &self,
         id: i32,
+        branch_id: i32,
         req: UpdateOrderRequest,
         user_id: i32,
     ) -> Result<(UpdateOrderResponse, Option<InvoiceResponse>), AppError> {
@@ -208,10 +312,39 @@ impl OrdersService {
 
         // Fetch the existing order
         let existing = Orders::find_by_id(id)
+            .filter(orders::Column::BranchId.eq(branch_id))
             .one(&self.db)
             .await?
             .ok_or_else(|| AppError::NotFound("Order not found".into()))?;
 
+        let previous_date = existing.order_date;
+
+        // Payments are recorded in the order's currency, which then has to stay
+        if let Some(currency) = req.currency.as_deref()
+            && currency_code(Some(currency))? != existing.original_currency
+            && self.has_payments(id).await?
+        {
+            return Err(AppError::BadRequest(
+                "The currency of an order with payments cannot be changed".into(),
+            ));
+        }
+
+        // A new amount, currency or date is valued again at the rate of the (new) order date
+        let revalue = req.total_amount.is_some() || req.currency.is_some() || req.order_date.is_some();
+        let conversion = if revalue {
+            let currency = req.currency.clone().unwrap_or_else(|| existing.original_currency.clone());
+            let conversion = ExchangeRatesService::new(self.db.clone())
+                .convert(
+                    Some(&currency),
+                    req.total_amount.unwrap_or(existing.original_amount),
+                    req.order_date.unwrap_or(existing.order_date),
+                )
+                .await?;
+            Some(conversion)
+        } else {
+            None
+        };
+
         // Build active model for update
         let mut active: orders::ActiveModel = existing.into();
 
@@ -221,8 +354,11 @@ impl OrdersService {
         if let Some(date) = req.order_date {
             active.order_date = Set(date);
         }
-        if let Some(amount) = req.total_amount {
-            active.total_amount = Set(amount);
+        if let Some(conversion) = conversion {
+            active.total_amount = Set(conversion.amount);
+            active.original_currency = Set(conversion.currency);
+            active.original_amount = Set(conversion.original_amount);
+            active.exchange_rate = Set(conversion.rate);
         }
         if let Some(desc) = req.description.clone() {
             active.description = Set(desc);
@@ -231,37 +367,33 @@ impl OrdersService {
         active.modified_by = Set(Some(user_id));
 
         // Update the order in DB
-        let updated_order = active.update(&self.db).await?;
+        let txn = self.db.begin().await?;
+        let updated_order = active.update(&txn).await?;
+        StaffActivityService::record_edit(&txn, RECORD_ORDER, id, ACTION_UPDATE, user_id, updated_order.branch_id)
+            .await?;
 
         // Try to fetch related invoice
         let invoice = invoices::Entity::find()
             .filter(invoices::Column::OrderId.eq(id))
-            .one(&self.db)
+            .one(&txn)
             .await?;
 
+        LedgerService::post_order(&txn, &updated_order).await?;
 
-        // After updating the order in the database
-        if let Some(updated_order) = updated_order {
-            let order_date = updated_order.order_date;
-            let first_day_of_month = NaiveDate::from_ymd_opt(order_date.year(), order_date.month(), 1)
-                .expect("Invalid order date");
-
-            let reports_service = ReportsService::new(self.db.clone());
-            if let Err(e) = reports_service.generate_monthly_report(first_day_of_month).await {
-                tracing::error!("Failed to auto-update monthly report after order update: {}", e);
-            }
-        }
+        // Both the old and the new month change when the order date moves
+        ReportQueue::mark_dirty(&txn, previous_date).await?;
+        ReportQueue::mark_dirty(&txn, updated_order.order_date).await?;
 
         // If invoice exists, update total_amount and description to match updated order
-        let updated_invoice = if let Some(mut invoice_model) = invoice {
+        let updated_invoice = if let Some(invoice_model) = invoice {
             let mut invoice_active: invoices::ActiveModel = invoice_model.into();
-            if let Some(amount) = req.total_amount {
-                invoice_active.total_amount = Set(amount);
+            if revalue {
+                invoice_active.total_amount = Set(updated_order.total_amount);
             }
             if let Some(desc) = req.description {
                 invoice_active.description = Set(desc);
             }
-            let updated = invoice_active.update(&self.db).await?;
+            let updated = invoice_active.update(&txn).await?;
             Some(InvoiceResponse {
                 invoice_id: updated.invoice_id,
                 order_id: updated.order_id,
@@ -269,10 +401,13 @@ impl OrdersService {
                 invoice_date: updated.invoice_date,
                 total_amount: updated.total_amount,
                 description: updated.description,
+                branch_id: updated.branch_id,
+                currency: money::base_currency(),
             })
         } else {
             None
         };
+        txn.commit().await?;
 
         // Return both updated order and invoice
         Ok((
@@ -281,24 +416,41 @@ impl OrdersService {
                 patient_name: updated_order.patient_name,
                 order_date: updated_order.order_date,
                 total_amount: updated_order.total_amount,
+                original_currency: updated_order.original_currency,
+                original_amount: updated_order.original_amount,
+                exchange_rate: updated_order.exchange_rate,
                 description: updated_order.description,
                 created_by: updated_order.created_by,
                 modified_by: updated_order.modified_by,
+                branch_id: updated_order.branch_id,
+                counterparty_branch_id: updated_order.counterparty_branch_id,
+                currency: money::base_currency(),
             },
             updated_invoice,
         ))
     }
 
-    /// Delete order (cascade deletes invoice)
-    pub async fn delete_order(&self, order_id: i32) -> Result<(), AppError> {
-        let order: orders::ActiveModel = orders::Entity::find_by_id(order_id)
+    /// Delete an order of a branch (cascade deletes invoice)
+    pub async fn delete_order(&self, order_id: i32, branch_id: i32, user_id: i32) -> Result<(), AppError> {
+        let order = orders::Entity::find_by_id(order_id)
+            .filter(orders::Column::BranchId.eq(branch_id))
             .one(&self.db)
             .await?
-            .ok_or(AppError::NotFound)?
-            .into();
+            .ok_or(AppError::NotFound("Order not found".into()))?;
+        let order_date = order.order_date;
+        let branch_id = order.branch_id;
+
+        // Payments go with the invoice, so their entries have to be removed first
+        let txn = self.db.begin().await?;
+        LedgerService::remove_order(&txn, order_id).await?;
 
         // SeaORM cascade delete handles invoice if FK is set
-        order.delete(&self.db).await?;
+        let order: orders::ActiveModel = order.into();
+        order.delete(&txn).await?;
+
+        StaffActivityService::record_edit(&txn, RECORD_ORDER, order_id, ACTION_DELETE, user_id, branch_id).await?;
+        ReportQueue::mark_dirty(&txn, order_date).await?;
+        txn.commit().await?;
         Ok(())
     }
 }
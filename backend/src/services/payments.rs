@@ -0,0 +1,231 @@
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder, TransactionTrait,
+};
+use sea_orm::prelude::Decimal;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use crate::{
+    entities::{invoices, orders, payments},
+    errors::AppError,
+    money::{self, Money},
+    services::exchange_rates::{currency_code, ExchangeRatesService},
+    services::ledger::{LedgerService, SOURCE_PAYMENT},
+    services::report_queue::ReportQueue,
+};
+
+#[derive(Clone)]
+pub struct PaymentsService {
+    pub db: DatabaseConnection,
+}
+
+#[derive(Deserialize)]
+pub struct CreatePaymentRequest {
+    pub invoice_id: i32,
+    pub amount: Money,            // in `currency`
+    pub currency: Option<String>, // defaults to the order's currency
+    pub payment_date: NaiveDate,
+    pub method: String,
+    pub created_by: i32,
+    /// Branch the invoice has to belong to
+    pub branch_id: i32,
+}
+
+/// `amount` is what was received, in the base currency at the rate of the payment date;
+/// `fx_gain_loss` is its difference to the receivable it settled
+#[derive(Serialize)]
+pub struct PaymentResponse {
+    pub payment_id: i32,
+    pub invoice_id: i32,
+    pub amount: Money,
+    pub original_currency: String,
+    pub original_amount: Money,
+    pub exchange_rate: Decimal,
+    pub fx_gain_loss: Money,
+    pub payment_date: NaiveDate,
+    pub method: String,
+    pub created_by: Option<i32>,
+    pub currency: &'static str,
+}
+
+/// Payments of an invoice and what is still owed.
+/// `paid` and `outstanding` are valued at the order's rate, like the receivable.
+#[derive(Serialize)]
+pub struct InvoicePayments {
+    pub invoice_id: i32,
+    pub total_amount: Money,
+    pub paid: Money,
+    pub outstanding: Money,
+    pub original_currency: String,
+    pub original_total: Money,
+    pub original_outstanding: Money,
+    pub fx_gain_loss: Money,
+    pub payments: Vec<PaymentResponse>,
+    pub currency: &'static str,
+}
+
+impl From<payments::Model> for PaymentResponse {
+    fn from(payment: payments::Model) -> Self {
+        PaymentResponse {
+            payment_id: payment.payment_id,
+            invoice_id: payment.invoice_id,
+            amount: payment.amount,
+            original_currency: payment.original_currency,
+            original_amount: payment.original_amount,
+            exchange_rate: payment.exchange_rate,
+            fx_gain_loss: payment.fx_gain_loss,
+            payment_date: payment.payment_date,
+            method: payment.method,
+            created_by: payment.created_by,
+            currency: money::base_currency(),
+        }
+    }
+}
+
+impl PaymentsService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Invoice with the order it bills; the order holds the currency and rate of the receivable.
+    /// Only invoices of `branch_id` are found, or of any branch when it is `None`.
+    async fn find_invoice(&self, invoice_id: i32, branch_id: Option<i32>) -> Result<(invoices::Model, orders::Model), AppError> {
+        let mut query = invoices::Entity::find_by_id(invoice_id).find_also_related(orders::Entity);
+        if let Some(branch_id) = branch_id {
+            query = query.filter(invoices::Column::BranchId.eq(branch_id));
+        }
+        let (invoice, order) = query
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound("Invoice not found".into()))?;
+        let order = order.ok_or(AppError::NotFound("Order not found".into()))?;
+        Ok((invoice, order))
+    }
+
+    /// Record a payment against an invoice and post it to the ledger.
+    /// Foreign-currency payments are valued at the rate of the payment date; the difference
+    /// to the receivable, booked at the order's rate, is an exchange gain or loss.
+    pub async fn create_payment(&self, req: CreatePaymentRequest) -> Result<PaymentResponse, AppError> {
+        let amount = req.amount;
+        if amount <= Money::ZERO {
+            return Err(AppError::BadRequest("Payment amount must be greater than zero".into()));
+        }
+        if req.method.trim().is_empty() {
+            return Err(AppError::BadRequest("Payment method is required".into()));
+        }
+
+        let (invoice, order) = self.find_invoice(req.invoice_id, Some(req.branch_id)).await?;
+        let invoice_payments = self.invoice_payments(invoice, &order).await?;
+
+        let currency = match req.currency.as_deref() {
+            Some(currency) => currency_code(Some(currency))?,
+            None => order.original_currency.clone(),
+        };
+        if currency != order.original_currency {
+            return Err(AppError::BadRequest(format!(
+                "Payments for this invoice must be made in {}",
+                order.original_currency
+            )));
+        }
+        if amount > invoice_payments.original_outstanding {
+            return Err(AppError::BadRequest(format!(
+                "Payment exceeds the outstanding balance of {} {}",
+                invoice_payments.original_outstanding, currency
+            )));
+        }
+
+        let received = ExchangeRatesService::new(self.db.clone())
+            .convert(Some(&currency), amount, req.payment_date)
+            .await?;
+
+        // The last payment clears whatever is left, so rounding never strands a cent on the receivable
+        let settled = if amount == invoice_payments.original_outstanding {
+            invoice_payments.outstanding
+        } else {
+            amount.at_rate(order.exchange_rate)
+        };
+
+        let payment = payments::ActiveModel {
+            invoice_id: Set(req.invoice_id),
+            amount: Set(received.amount),
+            original_currency: Set(received.currency),
+            original_amount: Set(received.original_amount),
+            exchange_rate: Set(received.rate),
+            fx_gain_loss: Set(received.amount - settled),
+            payment_date: Set(req.payment_date),
+            method: Set(req.method.trim().to_string()),
+            created_by: Set(Some(req.created_by)),
+            ..Default::default()
+        };
+
+        let txn = self.db.begin().await?;
+        let payment = payment.insert(&txn).await?;
+        LedgerService::post_payment(&txn, &payment).await?;
+
+        // Exchange gains and losses count towards the month of the payment
+        if !payment.fx_gain_loss.is_zero() {
+            ReportQueue::mark_dirty(&txn, payment.payment_date).await?;
+        }
+        txn.commit().await?;
+
+        Ok(payment.into())
+    }
+
+    /// Fetch the payments of an invoice with its outstanding balance,
+    /// within one branch unless `branch_id` is `None`
+    pub async fn get_invoice_payments(&self, invoice_id: i32, branch_id: Option<i32>) -> Result<InvoicePayments, AppError> {
+        let (invoice, order) = self.find_invoice(invoice_id, branch_id).await?;
+        self.invoice_payments(invoice, &order).await
+    }
+
+    /// Payment totals of an already loaded invoice
+    async fn invoice_payments(&self, invoice: invoices::Model, order: &orders::Model) -> Result<InvoicePayments, AppError> {
+        let invoice_id = invoice.invoice_id;
+        let payments_list = payments::Entity::find()
+            .filter(payments::Column::InvoiceId.eq(invoice_id))
+            .order_by_asc(payments::Column::PaymentDate)
+            .all(&self.db)
+            .await?;
+
+        let fx_gain_loss: Money = payments_list.iter().map(|p| p.fx_gain_loss).sum();
+        let paid: Money = payments_list.iter().map(|p| p.amount - p.fx_gain_loss).sum();
+        let original_paid: Money = payments_list.iter().map(|p| p.original_amount).sum();
+
+        Ok(InvoicePayments {
+            invoice_id,
+            total_amount: invoice.total_amount,
+            paid,
+            outstanding: (invoice.total_amount - paid).max(Money::ZERO),
+            original_currency: order.original_currency.clone(),
+            original_total: order.original_amount,
+            original_outstanding: (order.original_amount - original_paid).max(Money::ZERO),
+            fx_gain_loss,
+            payments: payments_list.into_iter().map(PaymentResponse::from).collect(),
+            currency: money::base_currency(),
+        })
+    }
+
+    /// Delete a payment on an invoice of a branch, and its ledger entry
+    pub async fn delete_payment(&self, payment_id: i32, branch_id: i32) -> Result<(), AppError> {
+        let payment = payments::Entity::find_by_id(payment_id)
+            .inner_join(invoices::Entity)
+            .filter(invoices::Column::BranchId.eq(branch_id))
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound("Payment not found".into()))?;
+
+        let txn = self.db.begin().await?;
+        LedgerService::remove_source(&txn, SOURCE_PAYMENT, payment.payment_id).await?;
+
+        let payment_date = payment.payment_date;
+        let had_fx = !payment.fx_gain_loss.is_zero();
+
+        let payment: payments::ActiveModel = payment.into();
+        payment.delete(&txn).await?;
+
+        if had_fx {
+            ReportQueue::mark_dirty(&txn, payment_date).await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+}
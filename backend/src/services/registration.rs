@@ -1,15 +1,40 @@
-use sea_orm::{DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter};
-use serde::{Deserialize, Serialize};
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder, Condition,
+    ConnectionTrait, QuerySelect,
+};
+use sea_orm::sea_query::Expr;
+use serde::Deserialize;
 use rand::{thread_rng, Rng};
-use rand::distributions::Alphanumeric;
+use argon2::{Argon2, PasswordVerifier, password_hash::PasswordHash};
+use std::sync::Arc;
+use chrono::{Duration, Utc};
 use crate::{
     entities::{registration_codes, registration_code_resets},
     errors::AppError,
+    config::Config,
+    services::invitations::normalize_email,
+    services::users::hash_password,
+    mail::{Mailer, Template},
+    tokens,
 };
 
+/// Verification codes are typed in by hand, so they avoid look-alike characters
+const VERIFICATION_CODE_CHARSET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const VERIFICATION_CODE_LEN: usize = 8;
+
+/// Shortest registration code a reset accepts
+const MIN_REGISTRATION_CODE_LEN: usize = 8;
+
+/// The registration code lets the first account be created. If it is lost, a verification
+/// code sent to the company email (`COMPANY_EMAIL`) lets it be replaced. Verification codes
+/// are stored hashed, expire, work once and stop working after too many wrong guesses.
 #[derive(Clone)]
 pub struct RegistrationService {
     pub db: DatabaseConnection,
+    mailer: Arc<dyn Mailer>,
+    company_email: Option<String>,
+    reset_ttl: Duration,
+    max_attempts: i32,
 }
 
 #[derive(Deserialize)]
@@ -24,62 +49,184 @@ pub struct ResetRegistrationRequest {
     pub new_registration_code: String,
 }
 
+fn generate_verification_code() -> String {
+    let mut rng = thread_rng();
+    (0..VERIFICATION_CODE_LEN)
+        .map(|_| VERIFICATION_CODE_CHARSET[rng.gen_range(0..VERIFICATION_CODE_CHARSET.len())] as char)
+        .collect()
+}
+
+fn hash_verification_code(code: &str) -> String {
+    tokens::hash(&code.trim().to_uppercase())
+}
+
+/// Whether `code` is the current registration code. Within a transaction the code stays
+/// locked until it ends, which serializes concurrent first registrations.
+pub async fn check_registration_code<C: ConnectionTrait>(db: &C, code: &str) -> Result<bool, AppError> {
+    let Some(reg_code) = registration_codes::Entity::find().lock_exclusive().one(db).await? else {
+        return Ok(false);
+    };
+
+    // Plaintext codes from before hashing were hashed by a migration
+    let parsed_hash = PasswordHash::new(&reg_code.code_hash).map_err(|_| {
+        tracing::error!("The registration code is not an Argon2 hash, run the migrations");
+        AppError::InternalError
+    })?;
+    Ok(Argon2::default().verify_password(code.as_bytes(), &parsed_hash).is_ok())
+}
+
 impl RegistrationService {
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+    pub fn new(db: DatabaseConnection, config: &Config, mailer: Arc<dyn Mailer>) -> Self {
+        Self {
+            db,
+            mailer,
+            company_email: config.company_email.as_deref().map(|email| email.trim().to_lowercase()),
+            reset_ttl: Duration::minutes(config.registration_reset_minutes),
+            max_attempts: config.registration_reset_max_attempts,
+        }
     }
 
-    /// Step 1: Send verification code to company email
+    fn company_email(&self) -> Result<&str, AppError> {
+        self.company_email
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest("Registration code resets are not configured".into()))
+    }
+
+    /// Remove verification codes that can no longer be used
+    pub async fn delete_expired(&self) -> Result<u64, AppError> {
+        let result = registration_code_resets::Entity::delete_many()
+            .filter(
+                Condition::any()
+                    .add(registration_code_resets::Column::Used.eq(true))
+                    .add(registration_code_resets::Column::ExpiresAt.lte(Utc::now().naive_utc())),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Step 1: Send a verification code to the company email. Any other address gets the
+    /// same answer and no email, so the company email can't be found by trying addresses.
     pub async fn forgot_registration_code(
         &self,
         req: ForgotRegistrationRequest,
-    ) -> Result<String, AppError> {
-        // Generate a random 6-digit verification code
-        let verification_code: String = thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(6)
-            .map(char::from)
-            .collect();
-
-        // Save hashed verification code linked to email in registration_code_resets table
-        let active_model = registration_code_resets::ActiveModel {
-            email: Set(req.email.clone()),
-            code: Set(verification_code.clone()),
-            ..Default::default()
-        };
+    ) -> Result<(), AppError> {
+        let company_email = self.company_email()?.to_string();
+        if normalize_email(&req.email)? != company_email {
+            return Ok(());
+        }
 
-        active_model.insert(&self.db).await?;
+        self.delete_expired().await?;
+
+        // Only the newest code works
+        registration_code_resets::Entity::update_many()
+            .col_expr(registration_code_resets::Column::Used, Expr::value(true))
+            .filter(registration_code_resets::Column::Email.eq(company_email.clone()))
+            .exec(&self.db)
+            .await?;
+
+        let verification_code = generate_verification_code();
+        let now = Utc::now().naive_utc();
+        registration_code_resets::ActiveModel {
+            email: Set(company_email.clone()),
+            hashed_verification_code: Set(hash_verification_code(&verification_code)),
+            expires_at: Set(now + self.reset_ttl),
+            used: Set(false),
+            attempts: Set(0),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
 
-        // Here you would send the code to the company's email
-        // For now, just return it for testing
-        Ok(verification_code)
+        // Only whoever reads the mailbox learns the code
+        let minutes = self.reset_ttl.num_minutes().to_string();
+        self.mailer
+            .send_template(
+                Template::RegistrationCodeReset,
+                &company_email,
+                &[("code", &verification_code), ("minutes", &minutes)],
+            )
+            .await
     }
 
-    /// Step 2: Verify code and reset registration code
+    /// Step 2: Verify the code and replace the registration code
     pub async fn reset_registration_code(
         &self,
         req: ResetRegistrationRequest,
     ) -> Result<String, AppError> {
+        let invalid = || AppError::BadRequest("Invalid or expired verification code".into());
+
+        let company_email = self.company_email()?;
+        let email = normalize_email(&req.email)?;
+        if email != company_email {
+            return Err(invalid());
+        }
+
+        let now = Utc::now().naive_utc();
         let record = registration_code_resets::Entity::find()
-            .filter(registration_code_resets::Column::Email.eq(req.email.clone()))
-            .filter(registration_code_resets::Column::Code.eq(req.verification_code.clone()))
+            .filter(registration_code_resets::Column::Email.eq(email))
+            .filter(registration_code_resets::Column::Used.eq(false))
+            .filter(registration_code_resets::Column::ExpiresAt.gt(now))
+            .order_by_desc(registration_code_resets::Column::CreatedAt)
             .one(&self.db)
             .await?
-            .ok_or(AppError::BadRequest("Invalid verification code".into()))?;
+            .ok_or_else(invalid)?;
 
-        // Update the registration_code table with new code
-        let mut reg_code: registration_codes::ActiveModel = registration_codes::Entity::find()
-            .one(&self.db)
-            .await?
-            .ok_or(AppError::InternalError)?
-            .into();
+        if record.hashed_verification_code != hash_verification_code(&req.verification_code) {
+            // Too many wrong guesses use the code up
+            let attempts = record.attempts + 1;
+            registration_code_resets::Entity::update_many()
+                .col_expr(
+                    registration_code_resets::Column::Attempts,
+                    Expr::col(registration_code_resets::Column::Attempts).add(1),
+                )
+                .col_expr(registration_code_resets::Column::Used, Expr::value(attempts >= self.max_attempts))
+                .filter(registration_code_resets::Column::Id.eq(record.id))
+                .exec(&self.db)
+                .await?;
+            return Err(invalid());
+        }
+
+        let new_code = req.new_registration_code.trim();
+        if new_code.chars().count() < MIN_REGISTRATION_CODE_LEN {
+            return Err(AppError::BadRequest(format!(
+                "The registration code must be at least {} characters",
+                MIN_REGISTRATION_CODE_LEN
+            )));
+        }
+
+        // Claimed only if still unused, so two requests can't both spend one code
+        let claimed = registration_code_resets::Entity::update_many()
+            .col_expr(registration_code_resets::Column::Used, Expr::value(true))
+            .filter(registration_code_resets::Column::Id.eq(record.id))
+            .filter(registration_code_resets::Column::Used.eq(false))
+            .exec(&self.db)
+            .await?;
+        if claimed.rows_affected == 0 {
+            return Err(invalid());
+        }
 
-        reg_code.code = Set(req.new_registration_code.clone());
-        reg_code.update(&self.db).await?;
+        let code_hash = hash_password(new_code)?;
+        match registration_codes::Entity::find().one(&self.db).await? {
+            Some(reg_code) => {
+                let mut active: registration_codes::ActiveModel = reg_code.into();
+                active.code_hash = Set(code_hash);
+                active.updated_at = Set(now);
+                active.update(&self.db).await?;
+            }
+            None => {
+                registration_codes::ActiveModel {
+                    code_hash: Set(code_hash),
+                    updated_at: Set(now),
+                    ..Default::default()
+                }
+                .insert(&self.db)
+                .await?;
+            }
+        }
 
-        // Optionally, delete the verification record after successful reset
-        let mut reset_model: registration_code_resets::ActiveModel = record.into();
-        reset_model.delete(&self.db).await?;
+        self.delete_expired().await?;
 
         Ok("Registration code successfully reset".into())
     }
@@ -0,0 +1,233 @@
+use std::time::Duration as StdDuration;
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, Condition, ConnectionTrait,
+};
+use sea_orm::sea_query::OnConflict;
+use serde::Serialize;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use crate::{
+    config::Config,
+    entities::{report_refresh_queue, reports},
+    errors::AppError,
+    services::reports::{ReportScope, ReportsService},
+    services::branches::BranchesService,
+};
+
+/// How often the worker looks for months that are due; a settled month waits at most this long
+const POLL_INTERVAL: StdDuration = StdDuration::from_secs(5);
+/// Upper bound for the delay between two failed recomputations
+const MAX_BACKOFF_SECS: i64 = 600;
+
+/// Tracks months whose report is out of date and recomputes them in the background
+#[derive(Clone)]
+pub struct ReportQueue {
+    pub db: DatabaseConnection,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFreshness {
+    /// Stored report reflects every write
+    Fresh,
+    /// Writes happened since the report was generated; recomputation is queued
+    Pending,
+    /// Recomputation gave up after the configured number of attempts
+    Failed,
+    /// No report has been generated and none is queued
+    Missing,
+}
+
+#[derive(Serialize)]
+pub struct ReportStatus {
+    pub month: NaiveDate,
+    pub status: ReportFreshness,
+    pub generated_at: Option<NaiveDateTime>,
+    pub pending_since: Option<NaiveDateTime>,
+    pub attempts: i32,
+    pub next_attempt_at: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+}
+
+impl ReportQueue {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    fn first_of_month(date: NaiveDate) -> NaiveDate {
+        NaiveDate::from_ymd_opt(date.year(), date.month(), 1).expect("Invalid date")
+    }
+
+    /// Mark the month containing `date` as dirty.
+    /// Repeated marks only move `last_marked_at`, so a burst of edits
+    /// collapses into a single recomputation.
+    /// Runs on the caller's connection so the mark commits with the edit itself.
+    pub async fn mark_dirty<C: ConnectionTrait>(conn: &C, date: NaiveDate) -> Result<(), AppError> {
+        use report_refresh_queue::Column;
+
+        let now = Utc::now().naive_utc();
+        let marker = report_refresh_queue::ActiveModel {
+            month: Set(Self::first_of_month(date)),
+            dirty_since: Set(now),
+            last_marked_at: Set(now),
+            attempts: Set(0),
+            next_attempt_at: Set(None),
+            last_error: Set(None),
+            ..Default::default()
+        };
+
+        report_refresh_queue::Entity::insert(marker)
+            .on_conflict(
+                OnConflict::column(Column::Month)
+                    .update_columns([
+                        Column::LastMarkedAt,
+                        Column::Attempts,
+                        Column::NextAttemptAt,
+                        Column::LastError,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Regenerate the consolidated report of a month and the report of every active branch
+    async fn regenerate(&self, month: NaiveDate) -> Result<(), AppError> {
+        let mut scopes = vec![ReportScope::Consolidated];
+        scopes.extend(
+            BranchesService::new(self.db.clone())
+                .active_branch_ids()
+                .await?
+                .into_iter()
+                .map(ReportScope::Branch),
+        );
+
+        for scope in scopes {
+            ReportsService::new(self.db.clone())
+                .with_scope(scope)
+                .generate_monthly_report(month)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Whether the stored consolidated report of a month (YYYY-MM-01) is up to date
+    pub async fn status(&self, month: NaiveDate, max_attempts: i32) -> Result<ReportStatus, AppError> {
+        let report = reports::Entity::find()
+            .filter(reports::Column::Month.eq(month))
+            .filter(reports::Column::BranchId.is_null())
+            .one(&self.db)
+            .await?;
+
+        let marker = report_refresh_queue::Entity::find()
+            .filter(report_refresh_queue::Column::Month.eq(month))
+            .one(&self.db)
+            .await?;
+
+        let generated_at = report.as_ref().map(|r| r.generated_at);
+
+        Ok(match marker {
+            Some(marker) => ReportStatus {
+                month,
+                status: if marker.attempts >= max_attempts {
+                    ReportFreshness::Failed
+                } else {
+                    ReportFreshness::Pending
+                },
+                generated_at,
+                pending_since: Some(marker.dirty_since),
+                attempts: marker.attempts,
+                next_attempt_at: marker.next_attempt_at,
+                last_error: marker.last_error,
+            },
+            None => ReportStatus {
+                month,
+                status: if report.is_some() {
+                    ReportFreshness::Fresh
+                } else {
+                    ReportFreshness::Missing
+                },
+                generated_at,
+                pending_since: None,
+                attempts: 0,
+                next_attempt_at: None,
+                last_error: None,
+            },
+        })
+    }
+
+    /// Recompute every month whose edits have settled (or that waited too long).
+    /// Returns how many reports were refreshed.
+    pub async fn process_due(&self, config: &Config) -> Result<usize, AppError> {
+        use report_refresh_queue::Column;
+
+        let now = Utc::now().naive_utc();
+        let settled = now - Duration::seconds(config.report_debounce_secs);
+        let overdue = now - Duration::seconds(config.report_max_wait_secs);
+
+        let due = report_refresh_queue::Entity::find()
+            .filter(Column::Attempts.lt(config.report_max_attempts))
+            .filter(
+                Condition::any()
+                    .add(Column::NextAttemptAt.is_null())
+                    .add(Column::NextAttemptAt.lte(now)),
+            )
+            .filter(
+                Condition::any()
+                    .add(Column::LastMarkedAt.lte(settled))
+                    .add(Column::DirtySince.lte(overdue)),
+            )
+            .all(&self.db)
+            .await?;
+
+        let mut refreshed = 0;
+
+        for marker in due {
+            match self.regenerate(marker.month).await {
+                Ok(_) => {
+                    // Keep the marker if the month was edited again while we were recomputing
+                    report_refresh_queue::Entity::delete_many()
+                        .filter(Column::Id.eq(marker.id))
+                        .filter(Column::LastMarkedAt.eq(marker.last_marked_at))
+                        .exec(&self.db)
+                        .await?;
+                    refreshed += 1;
+                }
+                Err(e) => {
+                    let attempts = marker.attempts + 1;
+                    let backoff = (config.report_debounce_secs.max(1) << attempts.min(16))
+                        .min(MAX_BACKOFF_SECS);
+
+                    tracing::warn!(
+                        "Recomputing report for {} failed (attempt {}/{}): {}",
+                        marker.month, attempts, config.report_max_attempts, e
+                    );
+
+                    let mut active: report_refresh_queue::ActiveModel = marker.into();
+                    active.attempts = Set(attempts);
+                    active.next_attempt_at = Set(Some(now + Duration::seconds(backoff)));
+                    active.last_error = Set(Some(e.to_string()));
+                    active.update(&self.db).await?;
+                }
+            }
+        }
+
+        Ok(refreshed)
+    }
+}
+
+/// Background loop started from `main`; never returns
+pub async fn run_worker(db: DatabaseConnection, config: Config) {
+    let queue = ReportQueue::new(db);
+
+    loop {
+        match queue.process_due(&config).await {
+            Ok(0) => {}
+            Ok(refreshed) => tracing::info!("Recomputed {} monthly report(s)", refreshed),
+            Err(e) => tracing::error!("Report worker failed to process the refresh queue: {}", e),
+        }
+
+        actix_web::rt::time::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -1,83 +1,693 @@
-use sea_orm::{DatabaseConnection, EntityTrait, ColumnTrait, QueryFilter, ActiveModelTrait, Set};
-use serde::{Deserialize, Serialize};
-use chrono::{NaiveDate, Datelike};
+use std::collections::{BTreeMap, BTreeSet};
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ColumnTrait, QueryFilter, QueryOrder, QuerySelect, ActiveModelTrait,
+    FromQueryResult, Set, Condition, RelationTrait, JoinType,
+};
+use sea_orm::prelude::Decimal;
+use sea_orm::sea_query::Expr;
+use serde::Serialize;
+use chrono::{NaiveDate, Datelike, Months, Utc};
 use crate::{
-    entities::{orders, expenses, reports},
+    entities::{orders, expenses, invoices, payments, reports},
     errors::AppError,
+    fiscal::{self, Period},
+    money::{self, Money},
+    services::export::{self, Cell, ExportFile, ExportFormat, Sheet},
 };
 
 #[derive(Clone)]
 pub struct ReportsService {
     pub db: DatabaseConnection,
+    pub scope: ReportScope,
+}
+
+/// Which books a report covers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportScope {
+    /// Every branch together; inter-branch transfers cancel out and are left out
+    Consolidated,
+    /// One branch; transfers it billed count as income, transfers billed to it as expenses
+    Branch(i32),
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct MonthlyReport {
-    pub month: NaiveDate,
+impl ReportScope {
+    /// Scope of a resolved branch filter (see `AuthenticatedUser::branch_scope`); `None` is consolidated
+    pub fn from_branch(branch_id: Option<i32>) -> Self {
+        branch_id.map_or(ReportScope::Consolidated, ReportScope::Branch)
+    }
+
+    pub fn branch_id(&self) -> Option<i32> {
+        match self {
+            ReportScope::Consolidated => None,
+            ReportScope::Branch(branch_id) => Some(*branch_id),
+        }
+    }
+}
+
+/// Expense label under which a branch sees the transfers billed to it
+pub const TRANSFER_LABEL: &str = "Inter-branch transfers";
+
+/// Aggregated figures for an inclusive date range
+#[derive(Serialize)]
+pub struct PeriodSummary {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// `None` for the consolidated figures
+    pub branch_id: Option<i32>,
     pub total_orders: i32,
-    pub total_income: f64,
-    pub total_expenses: f64,
-    pub net_profit: f64,
+    pub total_income: Money,
+    pub total_expenses: Money,
+    pub expenses_by_label: BTreeMap<String, Money>,
+    /// Exchange differences of foreign-currency payments received in the range
+    pub fx_gain_loss: Money,
+    pub net_profit: Money,
+    pub currency: &'static str,
+}
+
+/// Change of a single figure against a reference period
+#[derive(Serialize)]
+pub struct Delta<T> {
+    pub absolute: T,
+    /// `None` when the reference value is zero
+    pub percentage: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+pub struct PeriodDeltas {
+    pub total_orders: Delta<i32>,
+    pub total_income: Delta<Money>,
+    pub total_expenses: Delta<Money>,
+    pub expenses_by_label: BTreeMap<String, Delta<Money>>,
+    pub fx_gain_loss: Delta<Money>,
+    pub net_profit: Delta<Money>,
+}
+
+/// A period compared with the one before it and the same period last year
+#[derive(Serialize)]
+pub struct PeriodComparison {
+    pub current: PeriodSummary,
+    pub previous_period: PeriodSummary,
+    pub same_period_last_year: PeriodSummary,
+    pub vs_previous_period: PeriodDeltas,
+    pub vs_last_year: PeriodDeltas,
+}
+
+/// One group of a breakdown; `share` is the percentage of the group total
+#[derive(Serialize)]
+pub struct BreakdownLine {
+    pub name: String,
+    pub count: i32,
+    pub amount: Money,
+    pub share: Decimal,
 }
 
+#[derive(Serialize)]
+pub struct TopExpense {
+    pub expense_id: i32,
+    pub description: String,
+    pub label: String,
+    pub amount: Money,
+    pub expense_date: NaiveDate,
+}
+
+#[derive(Serialize)]
+pub struct ReportBreakdown {
+    pub expenses_by_label: Vec<BreakdownLine>,
+    pub income_by_service: Vec<BreakdownLine>,
+    pub top_expenses: Vec<TopExpense>,
+    pub currency: &'static str,
+}
+
+/// Comparison of a range plus the breakdown of its own figures
+#[derive(Serialize)]
+pub struct RangeReport {
+    #[serde(flatten)]
+    pub comparison: PeriodComparison,
+    pub breakdown: ReportBreakdown,
+}
+
+/// Totals of one day, as stored in a report's `daily_data`
+#[derive(Serialize)]
+pub struct DailyTotal {
+    pub date: NaiveDate,
+    pub orders: i32,
+    pub income: Money,
+    pub expenses: Money,
+    pub net: Money,
+}
+
+/// Row of a `GROUP BY` aggregation
+#[derive(FromQueryResult)]
+struct GroupTotal {
+    name: String,
+    count: i64,
+    amount: Option<Money>,
+}
+
+/// Number of largest expenses listed when the caller doesn't ask for a specific count
+pub const DEFAULT_TOP_EXPENSES: usize = 5;
+
 impl ReportsService {
+    /// Consolidated reports; see `with_scope` for a single branch
     pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+        Self { db, scope: ReportScope::Consolidated }
     }
 
-    /// Helper to get the last day of a month
-    fn last_day_of_month(date: NaiveDate) -> NaiveDate {
-        let (year, month) = (date.year(), date.month());
-        let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
-        let first_of_next_month = NaiveDate::from_ymd_opt(next_year, next_month, 1)
-            .expect("Invalid date for next month");
-        first_of_next_month.pred_opt().expect("Failed to get last day")
+    pub fn with_scope(mut self, scope: ReportScope) -> Self {
+        self.scope = scope;
+        self
     }
 
-    /// Generate monthly report for a given month (YYYY-MM-01)
-    pub async fn generate_monthly_report(&self, month: NaiveDate) -> Result<reports::Model, AppError> {
-        let end_of_month = Self::last_day_of_month(month);
+    /// Orders counted as income in this scope
+    pub(crate) fn order_scope(&self) -> Condition {
+        match self.scope {
+            ReportScope::Consolidated => Condition::all().add(orders::Column::CounterpartyBranchId.is_null()),
+            ReportScope::Branch(branch_id) => Condition::all().add(orders::Column::BranchId.eq(branch_id)),
+        }
+    }
+
+    /// Expenses recorded in this scope
+    pub(crate) fn expense_scope(&self) -> Condition {
+        match self.scope {
+            ReportScope::Consolidated => Condition::all(),
+            ReportScope::Branch(branch_id) => Condition::all().add(expenses::Column::BranchId.eq(branch_id)),
+        }
+    }
 
-        // Orders in that month
-        let orders_list = orders::Entity::find()
-            .filter(orders::Column::OrderDate.between(month, end_of_month))
+    /// Transfer orders this scope pays for; none when consolidated
+    pub(crate) fn transfers_billed(&self) -> Option<Condition> {
+        self.scope
+            .branch_id()
+            .map(|branch_id| Condition::all().add(orders::Column::CounterpartyBranchId.eq(branch_id)))
+    }
+
+    /// Report rows of this scope
+    fn report_scope(&self) -> Condition {
+        match self.scope {
+            ReportScope::Consolidated => Condition::all().add(reports::Column::BranchId.is_null()),
+            ReportScope::Branch(branch_id) => Condition::all().add(reports::Column::BranchId.eq(branch_id)),
+        }
+    }
+
+    /// Number of months when the range is made of whole calendar months
+    /// (a month, a fiscal quarter or year), otherwise `None`
+    fn whole_months(from: NaiveDate, to: NaiveDate) -> Option<u32> {
+        if from.day() != 1 || to != fiscal::end_of_month(to) || to < from {
+            return None;
+        }
+        let months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32 + 1;
+        Some(months as u32)
+    }
+
+    /// Range of the same length immediately before `from`.
+    /// Whole months (quarters, years) map to the same number of whole months before.
+    fn previous_period(from: NaiveDate, to: NaiveDate) -> (NaiveDate, NaiveDate) {
+        if let Some(months) = Self::whole_months(from, to) {
+            return (from - Months::new(months), from.pred_opt().expect("Failed to get previous day"));
+        }
+
+        let length = to - from;
+        let end = from.pred_opt().expect("Failed to get previous day");
+        (end - length, end)
+    }
+
+    /// Same range one year earlier (29 February falls back to the 28th)
+    fn same_period_last_year(from: NaiveDate, to: NaiveDate) -> (NaiveDate, NaiveDate) {
+        if Self::whole_months(from, to).is_some() {
+            let start = from - Months::new(12);
+            return (start, fiscal::end_of_month(to - Months::new(12)));
+        }
+
+        (from - Months::new(12), to - Months::new(12))
+    }
+
+    /// Order count and income for the range
+    async fn order_totals(&self, from: NaiveDate, to: NaiveDate) -> Result<(i32, Money), AppError> {
+        let totals: Option<(i64, Option<Money>)> = orders::Entity::find()
+            .select_only()
+            .column_as(Expr::col(orders::Column::OrderId).count(), "count")
+            .column_as(Expr::col(orders::Column::TotalAmount).sum(), "amount")
+            .filter(orders::Column::OrderDate.between(from, to))
+            .filter(self.order_scope())
+            .into_tuple()
+            .one(&self.db)
+            .await?;
+
+        let (count, amount) = totals.unwrap_or((0, None));
+        Ok((count as i32, Money::from_sum(amount)))
+    }
+
+    /// Exchange gains (positive) and losses of the payments received in the range
+    async fn fx_gain_loss(&self, from: NaiveDate, to: NaiveDate) -> Result<Money, AppError> {
+        let total: Option<Option<Money>> = payments::Entity::find()
+            .select_only()
+            .column_as(Expr::col((payments::Entity, payments::Column::FxGainLoss)).sum(), "amount")
+            .join(JoinType::InnerJoin, payments::Relation::Invoices.def())
+            .join(JoinType::InnerJoin, invoices::Relation::Orders.def())
+            .filter(payments::Column::PaymentDate.between(from, to))
+            .filter(self.order_scope())
+            .into_tuple()
+            .one(&self.db)
+            .await?;
+
+        Ok(Money::from_sum(total.flatten()))
+    }
+
+    /// Expense count and total per label; a branch also pays for the transfers billed to it
+    async fn expense_groups(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<GroupTotal>, AppError> {
+        let mut groups = expenses::Entity::find()
+            .select_only()
+            .column_as(expenses::Column::Label, "name")
+            .column_as(Expr::col(expenses::Column::ExpenseId).count(), "count")
+            .column_as(Expr::col(expenses::Column::Amount).sum(), "amount")
+            .filter(expenses::Column::ExpenseDate.between(from, to))
+            .filter(self.expense_scope())
+            .group_by(expenses::Column::Label)
+            .into_model::<GroupTotal>()
             .all(&self.db)
             .await?;
 
-        let total_orders = orders_list.len() as i32;
-        let total_income: f64 = orders_list.iter().map(|o| o.total_amount as f64).sum();
+        if let Some(billed) = self.transfers_billed() {
+            let transfers: Option<(i64, Option<Money>)> = orders::Entity::find()
+                .select_only()
+                .column_as(Expr::col(orders::Column::OrderId).count(), "count")
+                .column_as(Expr::col(orders::Column::TotalAmount).sum(), "amount")
+                .filter(orders::Column::OrderDate.between(from, to))
+                .filter(billed)
+                .into_tuple()
+                .one(&self.db)
+                .await?;
+
+            if let Some((count, amount)) = transfers.filter(|(count, _)| *count > 0) {
+                match groups.iter_mut().find(|group| group.name == TRANSFER_LABEL) {
+                    Some(group) => {
+                        group.count += count;
+                        group.amount = Some(Money::from_sum(group.amount) + Money::from_sum(amount));
+                    }
+                    None => groups.push(GroupTotal { name: TRANSFER_LABEL.to_string(), count, amount }),
+                }
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Order count and income per service (order description)
+    async fn income_groups(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<GroupTotal>, AppError> {
+        let groups = orders::Entity::find()
+            .select_only()
+            .column_as(orders::Column::Description, "name")
+            .column_as(Expr::col(orders::Column::OrderId).count(), "count")
+            .column_as(Expr::col(orders::Column::TotalAmount).sum(), "amount")
+            .filter(orders::Column::OrderDate.between(from, to))
+            .filter(self.order_scope())
+            .group_by(orders::Column::Description)
+            .into_model::<GroupTotal>()
+            .all(&self.db)
+            .await?;
+        Ok(groups)
+    }
 
-        // Expenses in that month
-        let expenses_list = expenses::Entity::find()
-            .filter(expenses::Column::ExpenseDate.between(month, end_of_month))
+    /// The `limit` largest expenses of the range
+    async fn top_expenses(&self, from: NaiveDate, to: NaiveDate, limit: usize) -> Result<Vec<TopExpense>, AppError> {
+        let largest = expenses::Entity::find()
+            .filter(expenses::Column::ExpenseDate.between(from, to))
+            .filter(self.expense_scope())
+            .order_by_desc(expenses::Column::Amount)
+            .limit(limit as u64)
             .all(&self.db)
             .await?;
 
-        let total_expenses: f64 = expenses_list.iter().map(|e| e.amount as f64).sum();
-        let net_profit = total_income - total_expenses;
+        Ok(largest
+            .into_iter()
+            .map(|e| TopExpense {
+                expense_id: e.expense_id,
+                description: e.description,
+                label: e.label,
+                amount: e.amount,
+                expense_date: e.expense_date,
+            })
+            .collect())
+    }
+
+    /// Per-day order count, income and expenses; days without activity are omitted
+    pub(crate) async fn daily_totals(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailyTotal>, AppError> {
+        let order_days: Vec<(NaiveDate, i64, Option<Money>)> = orders::Entity::find()
+            .select_only()
+            .column(orders::Column::OrderDate)
+            .column_as(Expr::col(orders::Column::OrderId).count(), "count")
+            .column_as(Expr::col(orders::Column::TotalAmount).sum(), "amount")
+            .filter(orders::Column::OrderDate.between(from, to))
+            .filter(self.order_scope())
+            .group_by(orders::Column::OrderDate)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        let mut expense_days: Vec<(NaiveDate, Option<Money>)> = expenses::Entity::find()
+            .select_only()
+            .column(expenses::Column::ExpenseDate)
+            .column_as(Expr::col(expenses::Column::Amount).sum(), "amount")
+            .filter(expenses::Column::ExpenseDate.between(from, to))
+            .filter(self.expense_scope())
+            .group_by(expenses::Column::ExpenseDate)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        if let Some(billed) = self.transfers_billed() {
+            let transfer_days: Vec<(NaiveDate, Option<Money>)> = orders::Entity::find()
+                .select_only()
+                .column(orders::Column::OrderDate)
+                .column_as(Expr::col(orders::Column::TotalAmount).sum(), "amount")
+                .filter(orders::Column::OrderDate.between(from, to))
+                .filter(billed)
+                .group_by(orders::Column::OrderDate)
+                .into_tuple()
+                .all(&self.db)
+                .await?;
+            expense_days.extend(transfer_days);
+        }
+
+        let mut days: BTreeMap<NaiveDate, DailyTotal> = BTreeMap::new();
+        let empty_day = |date| DailyTotal {
+            date,
+            orders: 0,
+            income: Money::ZERO,
+            expenses: Money::ZERO,
+            net: Money::ZERO,
+        };
+
+        for (date, count, amount) in order_days {
+            let day = days.entry(date).or_insert_with(|| empty_day(date));
+            day.orders = count as i32;
+            day.income = Money::from_sum(amount);
+        }
+        for (date, amount) in expense_days {
+            let day = days.entry(date).or_insert_with(|| empty_day(date));
+            day.expenses += Money::from_sum(amount);
+        }
+
+        Ok(days
+            .into_values()
+            .map(|mut day| {
+                day.net = day.income - day.expenses;
+                day
+            })
+            .collect())
+    }
+
+    /// Largest first, with each group's share of the total
+    fn group_lines(groups: Vec<GroupTotal>) -> Vec<BreakdownLine> {
+        let total: Money = groups.iter().map(|g| Money::from_sum(g.amount)).sum();
+
+        let mut lines: Vec<BreakdownLine> = groups
+            .into_iter()
+            .map(|group| {
+                let amount = Money::from_sum(group.amount);
+                BreakdownLine {
+                    name: group.name,
+                    count: group.count as i32,
+                    amount,
+                    share: amount.percentage_of(total).unwrap_or(Decimal::ZERO),
+                }
+            })
+            .collect();
+
+        lines.sort_by_key(|line| std::cmp::Reverse(line.amount));
+        lines
+    }
+
+    /// Summarize any inclusive date range
+    pub async fn summarize_period(&self, from: NaiveDate, to: NaiveDate) -> Result<PeriodSummary, AppError> {
+        let (total_orders, total_income) = self.order_totals(from, to).await?;
+
+        let expenses_by_label: BTreeMap<String, Money> = self
+            .expense_groups(from, to)
+            .await?
+            .into_iter()
+            .map(|group| (group.name, Money::from_sum(group.amount)))
+            .collect();
+        let total_expenses: Money = expenses_by_label.values().sum();
+        let fx_gain_loss = self.fx_gain_loss(from, to).await?;
+
+        Ok(PeriodSummary {
+            from,
+            to,
+            branch_id: self.scope.branch_id(),
+            total_orders,
+            total_income,
+            total_expenses,
+            expenses_by_label,
+            fx_gain_loss,
+            net_profit: total_income - total_expenses + fx_gain_loss,
+            currency: money::base_currency(),
+        })
+    }
+
+    /// The `limit` expense labels with the largest totals
+    pub async fn top_expense_labels(&self, from: NaiveDate, to: NaiveDate, limit: usize) -> Result<Vec<BreakdownLine>, AppError> {
+        let mut lines = Self::group_lines(self.expense_groups(from, to).await?);
+        lines.truncate(limit);
+        Ok(lines)
+    }
+
+    /// Expenses by label, income by service (order description) and the largest expenses
+    pub async fn breakdown_period(&self, from: NaiveDate, to: NaiveDate, top_n: usize) -> Result<ReportBreakdown, AppError> {
+        Ok(ReportBreakdown {
+            expenses_by_label: Self::group_lines(self.expense_groups(from, to).await?),
+            income_by_service: Self::group_lines(self.income_groups(from, to).await?),
+            top_expenses: self.top_expenses(from, to, top_n).await?,
+            currency: money::base_currency(),
+        })
+    }
+
+    fn delta(current: Money, reference: Money) -> Delta<Money> {
+        let absolute = current - reference;
+        Delta {
+            absolute,
+            percentage: absolute.percentage_of(reference.abs()),
+        }
+    }
+
+    fn count_delta(current: i32, reference: i32) -> Delta<i32> {
+        let absolute = current - reference;
+        Delta {
+            absolute,
+            percentage: (reference != 0).then(|| {
+                (Decimal::from(absolute) / Decimal::from(reference.abs()) * Decimal::ONE_HUNDRED).round_dp(2)
+            }),
+        }
+    }
+
+    fn deltas(current: &PeriodSummary, reference: &PeriodSummary) -> PeriodDeltas {
+        let labels: BTreeSet<&String> = current
+            .expenses_by_label
+            .keys()
+            .chain(reference.expenses_by_label.keys())
+            .collect();
+
+        let expenses_by_label = labels
+            .into_iter()
+            .map(|label| {
+                let now = current.expenses_by_label.get(label).copied().unwrap_or(Money::ZERO);
+                let then = reference.expenses_by_label.get(label).copied().unwrap_or(Money::ZERO);
+                (label.clone(), Self::delta(now, then))
+            })
+            .collect();
+
+        PeriodDeltas {
+            total_orders: Self::count_delta(current.total_orders, reference.total_orders),
+            total_income: Self::delta(current.total_income, reference.total_income),
+            total_expenses: Self::delta(current.total_expenses, reference.total_expenses),
+            expenses_by_label,
+            fx_gain_loss: Self::delta(current.fx_gain_loss, reference.fx_gain_loss),
+            net_profit: Self::delta(current.net_profit, reference.net_profit),
+        }
+    }
+
+    /// Compare a range with the previous period and the same period last year
+    pub async fn compare_period(&self, from: NaiveDate, to: NaiveDate) -> Result<PeriodComparison, AppError> {
+        if from > to {
+            return Err(AppError::BadRequest("Start date must not be after end date".into()));
+        }
+
+        let current = self.summarize_period(from, to).await?;
+        self.compare_with(current).await
+    }
+
+    /// Attach the previous period and last year to an already computed summary
+    async fn compare_with(&self, current: PeriodSummary) -> Result<PeriodComparison, AppError> {
+        let (prev_from, prev_to) = Self::previous_period(current.from, current.to);
+        let (ly_from, ly_to) = Self::same_period_last_year(current.from, current.to);
+
+        let previous_period = self.summarize_period(prev_from, prev_to).await?;
+        let same_period_last_year = self.summarize_period(ly_from, ly_to).await?;
+
+        Ok(PeriodComparison {
+            vs_previous_period: Self::deltas(&current, &previous_period),
+            vs_last_year: Self::deltas(&current, &same_period_last_year),
+            current,
+            previous_period,
+            same_period_last_year,
+        })
+    }
+
+    /// Fiscal year to date, compared with the same stretch of the previous fiscal year
+    pub async fn year_to_date(&self, as_of: NaiveDate) -> Result<PeriodComparison, AppError> {
+        let period = Period::year_to_date(as_of);
+        self.compare_period(period.from, period.to).await
+    }
+
+    /// Comparison and breakdown for an inclusive date range
+    pub async fn range_report(&self, from: NaiveDate, to: NaiveDate, top_n: usize) -> Result<RangeReport, AppError> {
+        if from > to {
+            return Err(AppError::BadRequest("Start date must not be after end date".into()));
+        }
+
+        let current = self.summarize_period(from, to).await?;
+        let breakdown = self.breakdown_period(from, to, top_n).await?;
+
+        Ok(RangeReport {
+            comparison: self.compare_with(current).await?,
+            breakdown,
+        })
+    }
+
+    /// Summary sheet: headline figures followed by the breakdown lines
+    fn summary_sheet(summary: &PeriodSummary, breakdown: &ReportBreakdown) -> Sheet {
+        let total = |item: &str, count: Option<i32>, amount: Money| {
+            vec![
+                Cell::Text("Totals".into()),
+                Cell::Text(item.into()),
+                Cell::Text(count.map(|c| c.to_string()).unwrap_or_default()),
+                Cell::Amount(amount),
+                Cell::Text(String::new()),
+            ]
+        };
+
+        let mut rows = vec![
+            total("Income", Some(summary.total_orders), summary.total_income),
+            total("Expenses", None, summary.total_expenses),
+            total("FX gain/loss", None, summary.fx_gain_loss),
+            total("Net profit", None, summary.net_profit),
+        ];
+
+        let sections = [
+            ("Income by service", &breakdown.income_by_service),
+            ("Expenses by label", &breakdown.expenses_by_label),
+        ];
+        for (section, lines) in sections {
+            for line in lines {
+                rows.push(vec![
+                    Cell::Text(section.into()),
+                    Cell::Text(line.name.clone()),
+                    Cell::Int(line.count.into()),
+                    Cell::Amount(line.amount),
+                    Cell::Percent(line.share),
+                ]);
+            }
+        }
+
+        Sheet {
+            name: "Summary".into(),
+            headers: vec!["Section", "Item", "Count", "Amount", "Share %"],
+            rows,
+        }
+    }
+
+    /// Export a period (month, fiscal quarter or year) as CSV (summary only)
+    /// or as a workbook with order and expense detail
+    pub async fn export_period(&self, period: &Period, format: ExportFormat) -> Result<ExportFile, AppError> {
+        let (from, to) = (period.from, period.to);
+
+        let summary = self.summarize_period(from, to).await?;
+        let breakdown = self.breakdown_period(from, to, DEFAULT_TOP_EXPENSES).await?;
+        let summary_sheet = Self::summary_sheet(&summary, &breakdown);
+
+        let basename = match self.scope {
+            ReportScope::Consolidated => format!("report-{}", period.name),
+            ReportScope::Branch(branch_id) => format!("report-{}-branch-{}", period.name, branch_id),
+        };
+        match format {
+            ExportFormat::Csv => export::csv_file(&basename, &summary_sheet),
+            ExportFormat::Xlsx => {
+                let orders_list = orders::Entity::find()
+                    .filter(orders::Column::OrderDate.between(from, to))
+                    .filter(self.order_scope())
+                    .order_by_asc(orders::Column::OrderDate)
+                    .all(&self.db)
+                    .await?;
+                let expenses_list = expenses::Entity::find()
+                    .filter(expenses::Column::ExpenseDate.between(from, to))
+                    .filter(self.expense_scope())
+                    .order_by_asc(expenses::Column::ExpenseDate)
+                    .all(&self.db)
+                    .await?;
+
+                export::xlsx_file(
+                    &basename,
+                    &[
+                        summary_sheet,
+                        Sheet::from_rows("Orders", &orders_list),
+                        Sheet::from_rows("Expenses", &expenses_list),
+                    ],
+                )
+            }
+            ExportFormat::Json => Err(AppError::BadRequest("JSON is not a file export format".into())),
+        }
+    }
+
+    /// Generate monthly report for a given month (YYYY-MM-01)
+    pub async fn generate_monthly_report(&self, month: NaiveDate) -> Result<reports::Model, AppError> {
+        let end_of_month = fiscal::end_of_month(month);
+
+        let summary = self.summarize_period(month, end_of_month).await?;
+        let breakdown = self.breakdown_period(month, end_of_month, DEFAULT_TOP_EXPENSES).await?;
+        let days = self.daily_totals(month, end_of_month).await?;
 
         let daily_data = serde_json::json!({
-            "orders": orders_list,
-            "expenses": expenses_list
+            "days": days,
+            "breakdown": breakdown
         });
 
-        let new_report = reports::ActiveModel {
-            month: Set(month),
-            total_orders: Set(total_orders),
-            total_income: Set(total_income),
-            total_expenses: Set(total_expenses),
-            net_profit: Set(net_profit),
-            daily_data: Set(daily_data),
-            ..Default::default()
+        // Regenerating a month replaces its previous report of the same scope
+        let existing = reports::Entity::find()
+            .filter(reports::Column::Month.eq(month))
+            .filter(self.report_scope())
+            .one(&self.db)
+            .await?;
+        let exists = existing.is_some();
+
+        let mut report: reports::ActiveModel = match existing {
+            Some(existing) => existing.into(),
+            None => reports::ActiveModel {
+                month: Set(month),
+                branch_id: Set(self.scope.branch_id()),
+                ..Default::default()
+            },
         };
 
-        let report = new_report.insert(&self.db).await?;
+        report.total_orders = Set(summary.total_orders);
+        report.total_income = Set(summary.total_income);
+        report.total_expenses = Set(summary.total_expenses);
+        report.fx_gain_loss = Set(summary.fx_gain_loss);
+        report.net_profit = Set(summary.net_profit);
+        report.currency = Set(summary.currency.to_string());
+        report.daily_data = Set(daily_data);
+        report.generated_at = Set(Utc::now().naive_utc());
+
+        let report = if exists {
+            report.update(&self.db).await?
+        } else {
+            report.insert(&self.db).await?
+        };
         Ok(report)
     }
 
-    /// Fetch all reports
+    /// Fetch all reports of this scope
     pub async fn get_all_reports(&self) -> Result<Vec<reports::Model>, AppError> {
         let all_reports = reports::Entity::find()
+            .filter(self.report_scope())
             .all(&self.db)
             .await?;
         Ok(all_reports)
@@ -87,9 +697,10 @@ impl ReportsService {
     pub async fn get_report_by_month(&self, month: NaiveDate) -> Result<reports::Model, AppError> {
         let report = reports::Entity::find()
             .filter(reports::Column::Month.eq(month))
+            .filter(self.report_scope())
             .one(&self.db)
             .await?
-            .ok_or(AppError::NotFound)?;
+            .ok_or(AppError::NotFound("Report not found".into()))?;
         Ok(report)
     }
-}
\ No newline at end of file
+}
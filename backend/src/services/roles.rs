@@ -0,0 +1,85 @@
+use sea_orm::{DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, PaginatorTrait};
+use serde::{Deserialize, Serialize};
+use crate::{
+    entities::{role_policies, users},
+    errors::AppError,
+    permissions::{Permission, Role},
+    services::api_keys,
+    services::users::UserResponse,
+};
+
+#[derive(Clone)]
+pub struct RolesService {
+    pub db: DatabaseConnection,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignRoleRequest {
+    pub role: String,
+}
+
+/// A role with everything it grants
+#[derive(Serialize)]
+pub struct RoleResponse {
+    pub role: Role,
+    pub permissions: &'static [Permission],
+    pub two_factor_required: bool,
+}
+
+impl RolesService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn get_roles(&self) -> Result<Vec<RoleResponse>, AppError> {
+        let policies = role_policies::Entity::find().all(&self.db).await?;
+
+        Ok(Role::ALL
+            .into_iter()
+            .map(|role| RoleResponse {
+                role,
+                permissions: role.permissions(),
+                two_factor_required: policies
+                    .iter()
+                    .any(|policy| policy.role == role.as_str() && policy.require_two_factor),
+            })
+            .collect())
+    }
+
+    /// Give a user another role; the last admin can't be demoted. API key service accounts
+    /// keep the permissions of their key, so their role can't be changed either.
+    pub async fn assign_role(&self, user_id: i32, req: AssignRoleRequest) -> Result<UserResponse, AppError> {
+        let role = Role::parse(&req.role)?;
+        let user = users::Entity::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .filter(|user| user.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+        let service_account = users::Entity::find_by_id(user_id)
+            .filter(api_keys::service_accounts())
+            .count(&self.db)
+            .await?;
+        if service_account > 0 {
+            return Err(AppError::BadRequest("API key service accounts have no role to change".into()));
+        }
+
+        if Role::from_column(&user.role) == Role::Admin && role != Role::Admin {
+            let admins = users::Entity::find()
+                .filter(users::Column::Role.eq(Role::Admin.as_str()))
+                .filter(users::Column::DeactivatedAt.is_null())
+                .filter(users::Column::DeletedAt.is_null())
+                .count(&self.db)
+                .await?;
+            if admins <= 1 {
+                return Err(AppError::BadRequest("The last admin cannot be given another role".into()));
+            }
+        }
+
+        let mut active: users::ActiveModel = user.into();
+        active.role = Set(role.as_str().to_string());
+        let updated = active.update(&self.db).await?;
+
+        Ok(updated.into())
+    }
+}
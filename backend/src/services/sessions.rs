@@ -0,0 +1,328 @@
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder, ConnectionTrait,
+};
+use sea_orm::sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use chrono::{Duration, NaiveDateTime, Utc};
+use std::net::IpAddr;
+use actix_web::{web, HttpRequest};
+
+use crate::{
+    entities::sessions,
+    errors::AppError,
+    config::Config,
+    middleware::auth::Claims,
+    tokens,
+};
+
+/// A session is one sign-in on one device. The client holds a short-lived access token
+/// and a refresh token; every refresh replaces the refresh token, and presenting a
+/// replaced one again revokes the session since the token must have been copied.
+#[derive(Clone)]
+pub struct SessionsService {
+    pub db: DatabaseConnection,
+    jwt_secret: String,
+    access_ttl: Duration,
+    refresh_ttl: Duration,
+}
+
+/// Where a sign-in or refresh came from
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let trusted_proxies = req
+            .app_data::<web::Data<Config>>()
+            .map(|config| config.trusted_proxies.as_slice())
+            .unwrap_or_default();
+
+        ClientInfo {
+            user_agent: req
+                .headers()
+                .get("User-Agent")
+                .and_then(|h| h.to_str().ok())
+                .map(str::to_string),
+            ip_address: client_ip(req, trusted_proxies).map(|ip| ip.to_string()),
+        }
+    }
+}
+
+/// Address a request came from. Forwarding headers are only believed when the connection
+/// comes from a trusted proxy, since anyone can send them: each proxy appends the address
+/// it got the request from, so the client is the last one that isn't a trusted proxy.
+fn client_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<IpAddr> = req
+        .headers()
+        .get_all("X-Forwarded-For")
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect();
+    Some(
+        forwarded
+            .into_iter()
+            .rev()
+            .find(|ip| !trusted_proxies.contains(ip))
+            .unwrap_or(peer),
+    )
+}
+
+#[derive(Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+    /// Seconds until `token` expires
+    pub expires_in: i64,
+}
+
+/// A signed-in device as its user sees it
+#[derive(Serialize)]
+pub struct SessionResponse {
+    pub session_id: i32,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    /// The session making the request
+    pub current: bool,
+}
+
+impl SessionsService {
+    pub fn new(db: DatabaseConnection, config: &Config) -> Self {
+        Self {
+            db,
+            jwt_secret: config.jwt_secret.clone(),
+            access_ttl: Duration::minutes(config.access_token_minutes),
+            refresh_ttl: Duration::days(config.refresh_token_days),
+        }
+    }
+
+    fn access_token(&self, user_id: i32, session_id: i32) -> Result<String, AppError> {
+        let expiration = Utc::now()
+            .checked_add_signed(self.access_ttl)
+            .ok_or(AppError::InternalError)?
+            .timestamp() as usize;
+
+        let claims = Claims { user_id, session_id, exp: expiration };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.jwt_secret.as_bytes()),
+        )
+        .map_err(|_| AppError::InternalError)
+    }
+
+    /// Open a session for a user who just proved who they are
+    pub async fn start(&self, user_id: i32, client: ClientInfo) -> Result<TokenPair, AppError> {
+        let now = Utc::now().naive_utc();
+        let refresh_token = tokens::generate();
+
+        let session = sessions::ActiveModel {
+            user_id: Set(user_id),
+            refresh_token_hash: Set(tokens::hash(&refresh_token)),
+            previous_token_hash: Set(None),
+            user_agent: Set(client.user_agent),
+            ip_address: Set(client.ip_address),
+            created_at: Set(now),
+            last_used_at: Set(now),
+            expires_at: Set(now + self.refresh_ttl),
+            revoked_at: Set(None),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+
+        Ok(TokenPair {
+            token: self.access_token(user_id, session.session_id)?,
+            refresh_token,
+            expires_in: self.access_ttl.num_seconds(),
+        })
+    }
+
+    /// Swap a refresh token for a new access token and a new refresh token
+    pub async fn refresh(&self, refresh_token: &str, client: ClientInfo) -> Result<TokenPair, AppError> {
+        let now = Utc::now().naive_utc();
+        let hash = tokens::hash(refresh_token);
+
+        let Some(session) = sessions::Entity::find()
+            .filter(sessions::Column::RefreshTokenHash.eq(hash.clone()))
+            .one(&self.db)
+            .await?
+        else {
+            // A replaced token coming back means two parties hold the session
+            if let Some(reused) = sessions::Entity::find()
+                .filter(sessions::Column::PreviousTokenHash.eq(hash))
+                .one(&self.db)
+                .await?
+            {
+                self.revoke(reused.session_id).await?;
+            }
+            return Err(AppError::Unauthorized);
+        };
+
+        if session.revoked_at.is_some() || session.expires_at <= now {
+            return Err(AppError::Unauthorized);
+        }
+
+        let user_id = session.user_id;
+        let new_token = tokens::generate();
+        let mut active: sessions::ActiveModel = session.into();
+        active.previous_token_hash = Set(Some(hash));
+        active.refresh_token_hash = Set(tokens::hash(&new_token));
+        active.last_used_at = Set(now);
+        active.expires_at = Set(now + self.refresh_ttl);
+        if client.user_agent.is_some() {
+            active.user_agent = Set(client.user_agent);
+        }
+        if client.ip_address.is_some() {
+            active.ip_address = Set(client.ip_address);
+        }
+        let session = active.update(&self.db).await?;
+
+        Ok(TokenPair {
+            token: self.access_token(user_id, session.session_id)?,
+            refresh_token: new_token,
+            expires_in: self.access_ttl.num_seconds(),
+        })
+    }
+
+    /// End one session; its access token stops working at once
+    pub async fn revoke(&self, session_id: i32) -> Result<(), AppError> {
+        sessions::Entity::update_many()
+            .col_expr(sessions::Column::RevokedAt, Expr::value(Utc::now().naive_utc()))
+            .filter(sessions::Column::SessionId.eq(session_id))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Open sessions of a user, most recently used first
+    pub async fn get_active(&self, user_id: i32, current_session: i32) -> Result<Vec<SessionResponse>, AppError> {
+        let sessions = sessions::Entity::find()
+            .filter(sessions::Column::UserId.eq(user_id))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .filter(sessions::Column::ExpiresAt.gt(Utc::now().naive_utc()))
+            .order_by_desc(sessions::Column::LastUsedAt)
+            .all(&self.db)
+            .await?;
+
+        Ok(sessions
+            .into_iter()
+            .map(|session| SessionResponse {
+                current: session.session_id == current_session,
+                session_id: session.session_id,
+                user_agent: session.user_agent,
+                ip_address: session.ip_address,
+                created_at: session.created_at,
+                last_used_at: session.last_used_at,
+                expires_at: session.expires_at,
+            })
+            .collect())
+    }
+
+    /// End one of a user's own sessions
+    pub async fn revoke_own(&self, user_id: i32, session_id: i32) -> Result<(), AppError> {
+        let session = sessions::Entity::find_by_id(session_id)
+            .one(&self.db)
+            .await?
+            .filter(|session| session.user_id == user_id)
+            .ok_or_else(|| AppError::NotFound("Session not found".into()))?;
+        self.revoke(session.session_id).await
+    }
+
+    /// End every session of a user but one; returns how many were still open
+    pub async fn revoke_others(&self, user_id: i32, keep_session: i32) -> Result<u64, AppError> {
+        let result = sessions::Entity::update_many()
+            .col_expr(sessions::Column::RevokedAt, Expr::value(Utc::now().naive_utc()))
+            .filter(sessions::Column::UserId.eq(user_id))
+            .filter(sessions::Column::SessionId.ne(keep_session))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// End every session of a user; returns how many were still open
+    pub async fn revoke_all(&self, user_id: i32) -> Result<u64, AppError> {
+        Self::revoke_all_on(&self.db, user_id).await
+    }
+
+    /// `revoke_all` on the caller's connection, so it commits with the caller's other writes
+    pub(crate) async fn revoke_all_on<C: ConnectionTrait>(conn: &C, user_id: i32) -> Result<u64, AppError> {
+        let result = sessions::Entity::update_many()
+            .col_expr(sessions::Column::RevokedAt, Expr::value(Utc::now().naive_utc()))
+            .filter(sessions::Column::UserId.eq(user_id))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .exec(conn)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// True when the session behind an access token is neither revoked nor expired
+pub async fn is_active(db: &DatabaseConnection, session_id: i32, user_id: i32) -> Result<bool, AppError> {
+    let session = sessions::Entity::find_by_id(session_id).one(db).await?;
+    Ok(session.is_some_and(|session| {
+        session.user_id == user_id
+            && session.revoked_at.is_none()
+            && session.expires_at > Utc::now().naive_utc()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    const PROXY: &str = "10.0.0.1";
+
+    fn request(peer: &str, forwarded_for: Option<&str>) -> HttpRequest {
+        let mut req = TestRequest::default().peer_addr(format!("{}:40000", peer).parse().unwrap());
+        if let Some(forwarded_for) = forwarded_for {
+            req = req.insert_header(("X-Forwarded-For", forwarded_for));
+        }
+        req.to_http_request()
+    }
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn ignores_forwarded_for_from_untrusted_peers() {
+        let req = request("203.0.113.7", Some("198.51.100.1"));
+        assert_eq!(client_ip(&req, &[]), ip("203.0.113.7"));
+        assert_eq!(client_ip(&req, &[PROXY.parse().unwrap()]), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn takes_the_last_untrusted_hop_behind_a_trusted_proxy() {
+        let trusted = [PROXY.parse().unwrap()];
+        // The client made up the first entry; the proxy appended the real one
+        let req = request(PROXY, Some("198.51.100.1, 203.0.113.7"));
+        assert_eq!(client_ip(&req, &trusted), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn falls_back_to_the_proxy_without_a_header() {
+        let req = request(PROXY, None);
+        assert_eq!(client_ip(&req, &[PROXY.parse().unwrap()]), ip(PROXY));
+    }
+}
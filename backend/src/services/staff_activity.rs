@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QuerySelect, Condition,
+    ConnectionTrait,
+};
+use sea_orm::prelude::Decimal;
+use sea_orm::sea_query::Expr;
+use serde::Serialize;
+use chrono::{NaiveDate, Utc};
+use crate::{
+    entities::{expenses, orders, record_edits, users},
+    errors::AppError,
+    money::{self, Money},
+};
+
+/// `record_edits.record_type` values
+pub const RECORD_ORDER: &str = "order";
+pub const RECORD_EXPENSE: &str = "expense";
+
+/// `record_edits.action` values
+pub const ACTION_UPDATE: &str = "update";
+pub const ACTION_DELETE: &str = "delete";
+
+#[derive(Clone)]
+pub struct StaffActivityService {
+    pub db: DatabaseConnection,
+}
+
+/// What one user entered and changed in a period
+#[derive(Serialize, Default)]
+pub struct StaffActivity {
+    /// `None` for records whose user was deleted or never recorded
+    pub user_id: Option<i32>,
+    pub username: String,
+    pub orders_entered: i32,
+    pub revenue_entered: Money,
+    pub expenses_recorded: i32,
+    pub expenses_amount: Money,
+    pub order_edits: i32,
+    pub expense_edits: i32,
+    pub deletions: i32,
+    /// Edits and deletions per 100 records entered; `None` when nothing was entered
+    pub edit_rate: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+pub struct StaffActivityReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// `None` for every branch
+    pub branch_id: Option<i32>,
+    /// Largest revenue first
+    pub users: Vec<StaffActivity>,
+    pub currency: &'static str,
+}
+
+impl StaffActivityService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Log a change to an order or expense; `modified_by` only keeps the last editor
+    pub async fn record_edit<C: ConnectionTrait>(
+        conn: &C,
+        record_type: &str,
+        record_id: i32,
+        action: &str,
+        user_id: i32,
+        branch_id: i32,
+    ) -> Result<(), AppError> {
+        record_edits::ActiveModel {
+            record_type: Set(record_type.to_string()),
+            record_id: Set(record_id),
+            action: Set(action.to_string()),
+            user_id: Set(Some(user_id)),
+            branch_id: Set(branch_id),
+            edited_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Orders and revenue entered, expenses recorded and edits made per user.
+    /// Orders and expenses count by their own date, edits by when they were made.
+    pub async fn report(&self, from: NaiveDate, to: NaiveDate, branch_id: Option<i32>) -> Result<StaffActivityReport, AppError> {
+        if from > to {
+            return Err(AppError::BadRequest("Start date must not be after end date".into()));
+        }
+
+        let mut order_scope = Condition::all();
+        let mut expense_scope = Condition::all();
+        let mut edit_scope = Condition::all();
+        if let Some(branch_id) = branch_id {
+            order_scope = order_scope.add(orders::Column::BranchId.eq(branch_id));
+            expense_scope = expense_scope.add(expenses::Column::BranchId.eq(branch_id));
+            edit_scope = edit_scope.add(record_edits::Column::BranchId.eq(branch_id));
+        }
+
+        let entered: Vec<(Option<i32>, i64, Option<Money>)> = orders::Entity::find()
+            .select_only()
+            .column(orders::Column::CreatedBy)
+            .column_as(Expr::col(orders::Column::OrderId).count(), "count")
+            .column_as(Expr::col(orders::Column::TotalAmount).sum(), "amount")
+            .filter(orders::Column::OrderDate.between(from, to))
+            .filter(order_scope)
+            .group_by(orders::Column::CreatedBy)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        let recorded: Vec<(Option<i32>, i64, Option<Money>)> = expenses::Entity::find()
+            .select_only()
+            .column(expenses::Column::CreatedBy)
+            .column_as(Expr::col(expenses::Column::ExpenseId).count(), "count")
+            .column_as(Expr::col(expenses::Column::Amount).sum(), "amount")
+            .filter(expenses::Column::ExpenseDate.between(from, to))
+            .filter(expense_scope)
+            .group_by(expenses::Column::CreatedBy)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        let edit_start = from.and_hms_opt(0, 0, 0).ok_or(AppError::InternalError)?;
+        let edit_end = to.succ_opt().and_then(|day| day.and_hms_opt(0, 0, 0)).ok_or(AppError::InternalError)?;
+        let edits: Vec<(Option<i32>, String, String, i64)> = record_edits::Entity::find()
+            .select_only()
+            .column(record_edits::Column::UserId)
+            .column(record_edits::Column::RecordType)
+            .column(record_edits::Column::Action)
+            .column_as(Expr::col(record_edits::Column::EditId).count(), "count")
+            .filter(record_edits::Column::EditedAt.gte(edit_start))
+            .filter(record_edits::Column::EditedAt.lt(edit_end))
+            .filter(edit_scope)
+            .group_by(record_edits::Column::UserId)
+            .group_by(record_edits::Column::RecordType)
+            .group_by(record_edits::Column::Action)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        let mut activity: BTreeMap<Option<i32>, StaffActivity> = BTreeMap::new();
+        for (user_id, count, amount) in entered {
+            let user = activity.entry(user_id).or_default();
+            user.orders_entered = count as i32;
+            user.revenue_entered = Money::from_sum(amount);
+        }
+        for (user_id, count, amount) in recorded {
+            let user = activity.entry(user_id).or_default();
+            user.expenses_recorded = count as i32;
+            user.expenses_amount = Money::from_sum(amount);
+        }
+        for (user_id, record_type, action, count) in edits {
+            let user = activity.entry(user_id).or_default();
+            match (record_type.as_str(), action.as_str()) {
+                (_, ACTION_DELETE) => user.deletions += count as i32,
+                (RECORD_ORDER, _) => user.order_edits += count as i32,
+                (_, _) => user.expense_edits += count as i32,
+            }
+        }
+
+        let ids: Vec<i32> = activity.keys().flatten().copied().collect();
+        let usernames: BTreeMap<i32, String> = users::Entity::find()
+            .filter(users::Column::UserId.is_in(ids))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|user| (user.user_id, user.username))
+            .collect();
+
+        let mut users_list: Vec<StaffActivity> = activity
+            .into_iter()
+            .map(|(user_id, mut user)| {
+                user.user_id = user_id;
+                user.username = match user_id {
+                    Some(id) => usernames.get(&id).cloned().unwrap_or_else(|| format!("User #{}", id)),
+                    None => "Unknown".to_string(),
+                };
+
+                let entered = user.orders_entered + user.expenses_recorded;
+                let changes = user.order_edits + user.expense_edits + user.deletions;
+                user.edit_rate = (entered > 0).then(|| {
+                    (Decimal::from(changes) / Decimal::from(entered) * Decimal::ONE_HUNDRED).round_dp(2)
+                });
+                user
+            })
+            .collect();
+        users_list.sort_by_key(|user| std::cmp::Reverse(user.revenue_entered));
+
+        Ok(StaffActivityReport {
+            from,
+            to,
+            branch_id,
+            users: users_list,
+            currency: money::base_currency(),
+        })
+    }
+}
@@ -0,0 +1,560 @@
+use std::collections::{BTreeMap, HashMap};
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder, QuerySelect,
+    JoinType, RelationTrait,
+};
+use sea_orm::sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use crate::{
+    entities::{accounts, invoices, orders, payments, statement_mappings},
+    errors::AppError,
+    fiscal::Period,
+    money::{self, Money},
+    services::ledger::{AccountCategory, AccountType, LedgerService, OPERATING_EXPENSES, SERVICE_REVENUE},
+};
+
+#[derive(Clone)]
+pub struct StatementsService {
+    pub db: DatabaseConnection,
+}
+
+/// Where an expense label lands on the income statement
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpenseSection {
+    CostOfServices,
+    OperatingExpenses,
+    OtherExpenses,
+}
+
+impl ExpenseSection {
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value {
+            "cost_of_services" => Ok(ExpenseSection::CostOfServices),
+            "operating_expenses" => Ok(ExpenseSection::OperatingExpenses),
+            "other_expenses" => Ok(ExpenseSection::OtherExpenses),
+            other => Err(AppError::BadRequest(format!(
+                "Unknown statement section '{}', expected cost_of_services, operating_expenses or other_expenses",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExpenseSection::CostOfServices => "cost_of_services",
+            ExpenseSection::OperatingExpenses => "operating_expenses",
+            ExpenseSection::OtherExpenses => "other_expenses",
+        }
+    }
+}
+
+/// Map an expense label (or the name of an extra expense account) to a statement line.
+/// Unmapped labels are listed under operating expenses with their own name.
+#[derive(Deserialize)]
+pub struct SaveMappingRequest {
+    pub label: String,
+    pub section: String,
+    pub line: Option<String>, // defaults to the label
+}
+
+#[derive(Serialize)]
+pub struct StatementLine {
+    pub name: String,
+    pub amount: Money,
+}
+
+#[derive(Serialize)]
+pub struct StatementSection {
+    pub lines: Vec<StatementLine>,
+    pub total: Money,
+}
+
+#[derive(Serialize)]
+pub struct IncomeStatement {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub revenue: StatementSection,
+    pub cost_of_services: StatementSection,
+    pub gross_profit: Money,
+    pub operating_expenses: StatementSection,
+    pub operating_income: Money,
+    pub other_income: StatementSection,
+    pub other_expenses: StatementSection,
+    pub net_income: Money,
+    pub currency: &'static str,
+}
+
+/// Invoice with money still owed on the balance-sheet date
+#[derive(Serialize)]
+pub struct UnpaidInvoice {
+    pub invoice_id: i32,
+    pub order_id: i32,
+    pub invoice_date: NaiveDate,
+    pub total_amount: Money,
+    pub paid: Money,
+    pub outstanding: Money,
+}
+
+#[derive(Serialize)]
+pub struct BalanceSheet {
+    pub as_of: NaiveDate,
+    pub cash_and_bank: StatementSection,
+    pub receivables: StatementSection,
+    pub fixed_assets: StatementSection,
+    pub other_assets: StatementSection,
+    pub total_assets: Money,
+    pub payables: StatementSection,
+    pub other_liabilities: StatementSection,
+    pub total_liabilities: Money,
+    pub equity: StatementSection,
+    pub total_liabilities_and_equity: Money,
+    pub balanced: bool,
+    pub unpaid_invoices: Vec<UnpaidInvoice>,
+    pub currency: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct CashAccountBalance {
+    pub account_id: i32,
+    pub code: String,
+    pub name: String,
+    pub category: String,
+    pub balance: Money,
+}
+
+/// Money on hand and in the bank
+#[derive(Serialize)]
+pub struct CashPosition {
+    pub as_of: NaiveDate,
+    pub accounts: Vec<CashAccountBalance>,
+    pub total: Money,
+    pub currency: &'static str,
+}
+
+impl StatementSection {
+    /// Largest line first; lines that net to zero are left out
+    fn from_lines(lines: BTreeMap<String, Money>) -> Self {
+        let mut lines: Vec<StatementLine> = lines
+            .into_iter()
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|(name, amount)| StatementLine { name, amount })
+            .collect();
+        lines.sort_by_key(|line| std::cmp::Reverse(line.amount));
+
+        let total = lines.iter().map(|line| line.amount).sum();
+        StatementSection { lines, total }
+    }
+}
+
+impl StatementsService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Fetch the label → statement line mapping
+    pub async fn get_mappings(&self) -> Result<Vec<statement_mappings::Model>, AppError> {
+        let mappings = statement_mappings::Entity::find()
+            .order_by_asc(statement_mappings::Column::Section)
+            .order_by_asc(statement_mappings::Column::Label)
+            .all(&self.db)
+            .await?;
+        Ok(mappings)
+    }
+
+    /// Create or replace the mapping of a label
+    pub async fn save_mapping(&self, req: SaveMappingRequest) -> Result<statement_mappings::Model, AppError> {
+        let section = ExpenseSection::parse(&req.section)?;
+        let label = req.label.trim().to_string();
+        if label.is_empty() {
+            return Err(AppError::BadRequest("Label is required".into()));
+        }
+        let line = req
+            .line
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .unwrap_or_else(|| label.clone());
+
+        let existing = statement_mappings::Entity::find()
+            .filter(statement_mappings::Column::Label.eq(label.as_str()))
+            .one(&self.db)
+            .await?;
+
+        let mapping = match existing {
+            Some(existing) => {
+                let mut active: statement_mappings::ActiveModel = existing.into();
+                active.section = Set(section.as_str().to_string());
+                active.line = Set(line);
+                active.update(&self.db).await?
+            }
+            None => {
+                statement_mappings::ActiveModel {
+                    label: Set(label),
+                    section: Set(section.as_str().to_string()),
+                    line: Set(line),
+                    ..Default::default()
+                }
+                .insert(&self.db)
+                .await?
+            }
+        };
+
+        Ok(mapping)
+    }
+
+    /// Delete mapping; the label falls back to operating expenses
+    pub async fn delete_mapping(&self, id: i32) -> Result<(), AppError> {
+        let result = statement_mappings::Entity::delete_by_id(id).exec(&self.db).await?;
+        if result.rows_affected == 0 {
+            return Err(AppError::NotFound("Mapping not found".into()));
+        }
+        Ok(())
+    }
+
+    /// Income statement for an inclusive date range, built from the ledger
+    pub async fn income_statement(&self, from: NaiveDate, to: NaiveDate) -> Result<IncomeStatement, AppError> {
+        let accounts_by_id: HashMap<i32, accounts::Model> = accounts::Entity::find()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|account| (account.account_id, account))
+            .collect();
+
+        let mappings: HashMap<String, (ExpenseSection, String)> = self
+            .get_mappings()
+            .await?
+            .into_iter()
+            .filter_map(|mapping| {
+                let section = ExpenseSection::parse(&mapping.section).ok()?;
+                Some((mapping.label.to_lowercase(), (section, mapping.line)))
+            })
+            .collect();
+
+        let mut revenue = BTreeMap::new();
+        let mut other_income = BTreeMap::new();
+        let mut cost_of_services = BTreeMap::new();
+        let mut operating_expenses = BTreeMap::new();
+        let mut other_expenses = BTreeMap::new();
+
+        let totals = LedgerService::new(self.db.clone())
+            .line_totals(Some(from), to, None, true)
+            .await?;
+
+        for total in totals {
+            let Some(account) = accounts_by_id.get(&total.account_id) else {
+                continue;
+            };
+            let debit = Money::from_sum(total.debit);
+            let credit = Money::from_sum(total.credit);
+
+            match AccountType::parse(&account.account_type)? {
+                AccountType::Revenue if account.code == SERVICE_REVENUE => {
+                    let service = total.memo.unwrap_or_else(|| account.name.clone());
+                    *revenue.entry(service).or_default() += credit - debit;
+                }
+                AccountType::Revenue => {
+                    *other_income.entry(account.name.clone()).or_default() += credit - debit;
+                }
+                AccountType::Expense => {
+                    // Expense postings carry their label as memo; other expense accounts map by name
+                    let label = if account.code == OPERATING_EXPENSES {
+                        total.memo.unwrap_or_else(|| account.name.clone())
+                    } else {
+                        account.name.clone()
+                    };
+                    let (section, line) = mappings
+                        .get(&label.to_lowercase())
+                        .cloned()
+                        .unwrap_or((ExpenseSection::OperatingExpenses, label));
+
+                    let lines = match section {
+                        ExpenseSection::CostOfServices => &mut cost_of_services,
+                        ExpenseSection::OperatingExpenses => &mut operating_expenses,
+                        ExpenseSection::OtherExpenses => &mut other_expenses,
+                    };
+                    *lines.entry(line).or_default() += debit - credit;
+                }
+                AccountType::Asset | AccountType::Liability | AccountType::Equity => {}
+            }
+        }
+
+        let revenue = StatementSection::from_lines(revenue);
+        let cost_of_services = StatementSection::from_lines(cost_of_services);
+        let operating_expenses = StatementSection::from_lines(operating_expenses);
+        let other_income = StatementSection::from_lines(other_income);
+        let other_expenses = StatementSection::from_lines(other_expenses);
+
+        let gross_profit = revenue.total - cost_of_services.total;
+        let operating_income = gross_profit - operating_expenses.total;
+        let net_income = operating_income + other_income.total - other_expenses.total;
+
+        Ok(IncomeStatement {
+            from,
+            to,
+            revenue,
+            cost_of_services,
+            gross_profit,
+            operating_expenses,
+            operating_income,
+            other_income,
+            other_expenses,
+            net_income,
+            currency: money::base_currency(),
+        })
+    }
+
+    /// Every account with its balance on its normal side as of a date
+    async fn account_balances(&self, as_of: NaiveDate) -> Result<Vec<(accounts::Model, AccountType, Money)>, AppError> {
+        let totals: HashMap<i32, Money> = LedgerService::new(self.db.clone())
+            .line_totals(None, as_of, None, false)
+            .await?
+            .into_iter()
+            .map(|total| {
+                let net = Money::from_sum(total.debit) - Money::from_sum(total.credit);
+                (total.account_id, net)
+            })
+            .collect();
+
+        let accounts_list = accounts::Entity::find()
+            .order_by_asc(accounts::Column::Code)
+            .all(&self.db)
+            .await?;
+
+        accounts_list
+            .into_iter()
+            .map(|account| {
+                let account_type = AccountType::parse(&account.account_type)?;
+                let net = totals.get(&account.account_id).copied().unwrap_or_default();
+                let balance = if account_type.is_debit_normal() { net } else { -net };
+                Ok((account, account_type, balance))
+            })
+            .collect()
+    }
+
+    /// Invoices dated up to `as_of` that weren't fully paid by then. Transfers between
+    /// branches stay out of the ledger, so their invoices aren't receivables either.
+    async fn unpaid_invoices(&self, as_of: NaiveDate) -> Result<Vec<UnpaidInvoice>, AppError> {
+        let paid: HashMap<i32, Money> = payments::Entity::find()
+            .select_only()
+            .column(payments::Column::InvoiceId)
+            .column_as(Expr::col(payments::Column::Amount).sum(), "paid")
+            .filter(payments::Column::PaymentDate.lte(as_of))
+            .group_by(payments::Column::InvoiceId)
+            .into_tuple::<(i32, Option<Money>)>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|(invoice_id, amount)| (invoice_id, Money::from_sum(amount)))
+            .collect();
+
+        let invoices_list = invoices::Entity::find()
+            .join(JoinType::InnerJoin, invoices::Relation::Orders.def())
+            .filter(orders::Column::CounterpartyBranchId.is_null())
+            .filter(invoices::Column::InvoiceDate.lte(as_of))
+            .order_by_asc(invoices::Column::InvoiceDate)
+            .all(&self.db)
+            .await?;
+
+        Ok(invoices_list
+            .into_iter()
+            .filter_map(|invoice| {
+                let paid = paid.get(&invoice.invoice_id).copied().unwrap_or_default();
+                let outstanding = invoice.total_amount - paid;
+                (outstanding > Money::ZERO).then_some(UnpaidInvoice {
+                    invoice_id: invoice.invoice_id,
+                    order_id: invoice.order_id,
+                    invoice_date: invoice.invoice_date,
+                    total_amount: invoice.total_amount,
+                    paid,
+                    outstanding,
+                })
+            })
+            .collect())
+    }
+
+    /// Balance sheet as of a date, from the ledger and the opening balances
+    pub async fn balance_sheet(&self, as_of: NaiveDate) -> Result<BalanceSheet, AppError> {
+        let mut cash_and_bank = BTreeMap::new();
+        let mut receivables = BTreeMap::new();
+        let mut fixed_assets = BTreeMap::new();
+        let mut other_assets = BTreeMap::new();
+        let mut payables = BTreeMap::new();
+        let mut other_liabilities = BTreeMap::new();
+        let mut equity = BTreeMap::new();
+        let mut retained_earnings = Money::ZERO;
+
+        // Income and expenses close into retained earnings at the end of each fiscal year;
+        // what was earned since the current one started is shown on its own line
+        let year_start = Period::year_to_date(as_of).from;
+        let mut prior_years_earnings = Money::ZERO;
+        if let Some(prior_year_end) = year_start.pred_opt() {
+            for (_, account_type, balance) in self.account_balances(prior_year_end).await? {
+                match account_type {
+                    AccountType::Revenue => prior_years_earnings += balance,
+                    AccountType::Expense => prior_years_earnings -= balance,
+                    _ => {}
+                }
+            }
+        }
+
+        for (account, account_type, balance) in self.account_balances(as_of).await? {
+            let category = account.category.as_deref().and_then(|c| AccountCategory::parse(c).ok());
+            let lines = match (account_type, category) {
+                (AccountType::Asset, Some(AccountCategory::Cash | AccountCategory::Bank)) => &mut cash_and_bank,
+                (AccountType::Asset, Some(AccountCategory::Receivable)) => &mut receivables,
+                (AccountType::Asset, Some(AccountCategory::FixedAsset)) => &mut fixed_assets,
+                (AccountType::Asset, _) => &mut other_assets,
+                (AccountType::Liability, Some(AccountCategory::Payable)) => &mut payables,
+                (AccountType::Liability, _) => &mut other_liabilities,
+                (AccountType::Equity, _) => &mut equity,
+                // Income and expenses close into retained earnings
+                (AccountType::Revenue, _) => {
+                    retained_earnings += balance;
+                    continue;
+                }
+                (AccountType::Expense, _) => {
+                    retained_earnings -= balance;
+                    continue;
+                }
+            };
+            *lines.entry(account.name).or_default() += balance;
+        }
+        equity.insert("Retained earnings".to_string(), prior_years_earnings);
+        equity.insert("Current year earnings".to_string(), retained_earnings - prior_years_earnings);
+
+        let cash_and_bank = StatementSection::from_lines(cash_and_bank);
+        let receivables = StatementSection::from_lines(receivables);
+        let fixed_assets = StatementSection::from_lines(fixed_assets);
+        let other_assets = StatementSection::from_lines(other_assets);
+        let payables = StatementSection::from_lines(payables);
+        let other_liabilities = StatementSection::from_lines(other_liabilities);
+        let equity = StatementSection::from_lines(equity);
+
+        let total_assets = cash_and_bank.total + receivables.total + fixed_assets.total + other_assets.total;
+        let total_liabilities = payables.total + other_liabilities.total;
+        let total_liabilities_and_equity = total_liabilities + equity.total;
+
+        Ok(BalanceSheet {
+            as_of,
+            cash_and_bank,
+            receivables,
+            fixed_assets,
+            other_assets,
+            total_assets,
+            payables,
+            other_liabilities,
+            total_liabilities,
+            equity,
+            total_liabilities_and_equity,
+            balanced: total_assets == total_liabilities_and_equity,
+            unpaid_invoices: self.unpaid_invoices(as_of).await?,
+            currency: money::base_currency(),
+        })
+    }
+
+    /// Balance of every cash and bank account as of a date
+    pub async fn cash_position(&self, as_of: NaiveDate) -> Result<CashPosition, AppError> {
+        let accounts: Vec<CashAccountBalance> = self
+            .account_balances(as_of)
+            .await?
+            .into_iter()
+            .filter_map(|(account, _, balance)| {
+                let category = AccountCategory::parse(account.category.as_deref()?).ok()?;
+                matches!(category, AccountCategory::Cash | AccountCategory::Bank).then_some(CashAccountBalance {
+                    account_id: account.account_id,
+                    code: account.code,
+                    name: account.name,
+                    category: category.as_str().to_string(),
+                    balance,
+                })
+            })
+            .collect();
+
+        let total = accounts.iter().map(|account| account.balance).sum();
+        Ok(CashPosition {
+            as_of,
+            accounts,
+            total,
+            currency: money::base_currency(),
+        })
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn section_rows(html: &mut String, title: &str, section: &StatementSection, total_label: &str) {
+    html.push_str(&format!("<tr class=\"heading\"><td colspan=\"2\">{}</td></tr>\n", title));
+    for line in &section.lines {
+        html.push_str(&format!(
+            "<tr><td class=\"line\">{}</td><td class=\"amount\">{}</td></tr>\n",
+            escape_html(&line.name),
+            line.amount
+        ));
+    }
+    html.push_str(&format!(
+        "<tr class=\"subtotal\"><td>{}</td><td class=\"amount\">{}</td></tr>\n",
+        total_label, section.total
+    ));
+}
+
+fn result_row(html: &mut String, label: &str, amount: Money) {
+    html.push_str(&format!(
+        "<tr class=\"result\"><td>{}</td><td class=\"amount\">{}</td></tr>\n",
+        label, amount
+    ));
+}
+
+/// Printable single-page layout of an income statement
+pub fn render_income_statement_html(statement: &IncomeStatement) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!(
+        "<title>Income Statement {} to {}</title>\n",
+        statement.from, statement.to
+    ));
+    html.push_str(
+        "<style>\n\
+         body { font-family: Georgia, serif; margin: 2cm; }\n\
+         h1, h2 { text-align: center; margin: 0.2em; }\n\
+         table { width: 100%; border-collapse: collapse; margin-top: 1.5em; }\n\
+         td { padding: 0.25em 0.5em; }\n\
+         .heading td { font-weight: bold; padding-top: 1em; }\n\
+         .line { padding-left: 2em; }\n\
+         .amount { text-align: right; font-variant-numeric: tabular-nums; }\n\
+         .subtotal td { border-top: 1px solid #000; font-style: italic; }\n\
+         .result td { border-top: 1px solid #000; font-weight: bold; }\n\
+         .net td { border-top: 1px solid #000; border-bottom: 3px double #000; font-weight: bold; }\n\
+         @media print { body { margin: 0; } }\n\
+         </style>\n</head>\n<body>\n",
+    );
+    html.push_str("<h1>Income Statement</h1>\n");
+    html.push_str(&format!(
+        "<h2>{} to {}</h2>\n<p style=\"text-align: center\">Amounts in {}</p>\n<table>\n",
+        statement.from.format("%B %-d, %Y"),
+        statement.to.format("%B %-d, %Y"),
+        statement.currency
+    ));
+
+    section_rows(&mut html, "Revenue", &statement.revenue, "Total revenue");
+    section_rows(&mut html, "Cost of services", &statement.cost_of_services, "Total cost of services");
+    result_row(&mut html, "Gross profit", statement.gross_profit);
+    section_rows(&mut html, "Operating expenses", &statement.operating_expenses, "Total operating expenses");
+    result_row(&mut html, "Operating income", statement.operating_income);
+    section_rows(&mut html, "Other income", &statement.other_income, "Total other income");
+    section_rows(&mut html, "Other expenses", &statement.other_expenses, "Total other expenses");
+
+    html.push_str(&format!(
+        "<tr class=\"net\"><td>Net income</td><td class=\"amount\">{}</td></tr>\n",
+        statement.net_income
+    ));
+    html.push_str("</table>\n</body>\n</html>\n");
+
+    html
+}
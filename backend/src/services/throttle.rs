@@ -0,0 +1,345 @@
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder, Condition,
+};
+use sea_orm::sea_query::Expr;
+use serde::Deserialize;
+use chrono::{Duration, NaiveDateTime, Utc};
+
+use crate::{
+    entities::{auth_throttles, lockout_events, users},
+    errors::AppError,
+    config::Config,
+};
+
+/// Throttled actions
+pub const ACTION_LOGIN: &str = "login";
+pub const ACTION_FORGOT_PASSWORD: &str = "forgot-password";
+pub const ACTION_FORGOT_REGISTRATION_CODE: &str = "forgot-registration-code";
+pub const ACTION_RESET_REGISTRATION_CODE: &str = "reset-registration-code";
+pub const ACTION_TWO_FACTOR: &str = "two-factor";
+pub const ACTION_CHANGE_PASSWORD: &str = "change-password";
+
+/// What a throttle counts failures of: the account named in the request or the client address
+pub const SCOPE_ACCOUNT: &str = "account";
+pub const SCOPE_IP: &str = "ip";
+
+/// Many people can share one address (a clinic behind one router), so it gets more room
+const IP_FAILURE_FACTOR: i32 = 4;
+
+/// Failed attempts are counted per account and per client IP. Every failure doubles the
+/// wait before the next attempt (1s, 2s, 4s, ..., at most `lockout`); `max_failures` in a row lock the
+/// account or address out for `lockout`, which is recorded in `lockout_events`.
+#[derive(Clone)]
+pub struct ThrottleService {
+    pub db: DatabaseConnection,
+    max_failures: i32,
+    lockout: Duration,
+}
+
+/// Who an attempt came from
+#[derive(Clone, Debug, Default)]
+pub struct Attempt {
+    /// Username or email the request names
+    pub account: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LockoutQuery {
+    /// Include expired and lifted lockouts
+    pub all: Option<bool>,
+}
+
+impl Attempt {
+    pub fn new(account: Option<&str>, ip_address: Option<String>) -> Self {
+        Attempt {
+            account: account.map(|account| account.trim().to_lowercase()),
+            ip_address,
+        }
+    }
+
+    fn subjects(&self) -> Vec<(&'static str, &str)> {
+        let mut subjects = Vec::new();
+        if let Some(account) = self.account.as_deref() {
+            subjects.push((SCOPE_ACCOUNT, account));
+        }
+        if let Some(ip) = self.ip_address.as_deref() {
+            subjects.push((SCOPE_IP, ip));
+        }
+        subjects
+    }
+}
+
+/// Wait after the `failures`-th failure in a row; never longer than a lockout
+fn backoff(failures: i32, lockout: Duration) -> Duration {
+    Duration::seconds(1 << (failures - 1).clamp(0, 20)).min(lockout)
+}
+
+/// Failures in a row once another one happens at `now`. Failures further apart than a
+/// lockout don't add up, but the count survives as long as its backoff is still running.
+fn failures_in_a_row(previous: Option<&auth_throttles::Model>, lockout: Duration, now: NaiveDateTime) -> i32 {
+    match previous {
+        Some(throttle) if throttle.last_failure_at + lockout.max(backoff(throttle.failures, lockout)) > now => {
+            throttle.failures + 1
+        }
+        _ => 1,
+    }
+}
+
+impl ThrottleService {
+    pub fn new(db: DatabaseConnection, config: &Config) -> Self {
+        Self {
+            db,
+            max_failures: config.auth_max_failures,
+            lockout: Duration::minutes(config.auth_lockout_minutes),
+        }
+    }
+
+    fn limit(&self, scope: &str) -> i32 {
+        if scope == SCOPE_IP {
+            self.max_failures * IP_FAILURE_FACTOR
+        } else {
+            self.max_failures
+        }
+    }
+
+    async fn find(&self, action: &str, scope: &str, subject: &str) -> Result<Option<auth_throttles::Model>, AppError> {
+        Ok(auth_throttles::Entity::find()
+            .filter(auth_throttles::Column::Action.eq(action))
+            .filter(auth_throttles::Column::Scope.eq(scope))
+            .filter(auth_throttles::Column::Subject.eq(subject))
+            .one(&self.db)
+            .await?)
+    }
+
+    /// Fail with `429 Too Many Requests` while the account or address is locked out or backing off
+    pub async fn check(&self, action: &str, attempt: &Attempt) -> Result<(), AppError> {
+        let now = Utc::now().naive_utc();
+
+        for (scope, subject) in attempt.subjects() {
+            let Some(throttle) = self.find(action, scope, subject).await? else {
+                continue;
+            };
+
+            if let Some(locked_until) = throttle.locked_until.filter(|until| *until > now) {
+                return Err(AppError::TooManyRequests(format!(
+                    "Too many failed attempts, locked until {} UTC",
+                    locked_until.format("%Y-%m-%d %H:%M:%S")
+                )));
+            }
+
+            if throttle.failures > 0 {
+                let next_attempt = throttle.last_failure_at + backoff(throttle.failures, self.lockout);
+                if next_attempt > now {
+                    return Err(AppError::TooManyRequests(format!(
+                        "Too many failed attempts, try again in {} seconds",
+                        (next_attempt - now).num_seconds().max(1)
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Count a failed attempt; locks the account or address out once it reaches the limit
+    pub async fn record_failure(&self, action: &str, attempt: &Attempt, user_id: Option<i32>) -> Result<(), AppError> {
+        let now = Utc::now().naive_utc();
+
+        for (scope, subject) in attempt.subjects() {
+            let existing = self.find(action, scope, subject).await?;
+
+            let failures = failures_in_a_row(existing.as_ref(), self.lockout, now);
+            let locks = failures >= self.limit(scope);
+            let locked_until = locks.then(|| now + self.lockout);
+
+            match existing {
+                Some(throttle) => {
+                    let mut active: auth_throttles::ActiveModel = throttle.into();
+                    active.failures = Set(if locks { 0 } else { failures });
+                    active.last_failure_at = Set(now);
+                    active.locked_until = Set(locked_until);
+                    active.update(&self.db).await?;
+                }
+                None => {
+                    auth_throttles::ActiveModel {
+                        action: Set(action.to_string()),
+                        scope: Set(scope.to_string()),
+                        subject: Set(subject.to_string()),
+                        failures: Set(if locks { 0 } else { failures }),
+                        last_failure_at: Set(now),
+                        locked_until: Set(locked_until),
+                        ..Default::default()
+                    }
+                    .insert(&self.db)
+                    .await?;
+                }
+            }
+
+            if let Some(locked_until) = locked_until {
+                lockout_events::ActiveModel {
+                    action: Set(action.to_string()),
+                    scope: Set(scope.to_string()),
+                    subject: Set(subject.to_string()),
+                    user_id: Set(if scope == SCOPE_ACCOUNT { user_id } else { None }),
+                    ip_address: Set(attempt.ip_address.clone()),
+                    failures: Set(failures),
+                    locked_at: Set(now),
+                    locked_until: Set(locked_until),
+                    ..Default::default()
+                }
+                .insert(&self.db)
+                .await?;
+
+                tracing::warn!("Locked out {} '{}' from {} until {}", scope, subject, action, locked_until);
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget the failures of an account after it succeeded; the address keeps its count
+    pub async fn record_success(&self, action: &str, attempt: &Attempt) -> Result<(), AppError> {
+        if let Some(account) = attempt.account.as_deref() {
+            auth_throttles::Entity::delete_many()
+                .filter(auth_throttles::Column::Action.eq(action))
+                .filter(auth_throttles::Column::Scope.eq(SCOPE_ACCOUNT))
+                .filter(auth_throttles::Column::Subject.eq(account))
+                .exec(&self.db)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Lockouts still in force, newest first; every recorded lockout with `include_all`
+    pub async fn get_lockouts(&self, include_all: bool) -> Result<Vec<lockout_events::Model>, AppError> {
+        let mut query = lockout_events::Entity::find();
+        if !include_all {
+            query = query
+                .filter(lockout_events::Column::UnlockedAt.is_null())
+                .filter(lockout_events::Column::LockedUntil.gt(Utc::now().naive_utc()));
+        }
+
+        Ok(query
+            .order_by_desc(lockout_events::Column::LockedAt)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Lift one lockout before it runs out
+    pub async fn unlock(&self, event_id: i32, unlocked_by: i32) -> Result<(), AppError> {
+        let event = lockout_events::Entity::find_by_id(event_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Lockout not found".into()))?;
+
+        auth_throttles::Entity::delete_many()
+            .filter(auth_throttles::Column::Action.eq(event.action.clone()))
+            .filter(auth_throttles::Column::Scope.eq(event.scope.clone()))
+            .filter(auth_throttles::Column::Subject.eq(event.subject.clone()))
+            .exec(&self.db)
+            .await?;
+
+        self.mark_unlocked(
+            Condition::all()
+                .add(lockout_events::Column::Action.eq(event.action))
+                .add(lockout_events::Column::Scope.eq(event.scope))
+                .add(lockout_events::Column::Subject.eq(event.subject)),
+            unlocked_by,
+        )
+        .await
+    }
+
+    /// Lift every lockout and clear the failures of a user's account, under its username or email
+    pub async fn unlock_user(&self, user_id: i32, unlocked_by: i32) -> Result<(), AppError> {
+        let user = users::Entity::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+        let mut subjects = vec![user.username.to_lowercase()];
+        subjects.extend(user.email);
+
+        auth_throttles::Entity::delete_many()
+            .filter(auth_throttles::Column::Scope.eq(SCOPE_ACCOUNT))
+            .filter(auth_throttles::Column::Subject.is_in(subjects.clone()))
+            .exec(&self.db)
+            .await?;
+
+        self.mark_unlocked(
+            Condition::all().add(lockout_events::Column::Scope.eq(SCOPE_ACCOUNT)).add(
+                Condition::any()
+                    .add(lockout_events::Column::UserId.eq(user_id))
+                    .add(lockout_events::Column::Subject.is_in(subjects)),
+            ),
+            unlocked_by,
+        )
+        .await
+    }
+
+    async fn mark_unlocked(&self, events: Condition, unlocked_by: i32) -> Result<(), AppError> {
+        let now = Utc::now().naive_utc();
+        lockout_events::Entity::update_many()
+            .col_expr(lockout_events::Column::UnlockedAt, Expr::value(now))
+            .col_expr(lockout_events::Column::UnlockedBy, Expr::value(unlocked_by))
+            .filter(events)
+            .filter(lockout_events::Column::UnlockedAt.is_null())
+            .filter(lockout_events::Column::LockedUntil.gt(now))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle(failures: i32, last_failure_at: NaiveDateTime) -> auth_throttles::Model {
+        auth_throttles::Model {
+            throttle_id: 1,
+            action: ACTION_LOGIN.into(),
+            scope: SCOPE_ACCOUNT.into(),
+            subject: "alice".into(),
+            failures,
+            last_failure_at,
+            locked_until: None,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_with_every_failure() {
+        let lockout = Duration::minutes(15);
+        assert_eq!(backoff(1, lockout), Duration::seconds(1));
+        assert_eq!(backoff(2, lockout), Duration::seconds(2));
+        assert_eq!(backoff(5, lockout), Duration::seconds(16));
+        assert_eq!(backoff(0, lockout), Duration::seconds(1));
+    }
+
+    #[test]
+    fn backoff_never_exceeds_the_lockout() {
+        let lockout = Duration::minutes(15);
+        assert_eq!(backoff(11, lockout), lockout);
+        assert_eq!(backoff(40, lockout), lockout);
+        assert_eq!(backoff(i32::MAX, lockout), lockout);
+    }
+
+    #[test]
+    fn first_failure_starts_the_count() {
+        let now = Utc::now().naive_utc();
+        assert_eq!(failures_in_a_row(None, Duration::minutes(15), now), 1);
+    }
+
+    #[test]
+    fn failures_within_a_lockout_add_up() {
+        let now = Utc::now().naive_utc();
+        let previous = throttle(3, now - Duration::minutes(14));
+        assert_eq!(failures_in_a_row(Some(&previous), Duration::minutes(15), now), 4);
+    }
+
+    #[test]
+    fn failures_further_apart_than_a_lockout_start_over() {
+        let now = Utc::now().naive_utc();
+        let previous = throttle(3, now - Duration::minutes(15));
+        assert_eq!(failures_in_a_row(Some(&previous), Duration::minutes(15), now), 1);
+    }
+
+}
@@ -0,0 +1,310 @@
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, Condition,
+};
+use sea_orm::sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use rand::{Rng, thread_rng};
+use chrono::{Duration, Utc};
+
+use crate::{
+    entities::{recovery_codes, role_policies, users},
+    errors::AppError,
+    config::Config,
+    permissions::Role,
+    services::user_admin::is_active,
+    tokens, totp,
+};
+
+/// Recovery codes handed out per enrollment
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// What a challenge token lets the client do next
+pub const CHALLENGE_VERIFY: &str = "verify";
+pub const CHALLENGE_ENROLL: &str = "enroll";
+
+/// Two-factor authentication with authenticator app codes (TOTP). A user enrolls by
+/// scanning a secret and confirming one code, and gets one-time recovery codes in case
+/// the device is lost. Admins can require two-factor authentication per role.
+#[derive(Clone)]
+pub struct TwoFactorService {
+    pub db: DatabaseConnection,
+    jwt_secret: String,
+    issuer: String,
+    challenge_ttl: Duration,
+}
+
+/// Short-lived proof that a password was right, traded for a session once the second
+/// factor is in. It has no session, so it is never accepted as an access token.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChallengeClaims {
+    user_id: i32,
+    purpose: String,
+    exp: usize,
+}
+
+#[derive(Deserialize)]
+pub struct CodeRequest {
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub struct SetTwoFactorPolicyRequest {
+    pub required: bool,
+}
+
+/// A secret to add to an authenticator app; enrollment finishes once a code from it is confirmed
+#[derive(Serialize)]
+pub struct EnrollmentResponse {
+    pub secret: String,
+    /// `otpauth://` URI to render as a QR code
+    pub otpauth_uri: String,
+}
+
+/// One-time recovery codes, shown only this once
+#[derive(Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Recovery codes are typed in by hand, so dashes and case don't matter
+fn hash_recovery_code(code: &str) -> String {
+    tokens::hash(&code.trim().replace('-', "").to_lowercase())
+}
+
+fn generate_recovery_code() -> String {
+    let bytes: [u8; 5] = thread_rng().r#gen();
+    let code = hex::encode(bytes);
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+impl TwoFactorService {
+    pub fn new(db: DatabaseConnection, config: &Config) -> Self {
+        Self {
+            db,
+            jwt_secret: config.jwt_secret.clone(),
+            issuer: config.totp_issuer.clone(),
+            challenge_ttl: Duration::minutes(5),
+        }
+    }
+
+    pub fn challenge_ttl(&self) -> Duration {
+        self.challenge_ttl
+    }
+
+    async fn find_user(&self, user_id: i32) -> Result<users::Model, AppError> {
+        users::Entity::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))
+    }
+
+    /// A challenge token for a user whose password checked out
+    pub fn issue_challenge(&self, user_id: i32, purpose: &str) -> Result<String, AppError> {
+        let expiration = Utc::now()
+            .checked_add_signed(self.challenge_ttl)
+            .ok_or(AppError::InternalError)?
+            .timestamp() as usize;
+
+        let claims = ChallengeClaims { user_id, purpose: purpose.to_string(), exp: expiration };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.jwt_secret.as_bytes()),
+        )
+        .map_err(|_| AppError::InternalError)
+    }
+
+    /// The user a challenge token was issued to, if it is valid for `purpose`
+    pub async fn check_challenge(&self, token: &str, purpose: &str) -> Result<users::Model, AppError> {
+        let decoding_key = DecodingKey::from_secret(self.jwt_secret.as_bytes());
+        let claims = decode::<ChallengeClaims>(token.trim(), &decoding_key, &Validation::default())
+            .map_err(|_| AppError::Unauthorized)?
+            .claims;
+        if claims.purpose != purpose {
+            return Err(AppError::Unauthorized);
+        }
+
+        users::Entity::find_by_id(claims.user_id)
+            .one(&self.db)
+            .await?
+            .filter(is_active)
+            .ok_or(AppError::Unauthorized)
+    }
+
+    /// Whether users with `role` must use two-factor authentication
+    pub async fn is_required(&self, role: Role) -> Result<bool, AppError> {
+        let policy = role_policies::Entity::find_by_id(role.as_str().to_string())
+            .one(&self.db)
+            .await?;
+        Ok(policy.is_some_and(|policy| policy.require_two_factor))
+    }
+
+    /// Require two-factor authentication for a role, or stop requiring it.
+    /// Users of the role who haven't enrolled have to at their next sign-in.
+    pub async fn set_required(&self, role: Role, required: bool) -> Result<(), AppError> {
+        let now = Utc::now().naive_utc();
+        let existing = role_policies::Entity::find_by_id(role.as_str().to_string())
+            .one(&self.db)
+            .await?;
+
+        match existing {
+            Some(policy) => {
+                let mut active: role_policies::ActiveModel = policy.into();
+                active.require_two_factor = Set(required);
+                active.updated_at = Set(now);
+                active.update(&self.db).await?;
+            }
+            None => {
+                role_policies::ActiveModel {
+                    role: Set(role.as_str().to_string()),
+                    require_two_factor: Set(required),
+                    updated_at: Set(now),
+                }
+                .insert(&self.db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Start enrolling with a new secret; starting again replaces an unconfirmed one
+    pub async fn begin_enrollment(&self, user_id: i32) -> Result<EnrollmentResponse, AppError> {
+        let user = self.find_user(user_id).await?;
+        if user.totp_confirmed_at.is_some() {
+            return Err(AppError::BadRequest("Two-factor authentication is already enabled".into()));
+        }
+
+        let secret = totp::generate_secret();
+        let otpauth_uri = totp::otpauth_uri(&self.issuer, &user.username, &secret);
+
+        let mut active: users::ActiveModel = user.into();
+        active.totp_secret = Set(Some(secret.clone()));
+        active.totp_last_step = Set(None);
+        active.update(&self.db).await?;
+
+        Ok(EnrollmentResponse { secret, otpauth_uri })
+    }
+
+    /// Finish enrolling with a code from the new secret; returns the recovery codes
+    pub async fn confirm_enrollment(&self, user_id: i32, code: &str) -> Result<RecoveryCodesResponse, AppError> {
+        let user = self.find_user(user_id).await?;
+        if user.totp_confirmed_at.is_some() {
+            return Err(AppError::BadRequest("Two-factor authentication is already enabled".into()));
+        }
+        let secret = user
+            .totp_secret
+            .clone()
+            .ok_or_else(|| AppError::BadRequest("Start enrolling before confirming a code".into()))?;
+
+        let step = totp::verify(&secret, code, Utc::now().timestamp())
+            .ok_or_else(|| AppError::BadRequest("Invalid two-factor code".into()))?;
+
+        let mut active: users::ActiveModel = user.into();
+        active.totp_confirmed_at = Set(Some(Utc::now().naive_utc()));
+        active.totp_last_step = Set(Some(step));
+        active.update(&self.db).await?;
+
+        self.replace_recovery_codes(user_id).await
+    }
+
+    /// Check a code from the authenticator app or an unused recovery code.
+    /// Each app code works once, and a recovery code is used up.
+    pub async fn verify(&self, user: &users::Model, code: &str) -> Result<bool, AppError> {
+        let (Some(secret), Some(_)) = (&user.totp_secret, user.totp_confirmed_at) else {
+            return Ok(false);
+        };
+
+        if let Some(step) = totp::verify(secret, code, Utc::now().timestamp()) {
+            // Only a step later than the last one used counts, so an overheard code can't be replayed
+            let result = users::Entity::update_many()
+                .col_expr(users::Column::TotpLastStep, Expr::value(step))
+                .filter(users::Column::UserId.eq(user.user_id))
+                .filter(
+                    Condition::any()
+                        .add(users::Column::TotpLastStep.is_null())
+                        .add(users::Column::TotpLastStep.lt(step)),
+                )
+                .exec(&self.db)
+                .await?;
+            return Ok(result.rows_affected > 0);
+        }
+
+        let result = recovery_codes::Entity::update_many()
+            .col_expr(recovery_codes::Column::UsedAt, Expr::value(Utc::now().naive_utc()))
+            .filter(recovery_codes::Column::UserId.eq(user.user_id))
+            .filter(recovery_codes::Column::CodeHash.eq(hash_recovery_code(code)))
+            .filter(recovery_codes::Column::UsedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn verify_enabled(&self, user_id: i32, code: &str) -> Result<users::Model, AppError> {
+        let user = self.find_user(user_id).await?;
+        if user.totp_confirmed_at.is_none() {
+            return Err(AppError::BadRequest("Two-factor authentication is not enabled".into()));
+        }
+        if !self.verify(&user, code).await? {
+            return Err(AppError::BadRequest("Invalid two-factor code".into()));
+        }
+        Ok(user)
+    }
+
+    /// Turn two-factor authentication off with a current code; not allowed when the role requires it
+    pub async fn disable(&self, user_id: i32, code: &str) -> Result<(), AppError> {
+        let user = self.verify_enabled(user_id, code).await?;
+        if self.is_required(Role::from_column(&user.role)).await? {
+            return Err(AppError::Forbidden("Your role requires two-factor authentication".into()));
+        }
+        self.clear(user).await
+    }
+
+    /// Remove a user's two-factor enrollment, for someone who lost both device and recovery codes.
+    /// If their role requires it they enroll again at the next sign-in.
+    pub async fn reset(&self, user_id: i32) -> Result<(), AppError> {
+        let user = self.find_user(user_id).await?;
+        self.clear(user).await
+    }
+
+    async fn clear(&self, user: users::Model) -> Result<(), AppError> {
+        let user_id = user.user_id;
+        let mut active: users::ActiveModel = user.into();
+        active.totp_secret = Set(None);
+        active.totp_confirmed_at = Set(None);
+        active.totp_last_step = Set(None);
+        active.update(&self.db).await?;
+
+        recovery_codes::Entity::delete_many()
+            .filter(recovery_codes::Column::UserId.eq(user_id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// New recovery codes for a current code; the old ones stop working
+    pub async fn regenerate_recovery_codes(&self, user_id: i32, code: &str) -> Result<RecoveryCodesResponse, AppError> {
+        self.verify_enabled(user_id, code).await?;
+        self.replace_recovery_codes(user_id).await
+    }
+
+    async fn replace_recovery_codes(&self, user_id: i32) -> Result<RecoveryCodesResponse, AppError> {
+        let now = Utc::now().naive_utc();
+        recovery_codes::Entity::delete_many()
+            .filter(recovery_codes::Column::UserId.eq(user_id))
+            .exec(&self.db)
+            .await?;
+
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+        let models = codes.iter().map(|code| recovery_codes::ActiveModel {
+            user_id: Set(user_id),
+            code_hash: Set(hash_recovery_code(code)),
+            created_at: Set(now),
+            used_at: Set(None),
+            ..Default::default()
+        });
+        recovery_codes::Entity::insert_many(models).exec(&self.db).await?;
+
+        Ok(RecoveryCodesResponse { recovery_codes: codes })
+    }
+}
@@ -0,0 +1,200 @@
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, QueryOrder, PaginatorTrait,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use chrono::{NaiveDateTime, Utc};
+
+use crate::{
+    entities::users,
+    errors::AppError,
+    config::Config,
+    mail::Mailer,
+    permissions::Role,
+    services::api_keys,
+    services::sessions::SessionsService,
+    services::users::{hash_password, UserResponse, UserService},
+    tokens,
+};
+
+/// Admin tasks over other people's accounts. Accounts are deactivated or soft-deleted,
+/// never removed, so the orders and expenses they created or modified still name them.
+#[derive(Clone)]
+pub struct UserAdminService {
+    pub db: DatabaseConnection,
+    sessions: SessionsService,
+    users: UserService,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserListQuery {
+    /// Include soft-deleted users
+    pub include_deleted: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct AdminUserResponse {
+    #[serde(flatten)]
+    pub user: UserResponse,
+    /// active, deactivated or deleted
+    pub status: &'static str,
+    pub last_login_at: Option<NaiveDateTime>,
+    pub deactivated_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+/// Where the reset token went: to the user's email, or to the admin to pass on when the
+/// user has no email or the email could not be sent, in which case it is shown only this once
+#[derive(Serialize)]
+pub struct ForcedResetResponse {
+    pub emailed_to: Option<String>,
+    pub reset_token: Option<String>,
+    /// The user has an email but sending to it failed
+    pub email_failed: bool,
+}
+
+impl From<users::Model> for AdminUserResponse {
+    fn from(user: users::Model) -> Self {
+        let status = if user.deleted_at.is_some() {
+            "deleted"
+        } else if user.deactivated_at.is_some() {
+            "deactivated"
+        } else {
+            "active"
+        };
+
+        AdminUserResponse {
+            status,
+            last_login_at: user.last_login_at,
+            deactivated_at: user.deactivated_at,
+            deleted_at: user.deleted_at,
+            user: user.into(),
+        }
+    }
+}
+
+/// Whether a user may sign in and use their tokens
+pub fn is_active(user: &users::Model) -> bool {
+    user.deactivated_at.is_none() && user.deleted_at.is_none()
+}
+
+impl UserAdminService {
+    pub fn new(db: DatabaseConnection, config: &Config, mailer: Arc<dyn Mailer>) -> Self {
+        Self {
+            sessions: SessionsService::new(db.clone(), config),
+            users: UserService::new(db.clone(), config, mailer),
+            db,
+        }
+    }
+
+    async fn find_user(&self, user_id: i32) -> Result<users::Model, AppError> {
+        users::Entity::find_by_id(user_id)
+            .filter(api_keys::service_accounts().not())
+            .one(&self.db)
+            .await?
+            .filter(|user| user.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound("User not found".into()))
+    }
+
+    /// An admin can't lock themselves out, and someone has to stay admin
+    async fn check_can_disable(&self, user: &users::Model, acting_user: i32) -> Result<(), AppError> {
+        if user.user_id == acting_user {
+            return Err(AppError::BadRequest("You cannot deactivate or delete your own account".into()));
+        }
+        if Role::from_column(&user.role) == Role::Admin && is_active(user) {
+            let admins = users::Entity::find()
+                .filter(users::Column::Role.eq(Role::Admin.as_str()))
+                .filter(users::Column::DeactivatedAt.is_null())
+                .filter(users::Column::DeletedAt.is_null())
+                .count(&self.db)
+                .await?;
+            if admins <= 1 {
+                return Err(AppError::BadRequest("The last active admin cannot be deactivated or deleted".into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Users by username, without the service accounts behind API keys
+    pub async fn get_users(&self, include_deleted: bool) -> Result<Vec<AdminUserResponse>, AppError> {
+        let mut query = users::Entity::find().filter(api_keys::service_accounts().not());
+        if !include_deleted {
+            query = query.filter(users::Column::DeletedAt.is_null());
+        }
+
+        let users = query.order_by_asc(users::Column::Username).all(&self.db).await?;
+        Ok(users.into_iter().map(AdminUserResponse::from).collect())
+    }
+
+    pub async fn get_user(&self, user_id: i32) -> Result<AdminUserResponse, AppError> {
+        Ok(self.find_user(user_id).await?.into())
+    }
+
+    /// Stop a user from signing in; their sessions end at once
+    pub async fn deactivate(&self, user_id: i32, acting_user: i32) -> Result<AdminUserResponse, AppError> {
+        let user = self.find_user(user_id).await?;
+        if user.deactivated_at.is_some() {
+            return Ok(user.into());
+        }
+        self.check_can_disable(&user, acting_user).await?;
+
+        let mut active: users::ActiveModel = user.into();
+        active.deactivated_at = Set(Some(Utc::now().naive_utc()));
+        let user = active.update(&self.db).await?;
+
+        self.sessions.revoke_all(user_id).await?;
+        Ok(user.into())
+    }
+
+    pub async fn reactivate(&self, user_id: i32) -> Result<AdminUserResponse, AppError> {
+        let user = self.find_user(user_id).await?;
+        if user.deactivated_at.is_none() {
+            return Ok(user.into());
+        }
+
+        let mut active: users::ActiveModel = user.into();
+        active.deactivated_at = Set(None);
+        Ok(active.update(&self.db).await?.into())
+    }
+
+    /// Make a user choose a new password: the current one stops working, they are signed
+    /// out everywhere, and they get a reset token by email (or through the admin). The token
+    /// is issued first, so a failed email hands it to the admin instead of locking the user out.
+    pub async fn force_password_reset(&self, user_id: i32) -> Result<ForcedResetResponse, AppError> {
+        let user = self.find_user(user_id).await?;
+        let reset_token = self.users.issue_password_reset(user_id).await?;
+
+        let txn = self.db.begin().await?;
+        let mut active: users::ActiveModel = user.into();
+        active.password_hash = Set(hash_password(&tokens::generate())?);
+        let user = active.update(&txn).await?;
+        SessionsService::revoke_all_on(&txn, user_id).await?;
+        txn.commit().await?;
+
+        if user.email.is_none() {
+            return Ok(ForcedResetResponse { emailed_to: None, reset_token: Some(reset_token), email_failed: false });
+        }
+        match self.users.send_password_reset(&user, &reset_token).await {
+            Ok(()) => Ok(ForcedResetResponse { emailed_to: user.email, reset_token: None, email_failed: false }),
+            Err(e) => {
+                tracing::error!("Failed to email the password reset for user {}: {}", user_id, e);
+                Ok(ForcedResetResponse { emailed_to: None, reset_token: Some(reset_token), email_failed: true })
+            }
+        }
+    }
+
+    /// Remove a user from the system while keeping the records that mention them
+    pub async fn delete_user(&self, user_id: i32, acting_user: i32) -> Result<(), AppError> {
+        let user = self.find_user(user_id).await?;
+        self.check_can_disable(&user, acting_user).await?;
+
+        let now = Utc::now().naive_utc();
+        let mut active: users::ActiveModel = user.into();
+        active.deleted_at = Set(Some(now));
+        active.update(&self.db).await?;
+
+        self.sessions.revoke_all(user_id).await?;
+        Ok(())
+    }
+}
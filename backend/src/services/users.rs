@@ -1,29 +1,54 @@
-use sea_orm::{DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter};
+use sea_orm::{
+    DatabaseConnection, EntityTrait, ActiveModelTrait, Set, ColumnTrait, QueryFilter, PaginatorTrait, TransactionTrait,
+};
+use sea_orm::sea_query::Expr;
 use serde::{Deserialize, Serialize};
 use rand::{Rng, thread_rng};
 use argon2::{Argon2, PasswordHasher, PasswordVerifier, password_hash::{SaltString, PasswordHash}};
-use jsonwebtoken::{encode, EncodingKey, Header};
-use chrono::{Utc, Duration};
+use std::sync::Arc;
+use chrono::{Duration, Utc};
 
-use crate::Config as ArgonConfig;
 use crate::{
-    entities::{users, registration_codes},
+    entities::{users, password_resets},
     errors::AppError,
     config::Config,
-    middleware::auth::Claims,
+    permissions::Role,
+    password_policy::PasswordPolicy,
+    services::branches::BranchesService,
+    services::sessions::{ClientInfo, SessionsService, TokenPair},
+    services::invitations::{normalize_email, InvitationsService},
+    services::registration::check_registration_code,
+    services::user_admin::is_active,
+    services::throttle::{
+        Attempt, ThrottleService, ACTION_CHANGE_PASSWORD, ACTION_FORGOT_PASSWORD, ACTION_LOGIN, ACTION_TWO_FACTOR,
+    },
+    services::two_factor::{
+        EnrollmentResponse, TwoFactorService, CHALLENGE_ENROLL, CHALLENGE_VERIFY,
+    },
+    mail::{Mailer, Template},
+    tokens,
 };
 
 #[derive(Clone)]
 pub struct UserService {
     pub db: DatabaseConnection,
-    pub jwt_secret: String,
+    pub sessions: SessionsService,
+    pub invitations: InvitationsService,
+    pub throttle: ThrottleService,
+    pub two_factor: TwoFactorService,
+    password_policy: PasswordPolicy,
+    mailer: Arc<dyn Mailer>,
+    reset_ttl: Duration,
 }
 
 #[derive(Deserialize)]
 pub struct RegisterRequest {
     pub username: String,
     pub password: String,
-    pub registration_code: String,
+    /// Code of the invitation being accepted
+    pub invitation_code: Option<String>,
+    /// Only for the very first account, which becomes the admin
+    pub registration_code: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -34,39 +59,131 @@ pub struct LoginRequest {
 
 #[derive(Deserialize)]
 pub struct ForgotPasswordRequest {
-    pub username: String,
-    pub registration_code: String,
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
     pub new_password: String,
 }
 
+/// Fields left out stay as they are; an empty string clears them
+#[derive(Deserialize)]
+pub struct UpdateProfileRequest {
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChallengeRequest {
+    pub challenge_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct TwoFactorLoginRequest {
+    pub challenge_token: String,
+    /// Code from the authenticator app, or a recovery code
+    pub code: String,
+}
+
 #[derive(Serialize)]
 pub struct AuthResponse {
-    pub token: String,
+    #[serde(flatten)]
+    pub tokens: TokenPair,
     pub user_id: i32,
     pub username: String,
 }
 
+/// The password was right but a second factor is needed before a session starts
+#[derive(Serialize)]
+pub struct TwoFactorChallenge {
+    /// `verify` to send a code to `/login/2fa`, `enroll` to set up an app first
+    pub two_factor: &'static str,
+    pub challenge_token: String,
+    /// Seconds until `challenge_token` expires
+    pub expires_in: i64,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    TwoFactor(TwoFactorChallenge),
+}
+
+/// Session of a user who just enrolled, with the recovery codes to keep
+#[derive(Serialize)]
+pub struct EnrolledResponse {
+    #[serde(flatten)]
+    pub auth: AuthResponse,
+    pub recovery_codes: Vec<String>,
+}
+
 #[derive(Serialize)]
 pub struct UserResponse {
     pub user_id: i32,
     pub username: String,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    pub role: String,
+    pub two_factor_enabled: bool,
 }
 
-impl UserService {
-    pub fn new(db: DatabaseConnection, jwt_secret: String) -> Self {
-        Self { db, jwt_secret }
+impl From<users::Model> for UserResponse {
+    fn from(user: users::Model) -> Self {
+        UserResponse {
+            two_factor_enabled: user.totp_confirmed_at.is_some(),
+            user_id: user.user_id,
+            username: user.username,
+            display_name: user.display_name,
+            email: user.email,
+            role: user.role,
+        }
     }
+}
 
-    pub async fn register_user(&self, req: RegisterRequest) -> Result<AuthResponse, AppError> {
-        // Check registration code validity
-        let reg_code = registration_codes::Entity::find()
-            .one(&self.db)
-            .await?
-            .ok_or(AppError::BadRequest("Invalid registration code".into()))?;
+/// Argon2 hash of a password with a fresh salt
+pub(crate) fn hash_password(password: &str) -> Result<String, AppError> {
+    let mut rng = thread_rng();
+    let salt_bytes: [u8; 16] = rng.r#gen();
+    let salt = SaltString::encode_b64(&salt_bytes).map_err(|_| AppError::InternalError)?;
+
+    let argon2 = Argon2::default();
+    Ok(argon2
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|_| AppError::InternalError)?
+        .to_string())
+}
 
-        if reg_code.code_hash != req.registration_code {
-            return Err(AppError::BadRequest("Invalid registration code".into()));
+impl UserService {
+    pub fn new(db: DatabaseConnection, config: &Config, mailer: Arc<dyn Mailer>) -> Self {
+        Self {
+            sessions: SessionsService::new(db.clone(), config),
+            invitations: InvitationsService::new(db.clone(), config, mailer.clone()),
+            throttle: ThrottleService::new(db.clone(), config),
+            two_factor: TwoFactorService::new(db.clone(), config),
+            password_policy: PasswordPolicy::from_config(config),
+            mailer,
+            reset_ttl: Duration::minutes(config.password_reset_minutes),
+            db,
         }
+    }
+
+    /// Register with an invitation, or with the registration code while there are no users yet
+    pub async fn register_user(&self, req: RegisterRequest, client: ClientInfo) -> Result<LoginResponse, AppError> {
+        let invitation = match &req.invitation_code {
+            Some(code) => Some(self.invitations.find_pending(code).await?),
+            None => None,
+        };
+
+        self.password_policy.check(&req.password, &req.username)?;
 
         // Check if username exists
         let existing = users::Entity::find()
@@ -78,108 +195,380 @@ impl UserService {
             return Err(AppError::BadRequest("Username already exists".into()));
         }
 
-        // Generate salt
-        let mut rng = thread_rng();
-        let salt_bytes: [u8; 16] = rng.r#gen();
-        let salt = SaltString::b64_encode(&salt_bytes).map_err(|_| AppError::InternalError)?;
+        let hash = hash_password(&req.password)?;
+
+        // Claiming the invitation, the bootstrap check and the account commit or fail together
+        let txn = self.db.begin().await?;
 
-        // Hash password
-        let argon2 = Argon2::default();
-        let hash = argon2
-            .hash_password(req.password.as_bytes(), &salt)
-            .map_err(|_| AppError::InternalError)?
-            .to_string();
+        // The first account sets everything else up
+        let (email, role) = match &invitation {
+            Some(invitation) => {
+                InvitationsService::claim(&txn, invitation.invitation_id).await?;
+                (Some(invitation.email.clone()), Role::from_column(&invitation.role))
+            }
+            None => {
+                let Some(code) = req.registration_code.as_deref() else {
+                    return Err(AppError::BadRequest("An invitation code is required".into()));
+                };
+                // Checking the code locks it, so a concurrent first registration counts this one
+                if !check_registration_code(&txn, code).await? {
+                    return Err(AppError::BadRequest("Invalid registration code".into()));
+                }
+                if users::Entity::find().count(&txn).await? > 0 {
+                    return Err(AppError::BadRequest("An invitation code is required".into()));
+                }
+                (None, Role::Admin)
+            }
+        };
 
         let new_user = users::ActiveModel {
             username: Set(req.username.clone()),
             password_hash: Set(hash),
+            role: Set(role.as_str().to_string()),
+            email: Set(email),
             ..Default::default()
         }
-        .insert(&self.db)
+        .insert(&txn)
         .await?;
 
-        // Generate JWT
-        let token = self.generate_jwt(new_user.user_id)?;
+        if let Some(invitation) = invitation {
+            InvitationsService::set_accepted_by(&txn, invitation.invitation_id, new_user.user_id).await?;
+        }
 
-        Ok(AuthResponse {
-            token,
-            user_id: new_user.user_id,
-            username: new_user.username,
-        })
+        BranchesService::assign_default_branch(&txn, new_user.user_id).await?;
+        txn.commit().await?;
+
+        // The invited role may require two-factor authentication from the start
+        self.sign_in(new_user, client).await
     }
 
-    pub async fn login_user(&self, req: LoginRequest) -> Result<AuthResponse, AppError> {
+    pub async fn login_user(&self, req: LoginRequest, client: ClientInfo) -> Result<LoginResponse, AppError> {
+        let attempt = Attempt::new(Some(&req.username), client.ip_address.clone());
+        self.throttle.check(ACTION_LOGIN, &attempt).await?;
+
         let user = users::Entity::find()
             .filter(users::Column::Username.eq(req.username.clone()))
+            .filter(users::Column::DeletedAt.is_null())
             .one(&self.db)
-            .await?
-            .ok_or(AppError::BadRequest("Invalid username or password".into()))?;
+            .await?;
 
-        let argon2 = Argon2::default();
-        let parsed_hash = PasswordHash::new(&user.password_hash).map_err(|_| AppError::InternalError)?;
-        let valid = argon2.verify_password(req.password.as_bytes(), &parsed_hash).is_ok();
+        let valid = match &user {
+            Some(user) => {
+                let argon2 = Argon2::default();
+                let parsed_hash = PasswordHash::new(&user.password_hash).map_err(|_| AppError::InternalError)?;
+                argon2.verify_password(req.password.as_bytes(), &parsed_hash).is_ok()
+            }
+            None => false,
+        };
+
+        let user = match user {
+            Some(user) if valid => user,
+            user => {
+                self.throttle
+                    .record_failure(ACTION_LOGIN, &attempt, user.map(|user| user.user_id))
+                    .await?;
+                return Err(AppError::BadRequest("Invalid username or password".into()));
+            }
+        };
 
-        if !valid {
-            return Err(AppError::BadRequest("Invalid username or password".into()));
+        self.throttle.record_success(ACTION_LOGIN, &attempt).await?;
+        if !is_active(&user) {
+            return Err(AppError::Forbidden("This account has been deactivated".into()));
         }
+        self.sign_in(user, client).await
+    }
 
-        let token = self.generate_jwt(user.user_id)?;
+    /// Start a session for a user whose password checked out, unless a second factor is still due
+    async fn sign_in(&self, user: users::Model, client: ClientInfo) -> Result<LoginResponse, AppError> {
+        let purpose = if user.totp_confirmed_at.is_some() {
+            Some(CHALLENGE_VERIFY)
+        } else if self.two_factor.is_required(Role::from_column(&user.role)).await? {
+            Some(CHALLENGE_ENROLL)
+        } else {
+            None
+        };
 
+        match purpose {
+            Some(purpose) => Ok(LoginResponse::TwoFactor(TwoFactorChallenge {
+                two_factor: purpose,
+                challenge_token: self.two_factor.issue_challenge(user.user_id, purpose)?,
+                expires_in: self.two_factor.challenge_ttl().num_seconds(),
+            })),
+            None => Ok(LoginResponse::Authenticated(self.start_session(user, client).await?)),
+        }
+    }
+
+    async fn start_session(&self, user: users::Model, client: ClientInfo) -> Result<AuthResponse, AppError> {
+        let tokens = self.sessions.start(user.user_id, client).await?;
+
+        users::Entity::update_many()
+            .col_expr(users::Column::LastLoginAt, Expr::value(Utc::now().naive_utc()))
+            .filter(users::Column::UserId.eq(user.user_id))
+            .exec(&self.db)
+            .await?;
         Ok(AuthResponse {
-            token,
+            tokens,
             user_id: user.user_id,
             username: user.username,
         })
     }
 
-    pub async fn forgot_password(&self, req: ForgotPasswordRequest) -> Result<(), AppError> {
-        // Find user by username
-        let mut user: users::ActiveModel = users::Entity::find()
-            .filter(users::Column::Username.eq(req.username.clone()))
+    /// Second sign-in step: a code from the authenticator app or a recovery code
+    pub async fn verify_two_factor(&self, req: TwoFactorLoginRequest, client: ClientInfo) -> Result<AuthResponse, AppError> {
+        let user = self.two_factor.check_challenge(&req.challenge_token, CHALLENGE_VERIFY).await?;
+
+        let attempt = Attempt::new(Some(&user.username), client.ip_address.clone());
+        self.throttle.check(ACTION_TWO_FACTOR, &attempt).await?;
+
+        if !self.two_factor.verify(&user, &req.code).await? {
+            self.throttle
+                .record_failure(ACTION_TWO_FACTOR, &attempt, Some(user.user_id))
+                .await?;
+            return Err(AppError::BadRequest("Invalid two-factor code".into()));
+        }
+
+        self.throttle.record_success(ACTION_TWO_FACTOR, &attempt).await?;
+        self.start_session(user, client).await
+    }
+
+    /// Sign-in step for a user whose role requires two-factor authentication but who hasn't set it up
+    pub async fn enroll_two_factor(&self, req: ChallengeRequest) -> Result<EnrollmentResponse, AppError> {
+        let user = self.two_factor.check_challenge(&req.challenge_token, CHALLENGE_ENROLL).await?;
+        self.two_factor.begin_enrollment(user.user_id).await
+    }
+
+    /// Finish enrolling during sign-in; starts the session and hands out the recovery codes
+    pub async fn confirm_two_factor(&self, req: TwoFactorLoginRequest, client: ClientInfo) -> Result<EnrolledResponse, AppError> {
+        let user = self.two_factor.check_challenge(&req.challenge_token, CHALLENGE_ENROLL).await?;
+
+        let attempt = Attempt::new(Some(&user.username), client.ip_address.clone());
+        self.throttle.check(ACTION_TWO_FACTOR, &attempt).await?;
+
+        let codes = match self.two_factor.confirm_enrollment(user.user_id, &req.code).await {
+            Ok(codes) => codes,
+            Err(AppError::BadRequest(message)) => {
+                self.throttle
+                    .record_failure(ACTION_TWO_FACTOR, &attempt, Some(user.user_id))
+                    .await?;
+                return Err(AppError::BadRequest(message));
+            }
+            Err(e) => return Err(e),
+        };
+
+        self.throttle.record_success(ACTION_TWO_FACTOR, &attempt).await?;
+        Ok(EnrolledResponse {
+            auth: self.start_session(user, client).await?,
+            recovery_codes: codes.recovery_codes,
+        })
+    }
+
+    /// Issue a password reset token for the account with this email.
+    /// Says nothing about whether the account exists.
+    pub async fn forgot_password(&self, req: ForgotPasswordRequest, client: ClientInfo) -> Result<(), AppError> {
+        let email = normalize_email(&req.email)?;
+
+        // Every request counts: the outcome is never shown, so none of them fails visibly
+        let attempt = Attempt::new(Some(&email), client.ip_address);
+        self.throttle.check(ACTION_FORGOT_PASSWORD, &attempt).await?;
+        self.throttle.record_failure(ACTION_FORGOT_PASSWORD, &attempt, None).await?;
+
+        let Some(user) = users::Entity::find()
+            .filter(users::Column::Email.eq(email))
+            .filter(users::Column::DeactivatedAt.is_null())
+            .filter(users::Column::DeletedAt.is_null())
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let token = self.issue_password_reset(user.user_id).await?;
+
+        // Sent in the background: a known address answers as fast as an unknown one,
+        // and a failed email can't tell them apart either
+        let service = self.clone();
+        actix_web::rt::spawn(async move {
+            if let Err(e) = service.send_password_reset(&user, &token).await {
+                tracing::error!("Failed to email the password reset for user {}: {}", user.user_id, e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Email a reset token to the user; fails when they have no email
+    pub async fn send_password_reset(&self, user: &users::Model, token: &str) -> Result<(), AppError> {
+        let email = user
+            .email
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest("The user has no email address".into()))?;
+        let minutes = self.reset_ttl.num_minutes().to_string();
+
+        self.mailer
+            .send_template(
+                Template::PasswordReset,
+                email,
+                &[("username", &user.username), ("token", token), ("minutes", &minutes)],
+            )
+            .await
+    }
+
+    /// A single-use reset token for a user; any older unused token stops working
+    pub async fn issue_password_reset(&self, user_id: i32) -> Result<String, AppError> {
+        let now = Utc::now().naive_utc();
+
+        password_resets::Entity::update_many()
+            .col_expr(password_resets::Column::UsedAt, Expr::value(now))
+            .filter(password_resets::Column::UserId.eq(user_id))
+            .filter(password_resets::Column::UsedAt.is_null())
+            .exec(&self.db)
+            .await?;
+
+        let token = tokens::generate();
+        password_resets::ActiveModel {
+            user_id: Set(user_id),
+            token_hash: Set(tokens::hash(&token)),
+            created_at: Set(now),
+            expires_at: Set(now + self.reset_ttl),
+            used_at: Set(None),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Set a new password with a reset token; signs the user out everywhere
+    pub async fn reset_password(&self, req: ResetPasswordRequest) -> Result<(), AppError> {
+        let now = Utc::now().naive_utc();
+        let invalid = || AppError::BadRequest("Invalid or expired reset token".into());
+
+        let reset = password_resets::Entity::find()
+            .filter(password_resets::Column::TokenHash.eq(tokens::hash(&req.token)))
             .one(&self.db)
             .await?
-            .ok_or(AppError::BadRequest("User not found".into()))?
-            .into();
+            .ok_or_else(invalid)?;
 
-        // Validate registration code
-        let reg_code = registration_codes::Entity::find()
+        if reset.used_at.is_some() || reset.expires_at <= now {
+            return Err(invalid());
+        }
+
+        let user = users::Entity::find_by_id(reset.user_id)
             .one(&self.db)
             .await?
-            .ok_or(AppError::BadRequest("Invalid registration code".into()))?;
+            .filter(is_active)
+            .ok_or_else(invalid)?;
+        // A rejected password leaves the token usable for another try
+        self.password_policy.check(&req.new_password, &user.username)?;
+
+        let password_hash = hash_password(&req.new_password)?;
+
+        // The token is used up, the password replaced and the sessions ended together,
+        // so a failure part way leaves the token usable and the old password unchanged
+        let txn = self.db.begin().await?;
 
-        if reg_code.code_hash != req.registration_code {
-            return Err(AppError::BadRequest("Invalid registration code".into()));
+        // Use the token up first so it can't be redeemed twice concurrently
+        let claimed = password_resets::Entity::update_many()
+            .col_expr(password_resets::Column::UsedAt, Expr::value(now))
+            .filter(password_resets::Column::ResetId.eq(reset.reset_id))
+            .filter(password_resets::Column::UsedAt.is_null())
+            .exec(&txn)
+            .await?;
+        if claimed.rows_affected == 0 {
+            return Err(invalid());
         }
 
-        // Hash new password
-        let mut rng = thread_rng();
-        let salt_bytes: [u8; 16] = rng.r#gen();
-        let salt = SaltString::b64_encode(&salt_bytes).map_err(|_| AppError::InternalError)?;
-        let argon2 = Argon2::default();
-        let hash = argon2
-            .hash_password(req.new_password.as_bytes(), &salt)
-            .map_err(|_| AppError::InternalError)?
-            .to_string();
+        let mut user: users::ActiveModel = user.into();
+        user.password_hash = Set(password_hash);
+        let user = user.update(&txn).await?;
 
-        // Update user password
-        user.password_hash = Set(hash);
-        user.update(&self.db).await?;
+        // Whoever knew the old password is signed out everywhere
+        SessionsService::revoke_all_on(&txn, user.user_id).await?;
 
+        txn.commit().await?;
         Ok(())
     }
 
-    fn generate_jwt(&self, user_id: i32) -> Result<String, AppError> {
-        let expiration = Utc::now()
-            .checked_add_signed(Duration::hours(24))
-            .ok_or(AppError::InternalError)?
-            .timestamp() as usize;
+    async fn find_user(&self, user_id: i32) -> Result<users::Model, AppError> {
+        users::Entity::find_by_id(user_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))
+    }
 
-        let claims = Claims { user_id, exp: expiration };
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(self.jwt_secret.as_bytes()),
-        )
-        .map_err(|_| AppError::InternalError)
+    pub async fn get_profile(&self, user_id: i32) -> Result<UserResponse, AppError> {
+        Ok(self.find_user(user_id).await?.into())
     }
-}
\ No newline at end of file
+
+    pub async fn update_profile(&self, user_id: i32, req: UpdateProfileRequest) -> Result<UserResponse, AppError> {
+        let user = self.find_user(user_id).await?;
+        let mut active: users::ActiveModel = user.into();
+
+        if let Some(display_name) = req.display_name {
+            let display_name = display_name.trim();
+            if display_name.chars().count() > 100 {
+                return Err(AppError::BadRequest("The display name can be at most 100 characters".into()));
+            }
+            active.display_name = Set((!display_name.is_empty()).then(|| display_name.to_string()));
+        }
+
+        if let Some(email) = req.email {
+            let email = match email.trim() {
+                "" => None,
+                email => Some(normalize_email(email)?),
+            };
+            if let Some(email) = &email {
+                let taken = users::Entity::find()
+                    .filter(users::Column::Email.eq(email.clone()))
+                    .filter(users::Column::UserId.ne(user_id))
+                    .one(&self.db)
+                    .await?;
+                if taken.is_some() {
+                    return Err(AppError::BadRequest(format!("{} belongs to another account", email)));
+                }
+            }
+            active.email = Set(email);
+        }
+
+        Ok(active.update(&self.db).await?.into())
+    }
+
+    /// Change the password with the current one; signs every other session out
+    pub async fn change_password(
+        &self,
+        user_id: i32,
+        session_id: i32,
+        req: ChangePasswordRequest,
+        client: ClientInfo,
+    ) -> Result<(), AppError> {
+        let user = self.find_user(user_id).await?;
+
+        // A stolen access token shouldn't be enough to guess the password
+        let attempt = Attempt::new(Some(&user.username), client.ip_address);
+        self.throttle.check(ACTION_CHANGE_PASSWORD, &attempt).await?;
+
+        let parsed_hash = PasswordHash::new(&user.password_hash).map_err(|_| AppError::InternalError)?;
+        if Argon2::default()
+            .verify_password(req.current_password.as_bytes(), &parsed_hash)
+            .is_err()
+        {
+            self.throttle
+                .record_failure(ACTION_CHANGE_PASSWORD, &attempt, Some(user_id))
+                .await?;
+            return Err(AppError::BadRequest("The current password is wrong".into()));
+        }
+        self.throttle.record_success(ACTION_CHANGE_PASSWORD, &attempt).await?;
+
+        self.password_policy.check(&req.new_password, &user.username)?;
+        if req.new_password == req.current_password {
+            return Err(AppError::BadRequest("The new password must differ from the current one".into()));
+        }
+
+        let mut active: users::ActiveModel = user.into();
+        active.password_hash = Set(hash_password(&req.new_password)?);
+        active.update(&self.db).await?;
+
+        self.sessions.revoke_others(user_id, session_id).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+//! Random secrets handed to a client once and stored only as a hash:
+//! refresh tokens, invitation codes and password reset tokens.
+
+use rand::{Rng, thread_rng};
+use sha2::{Digest, Sha256};
+
+/// 256 random bits, hex encoded
+pub fn generate() -> String {
+    let bytes: [u8; 32] = thread_rng().r#gen();
+    hex::encode(bytes)
+}
+
+/// SHA-256 of a token, hex encoded; the tokens are random enough not to need a salt
+pub fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.trim().as_bytes()))
+}